@@ -1,2 +1,2 @@
-python -m pip install -r engine/requirements.txt pyinstaller
-pyinstaller --noconfirm --name engine --onefile engine/run_engine.py
+python -m pip install -r engine/requirements.txt -r engine/requirements-grpc.txt pyinstaller
+pyinstaller --noconfirm --name engine --onefile --add-data "proto/engine.proto;proto" --hidden-import psutil --collect-data grpc_tools engine/run_engine.py
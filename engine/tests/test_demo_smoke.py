@@ -1,15 +1,19 @@
 from __future__ import annotations
 
 import json
+import os
 import time
 from pathlib import Path
 
+import numpy as np
+import pytest
 from fastapi.testclient import TestClient
 
-from windshadow_engine.main import app
+from windshadow_engine import main
+from windshadow_engine.main import JobState, RunRequest, app
 
 
-def test_demo_smoke(tmp_path: Path):
+def demo_request(tmp_path: Path, **overrides) -> tuple[dict, Path]:
     demo_project = Path(__file__).resolve().parents[2] / "demo" / "demo_project.wssproj.json"
     cfg = json.loads(demo_project.read_text(encoding="utf-8"))
     project_dir = tmp_path / "demo_project"
@@ -22,7 +26,11 @@ def test_demo_smoke(tmp_path: Path):
     req = cfg.copy()
     req["project_path"] = str(project_dir)
     req["dem_path"] = str(dem_dst)
+    req.update(overrides)
+    return req, project_dir
+
 
+def run_request(req: dict) -> dict:
     client = TestClient(app)
     job_id = client.post("/jobs/run", json=req).json()["id"]
 
@@ -31,7 +39,318 @@ def test_demo_smoke(tmp_path: Path):
         if state["status"] in {"done", "error"}:
             break
         time.sleep(0.05)
+    return state
+
+
+def run_demo(tmp_path: Path, **overrides) -> tuple[dict, Path]:
+    req, project_dir = demo_request(tmp_path, **overrides)
+    return run_request(req), project_dir
+
+
+def test_demo_smoke(tmp_path: Path):
+    state, project_dir = run_demo(tmp_path)
 
     assert state["status"] == "done", state.get("error")
     assert (project_dir / "outputs" / "preview.png").exists()
     assert (project_dir / "outputs" / "report.pdf").exists()
+
+
+def test_run_leaves_the_project_file_to_the_shell(tmp_path: Path):
+    # The shell saves project.wssproj.json and remembers its hash; a rewrite here would make the
+    # next save after a run report a conflict with itself.
+    req, project_dir = demo_request(tmp_path)
+    project_file = project_dir / "project.wssproj.json"
+    project_file.write_text(json.dumps(req, indent=2), encoding="utf-8")
+    saved = project_file.read_bytes()
+
+    state = run_request(req)
+
+    assert state["status"] == "done", state.get("error")
+    assert project_file.read_bytes() == saved
+
+
+def test_run_keeps_fields_the_engine_does_not_model(tmp_path: Path):
+    req, project_dir = demo_request(tmp_path)
+    project_file = project_dir / "project.wssproj.json"
+    shell_only = {
+        "scenarios": [{"name": "taller", "changes": {"turbines": []}}],
+        "external_results": [{"name": "windpro", "source": "windPRO", "path": "outputs/external/windpro.asc"}],
+        "read_only": True,
+    }
+    project_file.write_text(json.dumps({**req, **shell_only}, indent=2), encoding="utf-8")
+
+    state = run_request(req)
+
+    assert state["status"] == "done", state.get("error")
+    on_disk = json.loads(project_file.read_text(encoding="utf-8"))
+    assert {k: on_disk.get(k) for k in shell_only} == shell_only
+
+
+def test_external_groups_are_reported_separately(tmp_path: Path):
+    neighbour = {"name": "Neighbour", "status": "existing", "turbines": [
+        {"id": "N1", "x": 501000, "y": 5001000, "hub_height_m": 100, "rotor_diameter_m": 90},
+    ]}
+    state, project_dir = run_demo(tmp_path, external_groups=[neighbour])
+
+    assert state["status"] == "done", state.get("error")
+    assert (project_dir / "outputs" / "shadow_hours_external.asc").exists()
+    assert 0 < state["stats"]["external_max"] <= state["stats"]["max"]
+
+
+def test_cumulative_rows_split_receptor_hours_by_group(tmp_path: Path):
+    receptors = [{"id": "R1", "x": 500000, "y": 5000300, "height_m": 2}, {"id": "R2", "x": 500900, "y": 5000900, "height_m": 2}]
+    groups = [
+        {"name": "North", "status": "existing", "turbines": [{"id": "N1", "x": 501000, "y": 5001000, "hub_height_m": 100, "rotor_diameter_m": 90}]},
+        {"name": "South", "status": "consented", "turbines": [{"id": "S1", "x": 500100, "y": 5000200, "hub_height_m": 100, "rotor_diameter_m": 90}]},
+    ]
+    state, project_dir = run_demo(tmp_path, receptors=receptors, external_groups=groups)
+    assert state["status"] == "done", state.get("error")
+
+    rows = state["cumulative"]
+    assert [r["receptor"] for r in rows] == ["R1", "R2"]
+    assert any(g["hours_h"] > 0 for r in rows for g in r["groups"]), "no external turbine casts shadow on the receptors"
+    for r in rows:
+        assert [(g["name"], g["status"]) for g in r["groups"]] == [("North", "existing"), ("South", "consented")]
+        assert abs(r["total_h"] - r["own_h"] - sum(g["hours_h"] for g in r["groups"])) <= 0.02
+    assert (project_dir / "outputs" / "receptor_cumulative.csv").read_text().startswith("receptor;own_h;North_h;South_h;total_h")
+
+
+def test_realistic_case_scales_below_worst_case(tmp_path: Path):
+    (tmp_path / "worst").mkdir()
+    (tmp_path / "real").mkdir()
+    worst, _ = run_demo(tmp_path / "worst")
+    realistic = {"sunshine_probability": [0.5] * 12, "operating_fraction": 0.8, "wind_direction_frequency": [1, 0, 0, 0]}
+    real, _ = run_demo(tmp_path / "real", realistic=realistic)
+
+    assert real["status"] == "done", real.get("error")
+    assert 0 < real["stats"]["max"] < worst["stats"]["max"] * 0.4 + 1e-6
+
+
+def test_multi_year_run_averages_years_per_receptor(tmp_path: Path):
+    receptors = [{"id": "R1", "x": 500000, "y": 5000300, "height_m": 2}, {"id": "R2", "x": 500900, "y": 5000900, "height_m": 2}]
+    years = [
+        {"year": 2021, "sunshine_probability": [0.3] * 12, "operating_fraction": 0.9},
+        {"year": 2022, "sunshine_probability": [0.6] * 12, "operating_fraction": 0.9},
+    ]
+    realistic = {"sunshine_probability": [1.0] * 12, "years": years}
+    state, project_dir = run_demo(tmp_path, receptors=receptors, realistic=realistic)
+    assert state["status"] == "done", state.get("error")
+
+    outputs = project_dir / "outputs"
+    grids = [np.loadtxt(outputs / f"shadow_hours_{y}.asc", skiprows=6) for y in (2021, 2022)]
+    average = np.loadtxt(outputs / "shadow_hours.asc", skiprows=6)
+    assert np.allclose(average, (grids[0] + grids[1]) / 2, atol=0.01)
+    assert np.allclose(grids[1], grids[0] * 2, atol=0.02)
+
+    rows = state["interannual"]
+    assert [r["receptor"] for r in rows] == ["R1", "R2"]
+    assert any(r["std_h"] > 0 for r in rows), "no turbine casts shadow on the receptors"
+    for r in rows:
+        assert abs(r["mean_h"] - (r["years"]["2021"] + r["years"]["2022"]) / 2) <= 0.01
+        assert r["min_h"] == r["years"]["2021"] <= r["years"]["2022"] == r["max_h"]
+    assert (outputs / "receptor_interannual.csv").read_text().startswith("receptor;h_2021;h_2022;mean_h")
+
+
+def test_single_meteo_year_uses_its_own_statistics(tmp_path: Path):
+    receptors = [{"id": "R1", "x": 500000, "y": 5000300, "height_m": 2}]
+    year = {"year": 2021, "sunshine_probability": [0.3] * 12, "operating_fraction": 0.9}
+    (tmp_path / "full").mkdir()
+    (tmp_path / "single").mkdir()
+    full, _ = run_demo(tmp_path / "full", receptors=receptors, realistic={"sunshine_probability": [1.0] * 12})
+    single, project_dir = run_demo(tmp_path / "single", receptors=receptors, realistic={"sunshine_probability": [1.0] * 12, "years": [year]})
+    assert single["status"] == "done", single.get("error")
+
+    assert 0 < single["stats"]["max"] < full["stats"]["max"] * 0.4
+    assert [r["years"] for r in single["interannual"]] == [{"2021": single["interannual"][0]["mean_h"]}]
+    assert (project_dir / "outputs" / "shadow_hours_2021.asc").is_file()
+
+
+def test_paused_run_resumes_from_checkpoint(tmp_path: Path, monkeypatch):
+    monkeypatch.setenv("WSS_RUNTIME_DIR", str(tmp_path / "runtime"))
+    (tmp_path / "full").mkdir()
+    (tmp_path / "paused").mkdir()
+    full, _ = run_demo(tmp_path / "full")
+
+    req, _ = demo_request(tmp_path / "paused", checkpoint="ckpt1")
+    job = JobState(id="paused", status="running", checkpoint="ckpt1")
+    draw = main.draw_shadow
+    calls = 0
+
+    def pause_midway(*args, **kwargs):
+        nonlocal calls
+        calls += 1
+        if calls == 5000:
+            job.pause_requested = True
+        return draw(*args, **kwargs)
+
+    monkeypatch.setattr(main, "draw_shadow", pause_midway)
+    main.rasterize(job, RunRequest(**req))
+    monkeypatch.setattr(main, "draw_shadow", draw)
+    checkpoint = tmp_path / "runtime" / "checkpoints" / "ckpt1.npz"
+    assert job.status == "paused"
+    assert checkpoint.exists()
+
+    resumed = run_request(req)
+    assert resumed["status"] == "done", resumed.get("error")
+    assert any("Resuming from checkpoint" in line for line in resumed["logs"])
+    assert resumed["stats"] == full["stats"]
+    assert not checkpoint.exists()
+
+
+def test_repeated_submission_with_same_key_returns_same_job(tmp_path: Path):
+    req, _ = demo_request(tmp_path)
+    client = TestClient(app)
+    first = client.post("/jobs/run", json=req, headers={"Idempotency-Key": "k1"}).json()["id"]
+    again = client.post("/jobs/run", json=req, headers={"Idempotency-Key": "k1"}).json()["id"]
+    other = client.post("/jobs/run", json=req, headers={"Idempotency-Key": "k2"}).json()["id"]
+
+    assert first == again
+    assert other != first
+
+
+def test_print_map_scale_matches_page(tmp_path: Path):
+    width_m, height_m = 4000.0, 3000.0
+    frame_w, frame_h, denominator = main.map_layout(width_m, height_m, "A3")
+    assert abs(frame_w / frame_h - width_m / height_m) < 1e-9
+    assert abs(width_m * 1000 / denominator - frame_w) < 1e-9
+    assert main.nice_length(width_m / 5) == 500
+
+    state, project_dir = run_demo(tmp_path, output={"format": "asc", "dpi": 100, "page_size": "A3"})
+    assert state["status"] == "done", state.get("error")
+    height_px, width_px = main.plt.imread(project_dir / "outputs" / "map.png").shape[:2]
+    assert abs(width_px - 420 / 25.4 * 100) <= 1
+    assert abs(height_px - 297 / 25.4 * 100) <= 1
+
+
+def test_site_boundary_masks_map_and_reports_maxima(tmp_path: Path):
+    # A hole around T1 leaves its footprint outside the boundary.
+    shell = [[499800, 4999800], [501200, 4999800], [501200, 5001200], [499800, 5001200], [499800, 4999800]]
+    hole = [[500150, 5000150], [500150, 5000350], [500350, 5000350], [500350, 5000150], [500150, 5000150]]
+    boundary = {"type": "Polygon", "coordinates": [shell, hole]}
+    state, project_dir = run_demo(tmp_path, site_boundary=boundary)
+
+    assert state["status"] == "done", state.get("error")
+    stats = state["stats"]
+    assert abs(stats["boundary_area_m2"] - (1400 * 1400 - 200 * 200)) < 1e-6
+    assert max(stats["boundary_max"], stats["outside_max"]) == stats["max"]
+
+    # Cell centres 500125..500275: the first row and column fall between hole and shell.
+    mask = main.boundary_mask(boundary, (4, 4), 500100, 5000100, 50)
+    assert mask[0, 0] and mask[0, 3] and not mask[1, 1] and not mask[3, 3]
+
+
+def test_receptor_contributions_add_up_to_grid(tmp_path: Path):
+    receptors = [{"id": "R1", "x": 500000, "y": 5000300, "height_m": 2}, {"id": "R2", "x": 500900, "y": 5000900, "height_m": 2}]
+    state, project_dir = run_demo(tmp_path, receptors=receptors)
+    assert state["status"] == "done", state.get("error")
+
+    rows = [line.split(";") for line in (project_dir / "outputs" / "receptor_contributions.csv").read_text().splitlines()[1:]]
+    assert any(float(h) > 0 for *_, h in rows), "no turbine casts shadow on the receptors"
+    header = dict(line.split() for line in (project_dir / "outputs" / "shadow_hours.asc").read_text().splitlines()[:6])
+    grid = np.flipud(np.loadtxt(project_dir / "outputs" / "shadow_hours.asc", skiprows=6))
+    minx, miny, cell = float(header["xllcorner"]), float(header["yllcorner"]), float(header["cellsize"])
+    for r in receptors:
+        total = sum(float(h) for rid, _, _, h in rows if rid == r["id"])
+        expected = grid[int((r["y"] - miny) // cell), int((r["x"] - minx) // cell)]
+        # Every CSV value and the ASC cell are rounded to 0.01 h.
+        assert abs(total - expected) <= 0.005 * (len(rows) + 1) + 1e-9
+
+
+def test_failed_run_reports_code_and_stage(tmp_path: Path):
+    req, _ = demo_request(tmp_path, cellsize_m=7)
+    state = run_request(req)
+    assert state["status"] == "error"
+    assert state["error_detail"]["code"] == "invalid_cellsize"
+    assert state["error_detail"]["stage"] == "validate"
+    assert state["error_detail"]["details"]["cellsize_m"] == 7
+
+    missing = TestClient(app).get("/jobs/unknown").json()["detail"]
+    assert (missing["code"], missing["stage"]) == ("job_not_found", "request")
+
+
+def test_primed_cache_gives_the_same_run(tmp_path: Path, monkeypatch):
+    monkeypatch.setenv("WSS_RUNTIME_DIR", str(tmp_path / "runtime"))
+    (tmp_path / "cold").mkdir()
+    (tmp_path / "warm").mkdir()
+    cold, _ = run_demo(tmp_path / "cold")
+
+    req, _ = demo_request(tmp_path / "warm")
+    primed = run_request({**req, "mode": "prime"})
+    assert primed["status"] == "done", primed.get("error")
+    assert Path(primed["outputs"]["hillshade_png"]).exists()
+    assert len(list((tmp_path / "runtime" / "cache").glob("dem-*.npz"))) == 2
+
+    warm = run_request(req)
+    assert warm["status"] == "done", warm.get("error")
+    assert warm["stats"] == cold["stats"]
+
+
+def test_profiled_run_reports_stages(tmp_path: Path):
+    state, _ = run_demo(tmp_path, profile=True)
+    assert state["status"] == "done", state.get("error")
+    assert [s["stage"] for s in state["profile"]] == ["validate", "dem", "raycast", "aggregate", "export"]
+    assert all(s["seconds"] >= 0 and s["peak_mb"] >= s["rss_mb"] > 0 for s in state["profile"])
+
+
+def test_benchmark_times_the_standard_run(tmp_path: Path, monkeypatch):
+    monkeypatch.setenv("WSS_RUNTIME_DIR", str(tmp_path / "runtime"))
+    res = TestClient(app).post("/benchmark", params={"concurrency": 1}).json()
+    assert res["concurrency"] == 1 and res["seconds"] > 0
+    assert (tmp_path / "runtime" / "benchmark" / "run-0" / "outputs" / "shadow_hours.asc").exists()
+
+
+def test_memory_guard_refuses_runs_that_do_not_fit(tmp_path: Path, monkeypatch):
+    import psutil
+
+    req, _ = demo_request(tmp_path)
+    plan = TestClient(app).post("/jobs/memory", json=req).json()
+    assert plan["mode"] == "full" and plan["required_mb"] > 0
+
+    monkeypatch.setattr(psutil, "virtual_memory", lambda: type("Memory", (), {"available": 2**20})())
+    state = run_request(req)
+    assert state["status"] == "error"
+    assert state["error_detail"]["code"] == "insufficient_memory"
+    assert "DPI" in state["error"] or "cell size" in state["error"]
+
+
+def test_remote_listen_requires_tls(monkeypatch):
+    monkeypatch.setenv("WSS_HOST", "0.0.0.0")
+    monkeypatch.delenv("WSS_TLS_CERT", raising=False)
+    monkeypatch.delenv("WSS_TLS_KEY", raising=False)
+    with pytest.raises(SystemExit, match="without WSS_TLS_CERT"):
+        main.run()
+
+
+def test_remote_listen_requires_api_token(monkeypatch, tmp_path: Path):
+    monkeypatch.setenv("WSS_HOST", "0.0.0.0")
+    monkeypatch.setenv("WSS_TLS_CERT", str(tmp_path / "cert.pem"))
+    monkeypatch.setenv("WSS_TLS_KEY", str(tmp_path / "key.pem"))
+    monkeypatch.delenv("WSS_API_TOKEN", raising=False)
+    with pytest.raises(SystemExit, match="without WSS_API_TOKEN"):
+        main.run()
+
+
+def test_api_token_guards_everything_but_health(monkeypatch):
+    monkeypatch.setenv("WSS_API_TOKEN", "s3cret")
+    monkeypatch.setattr(main, "SERVER", type("Server", (), {"should_exit": False})())
+    client = TestClient(app)
+    assert client.get("/health").status_code == 200
+    for headers in ({}, {main.API_TOKEN_HEADER: "wrong"}):
+        res = client.post("/shutdown", headers=headers)
+        assert res.status_code == 401
+        assert res.json()["detail"]["code"] == "unauthorized"
+    assert not main.SERVER.should_exit
+    assert client.post("/shutdown", headers={main.API_TOKEN_HEADER: "s3cret"}).status_code == 200
+
+
+def test_port_file_names_the_process_and_shutdown_stops_the_server(tmp_path: Path, monkeypatch):
+    monkeypatch.setenv("WSS_PORT_FILE", "port-project-1.json")
+    monkeypatch.setenv("WSS_ENGINE_TOKEN", "42-project-1-1000")
+    main.write_port_file(tmp_path, 1234)
+    ports = json.loads((tmp_path / "port-project-1.json").read_text())
+    assert ports == {"port": 1234, "pid": os.getpid(), "token": "42-project-1-1000"}
+
+    server = type("Server", (), {"should_exit": False})()
+    monkeypatch.setattr(main, "SERVER", server)
+    assert TestClient(app).post("/shutdown").json() == {"status": "stopping"}
+    assert server.should_exit
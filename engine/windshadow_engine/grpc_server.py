@@ -0,0 +1,119 @@
+"""gRPC transport (proto/engine.proto) next to the HTTP API, for shells built with `grpc`.
+
+Both serve the same `JOBS`; here snapshots are pushed when a job changes, with only the new log
+lines, and output files are streamed in chunks. Messages are built from the .proto at startup
+(`grpc.protos_and_services`, needs grpcio-tools), so there are no generated stubs to keep in sync.
+"""
+
+from __future__ import annotations
+
+import json
+import sys
+import time
+from concurrent import futures
+from pathlib import Path
+
+import grpc
+from fastapi import HTTPException
+from pydantic import ValidationError
+
+from . import main
+
+WATCH_INTERVAL_S = 0.2
+CHUNK_BYTES = 1 << 20
+# Terminal states, as polled by the shell over HTTP.
+FINISHED = {"done", "error", "paused"}
+
+
+def proto_dir() -> Path:
+    """The repository's `proto/`, or its copy in a PyInstaller bundle."""
+    return Path(getattr(sys, "_MEIPASS", Path(__file__).resolve().parents[2])) / "proto"
+
+
+def abort(context, code: grpc.StatusCode, detail: dict) -> None:
+    """Ends the call with `detail` as JSON in the `error-detail` trailer, where the shell reads it."""
+    context.set_trailing_metadata((("error-detail", json.dumps(detail)),))
+    context.abort(code, detail["message"])
+
+
+def error_detail(protos, detail: dict | None):
+    if not detail:
+        return None
+    return protos.ErrorDetail(
+        code=detail["code"], stage=detail["stage"], message=detail["message"], details_json=json.dumps(detail["details"])
+    )
+
+
+def snapshot(protos, job: main.JobState, sent_logs: int):
+    bounds = [v for corner in job.overlay_bounds or [] for v in corner]
+    return protos.JobSnapshot(
+        id=job.id,
+        status=job.status,
+        progress_pct=job.progress_pct,
+        progress_message=job.progress_message,
+        error=job.error or "",
+        new_logs=job.logs[sent_logs:],
+        outputs=job.outputs,
+        stats=job.stats or {},
+        overlay_bounds=bounds,
+        error_detail=error_detail(protos, job.error_detail),
+        stage=job.stage,
+        profile_json=json.dumps(job.profile) if job.profile else "",
+        interannual_json=json.dumps(job.interannual) if job.interannual else "",
+        cumulative_json=json.dumps(job.cumulative) if job.cumulative else "",
+    )
+
+
+def serve(port: int) -> grpc.Server:
+    sys.path.insert(0, str(proto_dir()))
+    protos, services = grpc.protos_and_services("engine.proto")
+
+    class Engine(services.EngineServicer):
+        def SubmitRun(self, request, context):
+            try:
+                req = main.RunRequest(**json.loads(request.config_json))
+                return protos.JobRef(id=main.run_job(req, request.idempotency_key or None)["id"])
+            except HTTPException as exc:
+                code = grpc.StatusCode.INVALID_ARGUMENT if exc.status_code in (400, 422) else grpc.StatusCode.FAILED_PRECONDITION
+                detail = exc.detail if isinstance(exc.detail, dict) else {"code": f"http_{exc.status_code}", "stage": "request", "message": str(exc.detail), "details": {}}
+                abort(context, code, detail)
+            except main.EngineError as exc:
+                abort(context, grpc.StatusCode.FAILED_PRECONDITION, main.error_detail(exc, "request"))
+            except (ValueError, ValidationError) as exc:
+                abort(context, grpc.StatusCode.INVALID_ARGUMENT, main.error_detail(exc, "request"))
+
+        def WatchJob(self, request, context):
+            sent_logs, last = 0, None
+            while context.is_active():
+                job = main.JOBS.get(request.id)
+                if job is None:
+                    context.abort(grpc.StatusCode.NOT_FOUND, "job not found")
+                state = (job.status, job.progress_pct, job.progress_message, len(job.logs))
+                if state != last:
+                    yield snapshot(protos, job, sent_logs)
+                    sent_logs, last = len(job.logs), state
+                if job.status in FINISHED:
+                    return
+                time.sleep(WATCH_INTERVAL_S)
+
+        def PauseJob(self, request, context):
+            try:
+                return protos.JobRef(id=main.pause_job(request.id)["id"])
+            except HTTPException as exc:
+                code = grpc.StatusCode.NOT_FOUND if exc.status_code == 404 else grpc.StatusCode.FAILED_PRECONDITION
+                context.abort(code, exc.detail)
+
+        def GetFile(self, request, context):
+            job = main.JOBS.get(request.job_id)
+            path = job.outputs.get(request.kind) if job else None
+            if not path:
+                context.abort(grpc.StatusCode.NOT_FOUND, "job or file kind not available")
+            with open(path, "rb") as f:
+                while chunk := f.read(CHUNK_BYTES):
+                    yield protos.FileChunk(data=chunk)
+
+    server = grpc.server(futures.ThreadPoolExecutor(max_workers=8))
+    services.add_EngineServicer_to_server(Engine(), server)
+    server.add_insecure_port(f"127.0.0.1:{port}")
+    server.start()
+    return server
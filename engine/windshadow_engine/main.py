@@ -1,9 +1,13 @@
 from __future__ import annotations
 
+import functools
+import hashlib
+import hmac
 import json
 import math
 import os
 import threading
+import time
 import uuid
 from dataclasses import dataclass, field
 from datetime import datetime, timedelta
@@ -12,9 +16,16 @@ from typing import Any
 
 import matplotlib.pyplot as plt
 import numpy as np
+from matplotlib.path import Path as PlotPath
 import rasterio
-from fastapi import FastAPI, HTTPException
-from fastapi.responses import FileResponse
+from rasterio.coords import BoundingBox
+from rasterio.errors import WindowError
+from rasterio.transform import Affine
+from rasterio.windows import Window, from_bounds
+from fastapi import FastAPI, Header, HTTPException, Request
+from fastapi.encoders import jsonable_encoder
+from fastapi.exceptions import RequestValidationError
+from fastapi.responses import FileResponse, JSONResponse
 from pydantic import BaseModel, Field
 from pyproj import CRS, Transformer
 from reportlab.lib.pagesizes import A4
@@ -26,6 +37,26 @@ MAX_TURBINES = 20
 MAX_AREA_M = 12_000
 DEFAULT_BUFFER = 2_000
 CELLSIZE_ALLOWED = {8, 10, 20, 25, 50}
+CHECKPOINT_INTERVAL_S = 60
+TIMESTEP_MIN = 15
+# Longest shadow drawn; DEM clips are padded by it so rays never leave them.
+MAX_SHADOW_M = 20_000
+# Bump when the layout of cached artifacts changes.
+CACHE_VERSION = 1
+# Share of the available RAM a run may plan for; the rest absorbs estimate error and other jobs.
+MEMORY_HEADROOM = 0.7
+# Result grid, external share, boundary mask and the masked float64 copy drawn on the map.
+GRID_BYTES_PER_CELL = 4 + 4 + 1 + 8
+# RGBA canvas of the print map plus the PNG encoder's and the PDF embedding's copies.
+MAP_BYTES_PER_PX = 4 * 3
+# Timestep, solar elevation, azimuth and weight per step of the sun calendar.
+TIMESTEP_BYTES = 48 + 3 * 8
+# Landscape page sizes in mm for the print map.
+PAGE_SIZES_MM = {"A4": (297.0, 210.0), "A3": (420.0, 297.0)}
+MM_PER_INCH = 25.4
+MAP_MARGIN_MM = 15.0
+# Strip right of the map frame for the colour bar and its label.
+COLORBAR_MM = 30.0
 
 
 class Turbine(BaseModel):
@@ -36,8 +67,45 @@ class Turbine(BaseModel):
     rotor_diameter_m: float
 
 
+class Receptor(BaseModel):
+    id: str
+    x: float
+    y: float
+    height_m: float = 2.0
+
+
+class TurbineGroup(BaseModel):
+    name: str
+    status: str = Field(default="existing", pattern="^(existing|consented)$")
+    turbines: list[Turbine]
+
+
+class MeteoYear(BaseModel):
+    """Sunshine and operational data of one meteorological year, as in `RealisticCase`."""
+
+    year: int
+    sunshine_probability: list[float] = Field(min_length=12, max_length=12)
+    operating_fraction: float = Field(default=1.0, ge=0, le=1)
+    wind_direction_frequency: list[float] = []
+
+
+class RealisticCase(BaseModel):
+    """Weights turning astronomical worst-case hours into expected (realistic) hours."""
+
+    sunshine_probability: list[float] = Field(default_factory=lambda: [1.0] * 12, min_length=12, max_length=12)
+    operating_fraction: float = Field(default=1.0, ge=0, le=1)
+    # Relative frequency per wind sector, sector 0 centred on north, clockwise.
+    wind_direction_frequency: list[float] = []
+    # With two or more years each is computed on its own and the results are their long-term
+    # average; the weights above are then unused.
+    years: list[MeteoYear] = []
+
+
 class OutputConfig(BaseModel):
     format: str = Field(default="both", pattern="^(asc|geotiff|both)$")
+    # Print map resolution and page, independent of the screen the app runs on.
+    dpi: int = Field(default=150, ge=72, le=600)
+    page_size: str = Field(default="A4", pattern="^(A4|A3)$")
 
 
 class RunRequest(BaseModel):
@@ -47,8 +115,25 @@ class RunRequest(BaseModel):
     buffer_m: float = DEFAULT_BUFFER
     terrain_aware: bool = False
     dem_path: str
+    dsm_path: str = ""
+    landcover_path: str = ""
+    landcover_heights: dict[str, float] = {}
+    buildings_path: str = ""
     turbines: list[Turbine]
+    external_groups: list[TurbineGroup] = []
+    # Only sampled for the per-turbine contributions in `receptor_contributions.csv`.
+    receptors: list[Receptor] = []
+    realistic: RealisticCase | None = None
     output: OutputConfig = OutputConfig()
+    # GeoJSON Polygon or MultiPolygon in the project CRS; masks the print map, not the rasters.
+    site_boundary: dict[str, Any] | None = None
+    # Key of the checkpoint to write while running and to resume from; set by the shell.
+    checkpoint: str = ""
+    # `prime` only fills the cache (DEM clip, hillshade, sun calendar) for later runs. There are
+    # no horizon tables to prime: terrain is traced per sun position, see `terrain_adjusted_length`.
+    mode: str = Field(default="run", pattern="^(run|prime)$")
+    # Records time and memory per stage into `JobState.profile`.
+    profile: bool = False
 
 
 @dataclass
@@ -62,10 +147,82 @@ class JobState:
     outputs: dict[str, str] = field(default_factory=dict)
     overlay_bounds: list[list[float]] | None = None
     stats: dict[str, float] | None = None
+    checkpoint: str = ""
+    pause_requested: bool = False
+    # Pipeline stage being run, reported with errors: validate, dem, raycast, aggregate, export.
+    stage: str = "queued"
+    # `{code, stage, message, details}` when the job failed, see `error_detail`.
+    error_detail: dict[str, Any] | None = None
+    profiler: StageProfiler | None = None
+    # `full`, or `tiled` when screening rasters are read around the site only (see `memory_plan`).
+    memory_mode: str = "full"
+    # `[{stage, seconds, rss_mb, peak_mb}]` of a profiled run, once done.
+    profile: list[dict[str, Any]] | None = None
+    # Per receptor hours of each meteorological year with their spread, see `interannual_rows`.
+    interannual: list[dict[str, Any]] | None = None
+    # Per receptor own hours and those of each external group, see `cumulative_rows`.
+    cumulative: list[dict[str, Any]] | None = None
+
+
+class StageProfiler:
+    """Wall time per stage and the engine's resident memory at its end and at its peak, the
+    peak sampled on a background thread every SAMPLE_S."""
+
+    SAMPLE_S = 0.05
+
+    def __init__(self):
+        import psutil
+
+        self.process = psutil.Process()
+        self.stages: list[dict[str, Any]] = []
+        self.current: tuple[str, float] | None = None
+        self.peak = 0
+        self.lock = threading.Lock()
+        self.stopped = threading.Event()
+        threading.Thread(target=self._sample, daemon=True).start()
+
+    def _sample(self) -> None:
+        while not self.stopped.wait(self.SAMPLE_S):
+            rss = self.process.memory_info().rss
+            with self.lock:
+                self.peak = max(self.peak, rss)
+
+    def _close(self) -> None:
+        if self.current is None:
+            return
+        name, started = self.current
+        rss = self.process.memory_info().rss
+        with self.lock:
+            peak, self.peak = max(self.peak, rss), 0
+        self.stages.append({"stage": name, "seconds": round(time.perf_counter() - started, 4), "rss_mb": round(rss / 2**20, 1), "peak_mb": round(peak / 2**20, 1)})
+        self.current = None
+
+    def enter(self, name: str) -> None:
+        self._close()
+        with self.lock:
+            self.peak = self.process.memory_info().rss
+        self.current = (name, time.perf_counter())
+
+    def finish(self) -> list[dict[str, Any]]:
+        self._close()
+        self.stopped.set()
+        return self.stages
+
+
+class EngineError(ValueError):
+    """Failure with a stable `code` the shell can act on; `details` holds its parameters."""
+
+    def __init__(self, code: str, message: str, **details: Any):
+        super().__init__(message)
+        self.code = code
+        self.details = details
 
 
 app = FastAPI(title="Wind Shadow Engine")
 JOBS: dict[str, JobState] = {}
+# Idempotency-Key -> job id, so a retried submission does not start a second run.
+SUBMISSIONS: dict[str, str] = {}
+SUBMISSIONS_LOCK = threading.Lock()
 
 
 def log(job: JobState, msg: str) -> None:
@@ -75,15 +232,295 @@ def log(job: JobState, msg: str) -> None:
     job.progress_message = msg
 
 
-def save_project_state(project_path: Path, payload: dict[str, Any]) -> None:
-    path = project_path / "project.wssproj.json"
-    path.write_text(json.dumps(payload, indent=2), encoding="utf-8")
+def enter_stage(job: JobState, name: str) -> None:
+    job.stage = name
+    if job.profiler:
+        job.profiler.enter(name)
+
+
+def error_detail(exc: BaseException, stage: str) -> dict[str, Any]:
+    """The error schema shared with the shell (`engine::EngineError`)."""
+    if isinstance(exc, EngineError):
+        code, details = exc.code, exc.details
+    elif isinstance(exc, FileNotFoundError):
+        code, details = "file_not_found", {"path": exc.filename}
+    elif isinstance(exc, MemoryError):
+        code, details = "out_of_memory", {}
+    elif isinstance(exc, ValueError):
+        code, details = "invalid_input", {}
+    else:
+        code, details = "internal", {"type": type(exc).__name__}
+    return {"code": code, "stage": stage, "message": str(exc) or type(exc).__name__, "details": details}
+
+
+def request_error(status: int, code: str, message: str) -> HTTPException:
+    """HTTP error whose `detail` follows the same schema, at the `request` stage."""
+    return HTTPException(status, {"code": code, "stage": "request", "message": message, "details": {}})
+
+
+@app.exception_handler(RequestValidationError)
+def invalid_request(_: Request, exc: RequestValidationError):
+    detail = {"code": "invalid_request", "stage": "request", "message": "invalid run request", "details": {"errors": jsonable_encoder(exc.errors())}}
+    return JSONResponse(status_code=422, content={"detail": detail})
+
+
+# Shared secret for remote deployments (`WSS_API_TOKEN`); when set, every call but /health must
+# carry it in this header. Engines the shell spawns on loopback run without one.
+API_TOKEN_HEADER = "X-WSS-Token"
+
+
+def api_token() -> str:
+    return os.environ.get("WSS_API_TOKEN", "")
+
+
+@app.middleware("http")
+async def require_api_token(request: Request, call_next):
+    token = api_token()
+    if token and request.url.path != "/health" and not hmac.compare_digest(request.headers.get(API_TOKEN_HEADER, ""), token):
+        detail = {"code": "unauthorized", "stage": "request", "message": f"missing or wrong {API_TOKEN_HEADER}", "details": {}}
+        return JSONResponse(status_code=401, content={"detail": detail})
+    return await call_next(request)
+
+
+def runtime_dir() -> Path:
+    return Path(os.environ.get("WSS_RUNTIME_DIR", Path.home() / ".windshadowstudio"))
+
+
+def checkpoint_path(key: str) -> Path:
+    return runtime_dir() / "checkpoints" / f"{key}.npz"
+
+
+def request_fingerprint(req: RunRequest) -> str:
+    payload = json.dumps(req.model_dump(exclude={"checkpoint", "profile"}), sort_keys=True)
+    return hashlib.sha256(payload.encode("utf-8")).hexdigest()
 
 
-def read_dem(dem_path: Path):
+def save_checkpoint(key: str, req: RunRequest, grid, ext_grid, contrib, year_grids, source: int, step: int, op: int) -> None:
+    """Accumulated grids (per meteorological year too) and receptor contributions plus the next
+    (source, timestep) to compute, replaced atomically."""
+    path = checkpoint_path(key)
+    path.parent.mkdir(parents=True, exist_ok=True)
+    meta = {"fingerprint": request_fingerprint(req), "source": source, "step": step, "op": op}
+    tmp = path.with_name(f"{key}.tmp.npz")
+    np.savez(tmp, grid=grid, ext_grid=ext_grid, contrib=contrib, year_grids=year_grids, meta=json.dumps(meta))
+    os.replace(tmp, path)
+
+
+def load_checkpoint(key: str, req: RunRequest, shape: tuple[int, int]):
+    """Checkpoint for this exact request, or None (missing, other inputs, other grid)."""
+    path = checkpoint_path(key)
+    if not path.exists():
+        return None
+    with np.load(path) as data:
+        meta = json.loads(str(data["meta"]))
+        if meta["fingerprint"] != request_fingerprint(req) or data["grid"].shape != shape:
+            return None
+        if "year_grids" not in data:
+            return None
+        return data["grid"].copy(), data["ext_grid"].copy(), data["contrib"].copy(), data["year_grids"].copy(), meta
+
+
+def cache_dir() -> Path:
+    return runtime_dir() / "cache"
+
+
+def grid_extent(req: RunRequest) -> tuple[float, float, float, float]:
+    """Turbine bounding box plus buffer, clamped to MAX_AREA_M from its south-west corner."""
+    xs = [t.x for t in req.turbines]
+    ys = [t.y for t in req.turbines]
+    minx, miny = min(xs) - req.buffer_m, min(ys) - req.buffer_m
+    width = min(max(xs) + req.buffer_m - minx, MAX_AREA_M)
+    height = min(max(ys) + req.buffer_m - miny, MAX_AREA_M)
+    return minx, miny, minx + width, miny + height
+
+
+def padded_window(ds, extent: tuple[float, float, float, float]) -> Window | None:
+    """Pixels of `ds` within `extent` padded by MAX_SHADOW_M, None when they do not overlap."""
+    minx, miny, maxx, maxy = extent
+    pad = MAX_SHADOW_M
+    try:
+        window = from_bounds(minx - pad, miny - pad, maxx + pad, maxy + pad, transform=ds.transform)
+        return window.intersection(Window(0, 0, ds.width, ds.height)).round_offsets().round_lengths()
+    except WindowError:
+        return None
+
+
+def dem_cache_key(path: Path, extent: tuple[float, float, float, float]) -> str:
+    stat = path.stat()
+    payload = json.dumps([str(path.resolve()), stat.st_mtime_ns, stat.st_size, [round(v, 3) for v in extent], CACHE_VERSION])
+    return hashlib.sha256(payload.encode("utf-8")).hexdigest()[:24]
+
+
+def clipped_dem(path: Path, extent: tuple[float, float, float, float]):
+    """`read_dem` limited to `extent` padded by MAX_SHADOW_M, cached as npz in the runtime dir.
+
+    The bounds returned are still those of the whole DEM, for the turbine coverage checks.
+    """
+    cached = cache_dir() / f"dem-{dem_cache_key(path, extent)}.npz"
+    if cached.exists():
+        with np.load(cached) as data:
+            meta = json.loads(str(data["meta"]))
+            crs = CRS.from_wkt(meta["crs"]) if meta["crs"] else None
+            return data["dem"].copy(), Affine(*meta["transform"]), crs, meta["nodata"], BoundingBox(*meta["bounds"])
+    with rasterio.open(path) as ds:
+        window = padded_window(ds, extent)
+        if window is None:
+            # No overlap: the run reports turbines outside the DEM, nothing worth caching.
+            return read_dem(path)
+        arr = ds.read(1, window=window)
+        transform, crs, nodata, bounds = ds.window_transform(window), ds.crs, ds.nodata, ds.bounds
+    cached.parent.mkdir(parents=True, exist_ok=True)
+    meta = {"transform": list(transform)[:6], "crs": crs.to_wkt() if crs else "", "nodata": nodata, "bounds": list(bounds)}
+    tmp = cached.with_name(f"{cached.stem}.tmp.npz")
+    np.savez(tmp, dem=arr, meta=json.dumps(meta))
+    os.replace(tmp, cached)
+    return arr, transform, crs, nodata, bounds
+
+
+@functools.lru_cache(maxsize=1)
+def sun_calendar() -> tuple[tuple[datetime, ...], np.ndarray, np.ndarray]:
+    """Timesteps of the typical year with solar elevation and azimuth, computed once per engine."""
+    steps = []
+    d = datetime(TYPICAL_YEAR, 1, 1, 6, 0)
+    while d.year == TYPICAL_YEAR:
+        steps.append(d)
+        d += timedelta(minutes=TIMESTEP_MIN)
+    solar = np.array([approx_solar(dt) for dt in steps], dtype=np.float64)
+    return tuple(steps), solar[:, 0], solar[:, 1]
+
+
+def hillshade(dem: np.ndarray, cell: float, azimuth: float = 315.0, altitude: float = 45.0) -> np.ndarray:
+    """Shaded relief in 0..1 for a north-up array (row 0 north), lit from `azimuth`."""
+    dy, dx = np.gradient(dem.astype(np.float64), cell)
+    slope = np.arctan(np.hypot(dx, dy))
+    aspect = np.arctan2(-dx, dy)
+    az, alt = math.radians(360 - azimuth + 90), math.radians(altitude)
+    shade = np.sin(alt) * np.cos(slope) + np.cos(alt) * np.sin(slope) * np.cos(az - aspect)
+    return np.clip(shade, 0, 1)
+
+
+def prime(job: JobState, req: RunRequest) -> None:
+    """Fills the cache a later run of this project reads: DEM clip, sun calendar, hillshade.
+    Terrain screening has nothing to precompute, each ray is traced for its own sun position."""
+    job.stage = "dem"
+    extent = grid_extent(req)
+    dem, dem_tr, _, dem_nodata, _ = clipped_dem(Path(req.dem_path), extent)
+    log(job, f"DEM clip cached ({dem.shape[1]} x {dem.shape[0]} cells)")
+    job.progress_pct = 40
+    steps, _, _ = sun_calendar()
+    log(job, f"Sun calendar ready ({len(steps)} timesteps)")
+    job.progress_pct = 60
+
+    job.stage = "export"
+    png = cache_dir() / f"hillshade-{dem_cache_key(Path(req.dem_path), extent)}.png"
+    minx, miny, maxx, maxy = extent
+    if not png.exists():
+        # Hillshade of the run extent only, the map background under the results overlay.
+        rows, cols = rasterio.transform.rowcol(dem_tr, [minx, maxx], [maxy, miny])
+        r0, r1 = sorted((max(0, rows[0]), min(dem.shape[0], rows[1] + 1)))
+        c0, c1 = sorted((max(0, cols[0]), min(dem.shape[1], cols[1] + 1)))
+        window = dem[r0:r1, c0:c1].astype(np.float64)
+        if dem_nodata is not None:
+            window = np.where(window == dem_nodata, np.nan, window)
+        if window.size >= 4:
+            shade = hillshade(np.nan_to_num(window, nan=float(np.nanmean(window)) if np.isfinite(window).any() else 0.0), abs(dem_tr.a))
+            plt.imsave(png, shade, cmap="gray", vmin=0, vmax=1)
+    if png.exists():
+        job.outputs["hillshade_png"] = str(png)
+        job.overlay_bounds = to_wgs84_bounds(minx, miny, maxx, maxy, CRS.from_user_input(req.epsg))
+    job.progress_pct = 100
+    job.status = "done"
+    log(job, "Cache primed")
+
+
+def receptor_cells(receptors: list[Receptor], minx: float, miny: float, cell: float, shape: tuple[int, int]):
+    """(row, col) of each receptor in the grid, rows from `miny` up; None outside."""
+    cells = []
+    for r in receptors:
+        row, col = int((r.y - miny) // cell), int((r.x - minx) // cell)
+        cells.append((row, col) if 0 <= row < shape[0] and 0 <= col < shape[1] else None)
+    return cells
+
+
+def sample_cells(grid: np.ndarray, cells) -> np.ndarray:
+    return np.array([float(grid[c]) if c else 0.0 for c in cells], dtype=np.float64)
+
+
+def interannual_rows(receptors: list[Receptor], cells, years: list[MeteoYear], year_grids: np.ndarray) -> list[dict[str, Any]]:
+    """Hours of each year at each receptor inside the grid with their long-term mean, sample
+    standard deviation, range and coefficient of variation."""
+    rows = []
+    for r, c in zip(receptors, cells):
+        if c is None:
+            continue
+        hours = year_grids[(slice(None), *c)].astype(np.float64)
+        mean = float(hours.mean())
+        std = float(hours.std(ddof=1)) if len(hours) > 1 else 0.0
+        rows.append(
+            {
+                "receptor": r.id,
+                "years": {str(y.year): round(float(h), 2) for y, h in zip(years, hours)},
+                "mean_h": round(mean, 2),
+                "std_h": round(std, 2),
+                "min_h": round(float(hours.min()), 2),
+                "max_h": round(float(hours.max()), 2),
+                "cv": round(std / mean, 3) if mean > 0 else 0.0,
+            }
+        )
+    return rows
+
+
+def write_interannual(path: Path, years: list[MeteoYear], rows: list[dict[str, Any]]) -> None:
+    with path.open("w", encoding="utf-8") as f:
+        f.write(";".join(["receptor", *(f"h_{y.year}" for y in years), "mean_h", "std_h", "min_h", "max_h", "cv"]) + "\n")
+        for row in rows:
+            cols = [row["receptor"], *(f"{row['years'][str(y.year)]:.2f}" for y in years)]
+            cols += [f"{row[k]:.2f}" for k in ("mean_h", "std_h", "min_h", "max_h")] + [f"{row['cv']:.3f}"]
+            f.write(";".join(cols) + "\n")
+
+
+def write_contributions(path: Path, sources, receptors: list[Receptor], cells, contrib: np.ndarray) -> None:
+    """Hours each turbine adds at each receptor inside the grid, one row per pair."""
+    with path.open("w", encoding="utf-8") as f:
+        f.write("receptor;turbine;external;hours\n")
+        for ri, r in enumerate(receptors):
+            if cells[ri] is None:
+                continue
+            for si, (t, _, group) in enumerate(sources):
+                f.write(f"{r.id};{t.id};{int(group is not None)};{contrib[si, ri]:.2f}\n")
+
+
+def cumulative_rows(req: RunRequest, cells, sources, contrib: np.ndarray) -> list[dict[str, Any]]:
+    """Hours at each receptor inside the grid split into the project's own turbines and each
+    external group, with the cumulative total the compliance check is made on."""
+    rows = []
+    for ri, r in enumerate(req.receptors):
+        if cells[ri] is None:
+            continue
+        own = sum(contrib[si, ri] for si, (_, _, g) in enumerate(sources) if g is None)
+        groups = [
+            {"name": g.name, "status": g.status, "hours_h": round(float(sum(contrib[si, ri] for si, (_, _, sg) in enumerate(sources) if sg is g)), 2)}
+            for g in req.external_groups
+        ]
+        total = own + sum(contrib[si, ri] for si, (_, _, g) in enumerate(sources) if g is not None)
+        rows.append({"receptor": r.id, "own_h": round(float(own), 2), "groups": groups, "total_h": round(float(total), 2)})
+    return rows
+
+
+def write_cumulative(path: Path, groups: list[TurbineGroup], rows: list[dict[str, Any]]) -> None:
+    with path.open("w", encoding="utf-8") as f:
+        f.write(";".join(["receptor", "own_h", *(f"{g.name}_h" for g in groups), "total_h"]) + "\n")
+        for r in rows:
+            f.write(";".join([r["receptor"], f"{r['own_h']:.2f}", *(f"{g['hours_h']:.2f}" for g in r["groups"]), f"{r['total_h']:.2f}"]) + "\n")
+
+
+def read_dem(dem_path: Path, extent: tuple[float, float, float, float] | None = None):
+    """Band 1 with its transform, CRS, nodata and bounds; with `extent`, only the pixels
+    `padded_window` selects (the bounds stay those of the whole raster)."""
     with rasterio.open(dem_path) as ds:
-        arr = ds.read(1)
-        transform = ds.transform
+        window = padded_window(ds, extent) if extent else None
+        arr = ds.read(1, window=window)
+        transform = ds.window_transform(window) if window else ds.transform
         crs = ds.crs
         nodata = ds.nodata
         bounds = ds.bounds
@@ -95,76 +532,147 @@ def rasterize(job: JobState, req: RunRequest) -> None:
     outputs = project / "outputs"
     outputs.mkdir(parents=True, exist_ok=True)
 
+    if req.profile and not job.profiler:
+        job.profiler = StageProfiler()
+    enter_stage(job, "validate")
     if len(req.turbines) > MAX_TURBINES:
-        raise ValueError(f"Too many turbines ({len(req.turbines)}), max {MAX_TURBINES}")
+        raise EngineError("too_many_turbines", f"Too many turbines ({len(req.turbines)}), max {MAX_TURBINES}", count=len(req.turbines), max=MAX_TURBINES)
     if req.cellsize_m not in CELLSIZE_ALLOWED:
-        raise ValueError("cellsize_m must be one of 8,10,20,25,50")
-
-    dem, dem_tr, dem_crs, dem_nodata, dem_bounds = read_dem(Path(req.dem_path))
+        raise EngineError("invalid_cellsize", "cellsize_m must be one of 8,10,20,25,50", cellsize_m=req.cellsize_m, allowed=sorted(CELLSIZE_ALLOWED))
+    plan = memory_plan(req)
+    if plan["mode"] == "refuse":
+        raise EngineError("insufficient_memory", memory_guidance(plan), **plan)
+    job.memory_mode = plan["mode"]
+    if plan["mode"] == "tiled":
+        log(job, f"Memory: {plan['required_mb']:.0f} MB needed, {plan['available_mb']:.0f} MB available; reading screening rasters around the site only")
+
+    enter_stage(job, "dem")
+    minx, miny, maxx, maxy = grid_extent(req)
+    dem, dem_tr, dem_crs, dem_nodata, dem_bounds = clipped_dem(Path(req.dem_path), (minx, miny, maxx, maxy))
     model_crs = CRS.from_user_input(req.epsg)
     if dem_crs and dem_crs != model_crs:
         log(job, "Warning: DEM CRS differs from selected EPSG. Continuing with selected EPSG.")
+    # Rays only meet the surface in terrain-aware mode; otherwise the screening rasters are not read.
+    surface = screening_surface(job, req, dem, dem_tr, model_crs) if req.terrain_aware else None
+    if not req.terrain_aware and (req.dsm_path or req.landcover_path or req.buildings_path):
+        log(job, "Screening rasters ignored: screening requires terrain-aware mode")
 
     xs = [t.x for t in req.turbines]
     ys = [t.y for t in req.turbines]
-    minx, maxx = min(xs) - req.buffer_m, max(xs) + req.buffer_m
-    miny, maxy = min(ys) - req.buffer_m, max(ys) + req.buffer_m
-
-    width = min(maxx - minx, MAX_AREA_M)
-    height = min(maxy - miny, MAX_AREA_M)
-    if width < (maxx - minx) or height < (maxy - miny):
+    if maxx < max(xs) + req.buffer_m or maxy < max(ys) + req.buffer_m:
         log(job, "Area clamped to max 12km x 12km")
-
-    maxx = minx + width
-    maxy = miny + height
+    width, height = maxx - minx, maxy - miny
 
     cell = req.cellsize_m
     ncols = max(1, int(math.ceil(width / cell)))
     nrows = max(1, int(math.ceil(height / cell)))
     grid = np.zeros((nrows, ncols), dtype=np.float32)
-
-    steps = []
-    d = datetime(TYPICAL_YEAR, 1, 1, 6, 0)
-    while d.year == TYPICAL_YEAR:
-        steps.append(d)
-        d += timedelta(minutes=15)
-
-    total_ops = max(1, len(steps) * len(req.turbines))
+    # Neighbouring farms are accumulated apart so their share stays visible in the outputs.
+    ext_grid = np.zeros_like(grid)
+    sources = [(t, grid, None) for t in req.turbines]
+    for g in req.external_groups:
+        log(job, f"Cumulative: {len(g.turbines)} turbines from {g.status} farm {g.name}")
+        sources += [(t, ext_grid, g) for t in g.turbines]
+    cells = receptor_cells(req.receptors, minx, miny, cell, grid.shape)
+    # Hours per (source, receptor); the source being drawn holds minus its starting values.
+    contrib = np.zeros((len(sources), len(req.receptors)), dtype=np.float64)
+
+    steps, elevations, azimuths = sun_calendar()
+
+    years = meteo_years(req.realistic)
+    # Own and external hours per meteorological year, drawn with each year's weights.
+    year_grids = np.zeros((len(years), *grid.shape), dtype=np.float32)
+    if years:
+        year_weights = np.array([[timestep_weight(y, dt) for y in years] for dt in steps])
+        # Drawing is linear in the weight, so the mean weight gives the long-term average grid.
+        weights = list(year_weights.mean(axis=1))
+        span = f"{years[0].year}-{years[-1].year}" if len(years) > 1 else str(years[0].year)
+        log(job, f"Realistic case: {len(years)} meteorological years ({span}), long-term average")
+    else:
+        weights = [timestep_weight(req.realistic, dt) for dt in steps]
+        if req.realistic:
+            log(job, f"Realistic case: operating {req.realistic.operating_fraction:.2f}, {len(req.realistic.wind_direction_frequency)} wind sectors")
+
+    total_ops = max(1, len(steps) * len(sources))
     op = 0
-
-    for t in req.turbines:
+    start_source, start_step = 0, 0
+    key = job.checkpoint
+    resumed = load_checkpoint(key, req, grid.shape) if key else None
+    if resumed:
+        grid[:], ext_grid[:], contrib[:], year_grids[:], meta = resumed
+        start_source, start_step, op = meta["source"], meta["step"], meta["op"]
+        job.progress_pct = int(op * 100 / total_ops)
+        log(job, f"Resuming from checkpoint at {op * 100 // total_ops}%")
+    last_save = time.monotonic()
+
+    enter_stage(job, "raycast")
+    for si, (t, target, _) in enumerate(sources):
+        if si < start_source:
+            continue
         if not (dem_bounds.left <= t.x <= dem_bounds.right and dem_bounds.bottom <= t.y <= dem_bounds.top):
             log(job, f"Turbine {t.id} outside DEM, ignored")
             continue
+        if not (resumed and si == start_source):
+            contrib[si] = -sample_cells(target, cells)
         z_ground = sample_dem(dem, dem_tr, t.x, t.y)
         hub_z = z_ground + t.hub_height_m
 
-        for dt in steps:
-            elev, azim = approx_solar(dt)
+        for k in range(start_step if si == start_source else 0, len(steps)):
+            if key and (job.pause_requested or time.monotonic() - last_save > CHECKPOINT_INTERVAL_S):
+                save_checkpoint(key, req, grid, ext_grid, contrib, year_grids, si, k, op)
+                last_save = time.monotonic()
+                if job.pause_requested:
+                    job.status = "paused"
+                    log(job, f"Paused at {job.progress_pct}%")
+                    return
+            weight, elev, azim = weights[k], float(elevations[k]), float(azimuths[k])
             if elev <= 0:
                 op += 1
                 continue
-            length = min(20_000, t.hub_height_m / math.tan(math.radians(elev)))
+            length = min(MAX_SHADOW_M, t.hub_height_m / math.tan(math.radians(elev)))
             if req.terrain_aware:
-                length = terrain_adjusted_length(dem, dem_tr, t.x, t.y, hub_z, elev, azim, cell, length)
+                length = terrain_adjusted_length(surface, t.x, t.y, hub_z, elev, azim, cell, length)
 
-            draw_shadow(grid, minx, miny, cell, t.x, t.y, azim, length, t.rotor_diameter_m)
+            draw_shadow(target, minx, miny, cell, t.x, t.y, azim, length, t.rotor_diameter_m, weight)
+            if years:
+                draw_shadow(year_grids, minx, miny, cell, t.x, t.y, azim, length, t.rotor_diameter_m, year_weights[k])
             op += 1
             if op % 500 == 0:
                 job.progress_pct = int(op * 100 / total_ops)
+        contrib[si] += sample_cells(target, cells)
 
     job.progress_pct = 85
+    enter_stage(job, "aggregate")
+    if req.external_groups:
+        grid += ext_grid
     valid = grid[grid >= 0]
     stats = {
         "min": float(np.min(valid)) if valid.size else 0,
         "max": float(np.max(valid)) if valid.size else 0,
         "mean": float(np.mean(valid)) if valid.size else 0,
     }
+    if req.external_groups:
+        stats["external_max"] = float(np.max(ext_grid))
+    mask = None
+    if req.site_boundary:
+        mask = boundary_mask(req.site_boundary, grid.shape, minx, miny, cell)
+        stats["boundary_area_m2"] = boundary_area_m2(req.site_boundary)
+        stats["boundary_max"] = float(np.max(grid[mask])) if mask.any() else 0
+        stats["outside_max"] = float(np.max(grid[~mask])) if (~mask).any() else 0
+        log(job, f"Site boundary: {stats['boundary_area_m2'] / 10_000:.1f} ha, {int(mask.sum())} cells inside")
+    if years:
+        job.interannual = interannual_rows(req.receptors, cells, years, year_grids)
+        stats["interannual_years"] = len(years)
+        stats["interannual_std_max"] = max((r["std_h"] for r in job.interannual), default=0.0)
+    if req.external_groups and req.receptors:
+        job.cumulative = cumulative_rows(req, cells, sources, contrib)
     job.stats = stats
 
+    enter_stage(job, "export")
     asc_path = outputs / "shadow_hours.asc"
     tif_path = outputs / "shadow_hours.tif"
     png_path = outputs / "preview.png"
+    map_path = outputs / "map.png"
     pdf_path = outputs / "report.pdf"
 
     if req.output.format in {"asc", "both"}:
@@ -173,16 +681,42 @@ def rasterize(job: JobState, req: RunRequest) -> None:
     if req.output.format in {"geotiff", "both"}:
         write_tif(tif_path, grid, minx, miny, cell, model_crs)
         job.outputs["geotiff"] = str(tif_path)
+    if req.external_groups:
+        ext_path = outputs / "shadow_hours_external.asc"
+        write_asc(ext_path, ext_grid, minx, miny, cell)
+        job.outputs["external_asc"] = str(ext_path)
+    if req.receptors:
+        contrib_path = outputs / "receptor_contributions.csv"
+        write_contributions(contrib_path, sources, req.receptors, cells, contrib)
+        job.outputs["contributions_csv"] = str(contrib_path)
+    if job.cumulative:
+        cumulative_path = outputs / "receptor_cumulative.csv"
+        write_cumulative(cumulative_path, req.external_groups, job.cumulative)
+        job.outputs["cumulative_csv"] = str(cumulative_path)
+    for y, year_grid in zip(years, year_grids):
+        year_path = outputs / f"shadow_hours_{y.year}.asc"
+        write_asc(year_path, year_grid, minx, miny, cell)
+        job.outputs[f"asc_{y.year}"] = str(year_path)
+    if job.interannual:
+        interannual_path = outputs / "receptor_interannual.csv"
+        write_interannual(interannual_path, years, job.interannual)
+        job.outputs["interannual_csv"] = str(interannual_path)
 
     make_preview(png_path, grid)
-    make_pdf(pdf_path, req, stats, job.outputs)
+    scale = make_map(map_path, grid, minx, miny, cell, req.output, req.site_boundary, mask)
+    job.outputs["map_png"] = str(map_path)
+    make_pdf(pdf_path, req, stats, job.outputs, map_path, scale, job.cumulative)
     job.outputs["preview_png"] = str(png_path)
     job.outputs["pdf"] = str(pdf_path)
 
     b = to_wgs84_bounds(minx, miny, maxx, maxy, model_crs)
     job.overlay_bounds = b
 
-    save_project_state(project, req.model_dump())
+    if key:
+        checkpoint_path(key).unlink(missing_ok=True)
+    if job.profiler:
+        job.profile = job.profiler.finish()
+        log(job, "Profile: " + ", ".join(f"{s['stage']} {s['seconds']:.1f} s / {s['peak_mb']:.0f} MB" for s in job.profile))
     job.progress_pct = 100
     job.status = "done"
     log(job, "Completed")
@@ -197,6 +731,31 @@ def approx_solar(dt: datetime) -> tuple[float, float]:
     return elev, azim
 
 
+def rotor_orientation_factor(frequency: list[float], azim: float) -> float:
+    """Mean projected rotor area seen from the sun, the rotor facing each wind sector in turn."""
+    total = sum(frequency)
+    if total <= 0:
+        return 1.0
+    n = len(frequency)
+    return sum(f * abs(math.cos(math.radians(azim - k * 360 / n))) for k, f in enumerate(frequency)) / total
+
+
+def meteo_years(realistic: RealisticCase | None) -> list[MeteoYear]:
+    """Meteorological years to compute apart, by year; a single year is computed with its own
+    statistics, not the realistic case it sits in."""
+    years = sorted(realistic.years, key=lambda y: y.year) if realistic else []
+    if len({y.year for y in years}) != len(years):
+        raise EngineError("invalid_input", "Meteorological years must be distinct", years=[y.year for y in years])
+    return years
+
+
+def timestep_weight(realistic: RealisticCase | MeteoYear | None, dt: datetime) -> float:
+    if realistic is None:
+        return 1.0
+    _, azim = approx_solar(dt)
+    return realistic.sunshine_probability[dt.month - 1] * realistic.operating_fraction * rotor_orientation_factor(realistic.wind_direction_frequency, azim)
+
+
 def sample_dem(arr, tr, x, y):
     col = int((x - tr.c) / tr.a)
     row = int((tr.f - y) / abs(tr.e))
@@ -205,7 +764,123 @@ def sample_dem(arr, tr, x, y):
     return float(arr[row, col])
 
 
-def terrain_adjusted_length(dem, tr, x, y, hub_z, elev, azim, step, max_len):
+def raster_bytes(path: Path, extent: tuple[float, float, float, float] | None = None) -> int:
+    """Bytes `read_dem` allocates for band 1 of `path`, read whole or windowed to `extent`."""
+    with rasterio.open(path) as ds:
+        window = padded_window(ds, extent) if extent else None
+        width, height = (int(window.width), int(window.height)) if window else (ds.width, ds.height)
+        return width * height * np.dtype(ds.dtypes[0]).itemsize
+
+
+def memory_plan(req: RunRequest) -> dict[str, Any]:
+    """Estimated peak memory of the run against the RAM available now.
+
+    `mode` is `full` when it fits, `tiled` when it fits only with the screening rasters (DSM,
+    land cover, buildings) windowed around the site like the DEM clip, `refuse` otherwise.
+    """
+    import psutil
+
+    extent = grid_extent(req)
+    minx, miny, maxx, maxy = extent
+    cells = math.ceil((maxx - minx) / req.cellsize_m) * math.ceil((maxy - miny) / req.cellsize_m)
+    page_w, page_h = PAGE_SIZES_MM[req.output.page_size]
+    sources = len(req.turbines) + sum(len(g.turbines) for g in req.external_groups)
+    screening = [Path(p) for p in (req.dsm_path, req.landcover_path, req.buildings_path) if p and req.terrain_aware]
+    parts = {
+        "grids": cells * (GRID_BYTES_PER_CELL + 4 * len(meteo_years(req.realistic))),
+        "dem": raster_bytes(Path(req.dem_path), extent),
+        "screening": sum(raster_bytes(p) for p in screening),
+        "timesteps": len(sun_calendar()[0]) * TIMESTEP_BYTES,
+        "receptors": sources * len(req.receptors) * 8,
+        "map": int(page_w / MM_PER_INCH * req.output.dpi * page_h / MM_PER_INCH * req.output.dpi) * MAP_BYTES_PER_PX,
+    }
+    required = sum(parts.values())
+    tiled = required - parts["screening"] + sum(raster_bytes(p, extent) for p in screening)
+    available = psutil.virtual_memory().available
+    budget = available * MEMORY_HEADROOM
+    mode = "full" if required <= budget else "tiled" if tiled <= budget else "refuse"
+    mb = 2**20
+    return {
+        "mode": mode,
+        "required_mb": round(required / mb, 1),
+        "tiled_required_mb": round(tiled / mb, 1),
+        "available_mb": round(available / mb, 1),
+        "parts_mb": {k: round(v / mb, 1) for k, v in parts.items()},
+    }
+
+
+def memory_guidance(plan: dict[str, Any]) -> str:
+    parts = plan["parts_mb"]
+    largest = max(parts, key=parts.get)
+    hints = {
+        "grids": "use a coarser cell size or a smaller buffer",
+        "dem": "use a DEM with coarser resolution",
+        "screening": "use coarser DSM, land cover or building rasters",
+        "receptors": "split the receptors over several runs",
+        "map": "lower the print map DPI or page size",
+        "timesteps": "close other runs",
+    }
+    return (
+        f"The run needs about {plan['tiled_required_mb']:.0f} MB even with tiled raster reads, "
+        f"{plan['available_mb']:.0f} MB available: {hints[largest]} ({largest} {parts[largest]:.0f} MB), "
+        "or close other runs and lower the engine workers."
+    )
+
+
+def screening_extent(job: JobState, req: RunRequest):
+    """Extent to window screening rasters to under the memory guard, None to read them whole."""
+    return grid_extent(req) if job.memory_mode == "tiled" else None
+
+
+def screening_surface(job: JobState, req: RunRequest, dem, dem_tr, model_crs: CRS):
+    """Base screening surface, raised to roof level where building footprints were rasterized."""
+    base = base_screening_surface(job, req, dem, dem_tr, model_crs)
+    if not req.buildings_path:
+        return base
+    bld, bld_tr, _, _, _ = read_dem(Path(req.buildings_path), screening_extent(job, req))
+    log(job, "Building heights merged into the screening surface")
+    return lambda x, y: max(base(x, y), sample_dem(dem, dem_tr, x, y) + max(0.0, sample_dem(bld, bld_tr, x, y)))
+
+
+def base_screening_surface(job: JobState, req: RunRequest, dem, dem_tr, model_crs: CRS):
+    """Sampler for the surface that blocks shadow rays.
+
+    Base heights always come from the bare-earth DEM (DTM); when a DSM is given, vegetation
+    and buildings in it screen the rays instead of the bare terrain. Without a DSM a land
+    cover raster can stand in, each class adding its configured height on top of the DTM.
+    """
+    if not req.dsm_path and req.landcover_path:
+        return landcover_surface(job, req, dem, dem_tr, model_crs)
+    if not req.dsm_path:
+        return lambda x, y: sample_dem(dem, dem_tr, x, y)
+    dsm, dsm_tr, dsm_crs, _, _ = read_dem(Path(req.dsm_path), screening_extent(job, req))
+    if dsm_crs and dsm_crs != model_crs:
+        log(job, "Warning: DSM CRS differs from selected EPSG. Continuing with selected EPSG.")
+    return lambda x, y: sample_dem(dsm, dsm_tr, x, y)
+
+
+def landcover_surface(job: JobState, req: RunRequest, dem, dem_tr, model_crs: CRS):
+    lc, lc_tr, lc_crs, lc_nodata, _ = read_dem(Path(req.landcover_path), screening_extent(job, req))
+    if lc_crs and lc_crs != model_crs:
+        log(job, "Warning: land cover CRS differs from selected EPSG. Continuing with selected EPSG.")
+    # Nodata (and NaN) cells are unclassified: they add nothing and never reach `int`.
+    classes = lc.astype(np.float32)
+    if lc_nodata is not None:
+        classes[lc == lc_nodata] = np.nan
+    heights = {int(float(k)): v for k, v in req.landcover_heights.items()}
+    missing = sorted(set(np.unique(classes[np.isfinite(classes)]).astype(int).tolist()) - set(heights))
+    if missing:
+        log(job, f"Land cover classes without screening height (0 m): {missing[:20]}")
+    log(job, "Screening heights synthesized from land cover (no DSM)")
+
+    def surface(x, y):
+        cls = sample_dem(classes, lc_tr, x, y)
+        return sample_dem(dem, dem_tr, x, y) + (heights.get(int(cls), 0.0) if math.isfinite(cls) else 0.0)
+
+    return surface
+
+
+def terrain_adjusted_length(surface, x, y, hub_z, elev, azim, step, max_len):
     rad = math.radians((azim + 180) % 360)
     tan_e = math.tan(math.radians(elev))
     d = step
@@ -213,14 +888,15 @@ def terrain_adjusted_length(dem, tr, x, y, hub_z, elev, azim, step, max_len):
         px = x + d * math.sin(rad)
         py = y + d * math.cos(rad)
         z_ray = hub_z - d * tan_e
-        z_dem = sample_dem(dem, tr, px, py)
+        z_dem = surface(px, py)
         if z_ray <= z_dem:
             return d
         d += step
     return max_len
 
 
-def draw_shadow(grid, minx, miny, cell, x, y, azim, length, width):
+def draw_shadow(grid, minx, miny, cell, x, y, azim, length, width, weight=1.0):
+    """Adds the shadow of one timestep; a stack of grids (leading axis) takes a weight per grid."""
     rad = math.radians((azim + 180) % 360)
     steps = int(max(1, length / cell))
     spread = max(1, int((width / 2) / cell))
@@ -230,11 +906,39 @@ def draw_shadow(grid, minx, miny, cell, x, y, azim, length, width):
         py = y + d * math.cos(rad)
         col = int((px - minx) / cell)
         row = int((py - miny) / cell)
-        if 0 <= row < grid.shape[0] and 0 <= col < grid.shape[1]:
+        if 0 <= row < grid.shape[-2] and 0 <= col < grid.shape[-1]:
             for s in range(-spread, spread + 1):
                 c2 = col + s
-                if 0 <= c2 < grid.shape[1]:
-                    grid[row, c2] += 0.25
+                if 0 <= c2 < grid.shape[-1]:
+                    grid[..., row, c2] += 0.25 * weight
+
+
+def boundary_polygons(geometry: dict[str, Any]) -> list[list[list[list[float]]]]:
+    if geometry.get("type") == "Polygon":
+        return [geometry["coordinates"]]
+    if geometry.get("type") == "MultiPolygon":
+        return geometry["coordinates"]
+    raise EngineError("invalid_boundary", f"site_boundary must be a Polygon or MultiPolygon, not {geometry.get('type')}", type=geometry.get("type"))
+
+
+def boundary_mask(geometry: dict[str, Any], shape: tuple[int, int], minx: float, miny: float, cell: float) -> np.ndarray:
+    """Cells whose centre lies inside the boundary, rows from `miny` up; holes by even-odd."""
+    rows, cols = shape
+    xx, yy = np.meshgrid(minx + (np.arange(cols) + 0.5) * cell, miny + (np.arange(rows) + 0.5) * cell)
+    centres = np.column_stack([xx.ravel(), yy.ravel()])
+    mask = np.zeros(rows * cols, dtype=bool)
+    for polygon in boundary_polygons(geometry):
+        for ring in polygon:
+            mask ^= PlotPath(np.asarray(ring, dtype=float)[:, :2]).contains_points(centres)
+    return mask.reshape(shape)
+
+
+def boundary_area_m2(geometry: dict[str, Any]) -> float:
+    def ring_area(ring) -> float:
+        xy = np.asarray(ring, dtype=float)[:, :2]
+        return abs(float(np.dot(xy[:, 0], np.roll(xy[:, 1], -1)) - np.dot(np.roll(xy[:, 0], -1), xy[:, 1]))) / 2
+
+    return sum(ring_area(p[0]) - sum(ring_area(h) for h in p[1:]) for p in boundary_polygons(geometry))
 
 
 def write_asc(path: Path, grid: np.ndarray, minx: float, miny: float, cell: float):
@@ -274,7 +978,71 @@ def make_preview(path: Path, grid: np.ndarray):
     plt.close()
 
 
-def make_pdf(path: Path, req: RunRequest, stats: dict[str, float], outputs: dict[str, str]):
+def nice_length(m: float) -> float:
+    """Largest 1, 2 or 5 times a power of ten not above `m`."""
+    power = 10 ** math.floor(math.log10(m))
+    return max(f * power for f in (1, 2, 5) if f * power <= m)
+
+
+def map_layout(width_m: float, height_m: float, page_size: str) -> tuple[float, float, float]:
+    """Map frame width and height in mm on the page and the scale denominator (1:N).
+
+    The grid is fitted into the page minus margins and a strip for the colour bar, keeping one
+    ground metre the same length on paper in both directions.
+    """
+    page_w, page_h = PAGE_SIZES_MM[page_size]
+    avail_w, avail_h = page_w - 3 * MAP_MARGIN_MM - COLORBAR_MM, page_h - 2 * MAP_MARGIN_MM
+    denominator = max(width_m * 1000 / avail_w, height_m * 1000 / avail_h)
+    return width_m * 1000 / denominator, height_m * 1000 / denominator, denominator
+
+
+def make_map(
+    path: Path,
+    grid: np.ndarray,
+    minx: float,
+    miny: float,
+    cell: float,
+    output: OutputConfig,
+    boundary: dict[str, Any] | None = None,
+    mask: np.ndarray | None = None,
+) -> float:
+    """Print map at `output.dpi` on a landscape `output.page_size` page; returns the scale denominator.
+
+    With a site boundary, cells outside it are left blank and its outline is drawn.
+
+    The figure is laid out in mm rather than with tight bounding boxes, so the frame size on paper
+    and with it the scale bar stay exact at any resolution.
+    """
+    page_w, page_h = PAGE_SIZES_MM[output.page_size]
+    rows, cols = grid.shape
+    width_m, height_m = cols * cell, rows * cell
+    frame_w, frame_h, denominator = map_layout(width_m, height_m, output.page_size)
+    fig = plt.figure(figsize=(page_w / MM_PER_INCH, page_h / MM_PER_INCH))
+    left, bottom = MAP_MARGIN_MM / page_w, (page_h - frame_h) / 2 / page_h
+    ax = fig.add_axes((left, bottom, frame_w / page_w, frame_h / page_h))
+    shown = np.where(mask, grid, np.nan) if mask is not None else grid
+    im = ax.imshow(shown, cmap="inferno", origin="lower", extent=(minx, minx + width_m, miny, miny + height_m), aspect="auto")
+    ax.set_xlim(minx, minx + width_m)
+    ax.set_ylim(miny, miny + height_m)
+    ax.tick_params(labelsize=6)
+    for polygon in boundary_polygons(boundary) if boundary else []:
+        for ring in polygon:
+            xy = np.asarray(ring, dtype=float)
+            ax.plot(xy[:, 0], xy[:, 1], color="cyan", linewidth=0.8)
+    cax = fig.add_axes(((2 * MAP_MARGIN_MM + frame_w) / page_w, bottom, COLORBAR_MM / 3 / page_w, frame_h / page_h))
+    fig.colorbar(im, cax=cax, label="Annual shadow hours")
+
+    bar_m = nice_length(width_m / 5)
+    x0, y0 = minx + 0.04 * width_m, miny + 0.04 * height_m
+    ax.plot([x0, x0 + bar_m], [y0, y0], color="white", linewidth=3, solid_capstyle="butt")
+    label = f"{bar_m / 1000:g} km" if bar_m >= 1000 else f"{bar_m:g} m"
+    ax.text(x0, y0 + 0.015 * height_m, f"{label}  (1:{denominator:,.0f} on {output.page_size})", color="white", fontsize=7)
+    fig.savefig(path, dpi=output.dpi)
+    plt.close(fig)
+    return denominator
+
+
+def make_pdf(path: Path, req: RunRequest, stats: dict[str, float], outputs: dict[str, str], map_path: Path, scale: float, cumulative: list[dict[str, Any]] | None = None):
     c = canvas.Canvas(str(path), pagesize=A4)
     y = 800
     c.drawString(30, y, "Wind Shadow Studio - Report")
@@ -282,15 +1050,56 @@ def make_pdf(path: Path, req: RunRequest, stats: dict[str, float], outputs: dict
     c.drawString(30, y, f"EPSG: {req.epsg} | Cellsize: {req.cellsize_m} | Buffer: {req.buffer_m}")
     y -= 18
     c.drawString(30, y, f"Terrain-aware: {req.terrain_aware} | DEM: {req.dem_path}")
+    if req.dsm_path:
+        y -= 18
+        c.drawString(30, y, f"DSM (screening): {req.dsm_path}")
+    elif req.landcover_path:
+        y -= 18
+        c.drawString(30, y, f"Land cover (screening): {req.landcover_path}")
+    if req.buildings_path:
+        y -= 18
+        c.drawString(30, y, f"Buildings (screening): {req.buildings_path}")
+    years = meteo_years(req.realistic)
+    if years:
+        y -= 18
+        c.drawString(30, y, f"Realistic case: long-term average of {len(years)} meteorological years ({years[0].year}-{years[-1].year})")
+        if "interannual_std_max" in stats:
+            y -= 18
+            c.drawString(30, y, f"Inter-annual variability at receptors: standard deviation up to {stats['interannual_std_max']:.2f} h")
+    elif req.realistic:
+        y -= 18
+        c.drawString(30, y, f"Realistic case: operating fraction {req.realistic.operating_fraction:.2f}, sunshine/wind weighted")
     y -= 18
     c.drawString(30, y, f"Output format: {req.output.format}")
     y -= 18
     c.drawString(30, y, f"Stats min/max/mean: {stats['min']:.2f}/{stats['max']:.2f}/{stats['mean']:.2f}")
+    if req.site_boundary:
+        y -= 18
+        c.drawString(
+            30,
+            y,
+            f"Site boundary: {stats['boundary_area_m2'] / 10_000:.1f} ha | max inside {stats['boundary_max']:.2f} h, outside {stats['outside_max']:.2f} h",
+        )
+    if req.external_groups:
+        y -= 18
+        c.drawString(30, y, f"Cumulative assessment, external share max: {stats['external_max']:.2f} h")
+        for g in req.external_groups:
+            y -= 16
+            at_receptors = max((rg["hours_h"] for r in cumulative or [] for rg in r["groups"] if rg["name"] == g.name), default=None)
+            share = f" | up to {at_receptors:.2f} h at receptors" if at_receptors is not None else ""
+            c.drawString(40, y, f"- external ({g.status}) {g.name}: {', '.join(t.id for t in g.turbines)}{share}")
     y -= 24
     c.drawString(30, y, "Output files:")
     for k, v in outputs.items():
         y -= 16
         c.drawString(40, y, f"- {k}: {v}")
+    # The map page keeps the print resolution of `map.png`, so the scale holds when printed 1:1.
+    c.showPage()
+    page_w, page_h = (v / MM_PER_INCH * 72 for v in PAGE_SIZES_MM[req.output.page_size])
+    c.setPageSize((page_w, page_h))
+    c.drawImage(str(map_path), 0, 0, width=page_w, height=page_h)
+    c.setFont("Helvetica", 7)
+    c.drawString(20, 8, f"Scale 1:{scale:,.0f} at {req.output.page_size}, {req.output.dpi} DPI - print at 100%")
     c.save()
 
 
@@ -306,19 +1115,45 @@ def health():
     return {"status": "ok", "timezone": ROME_TZ, "year": TYPICAL_YEAR}
 
 
+# The uvicorn server of `run()`, stopped by `POST /shutdown`.
+SERVER: Any = None
+
+
+@app.post("/shutdown")
+def shutdown():
+    """Stops serving once this response is sent; unfinished runs resume from their last
+    checkpoint when the shell submits them to the next engine."""
+    if SERVER is not None:
+        SERVER.should_exit = True
+    return {"status": "stopping"}
+
+
 @app.post("/jobs/run")
-def run_job(req: RunRequest):
-    job_id = str(uuid.uuid4())
-    job = JobState(id=job_id, status="running", progress_message="Starting")
-    JOBS[job_id] = job
+def run_job(req: RunRequest, idempotency_key: str | None = Header(default=None)):
+    with SUBMISSIONS_LOCK:
+        known = SUBMISSIONS.get(idempotency_key) if idempotency_key else None
+        if known in JOBS:
+            return {"id": known}
+        job_id = str(uuid.uuid4())
+        job = JobState(id=job_id, status="running", progress_message="Starting", checkpoint=req.checkpoint)
+        JOBS[job_id] = job
+        if idempotency_key:
+            SUBMISSIONS[idempotency_key] = job_id
 
     def _worker():
         try:
-            rasterize(job, req)
+            if req.mode == "prime":
+                prime(job, req)
+            else:
+                rasterize(job, req)
         except Exception as exc:  # noqa: BLE001
             job.status = "error"
-            job.error = str(exc)
-            log(job, f"Error: {exc}")
+            job.error_detail = error_detail(exc, job.stage)
+            job.error = job.error_detail["message"]
+            log(job, f"Error ({job.error_detail['code']} during {job.stage}): {job.error}")
+        finally:
+            if job.profiler:
+                job.profiler.stopped.set()
 
     threading.Thread(target=_worker, daemon=True).start()
     return {"id": job_id}
@@ -328,13 +1163,18 @@ def run_job(req: RunRequest):
 def get_job(job_id: str):
     job = JOBS.get(job_id)
     if not job:
-        raise HTTPException(404, "job not found")
+        raise request_error(404, "job_not_found", "job not found")
     return {
         "id": job.id,
         "status": job.status,
         "progress_pct": job.progress_pct,
         "progress_message": job.progress_message,
         "error": job.error,
+        "error_detail": job.error_detail,
+        "stage": job.stage,
+        "profile": job.profile,
+        "interannual": job.interannual,
+        "cumulative": job.cumulative,
         "logs": job.logs[-400:],
         "outputs": job.outputs,
         "overlay_bounds": job.overlay_bounds,
@@ -342,17 +1182,99 @@ def get_job(job_id: str):
     }
 
 
+@app.post("/jobs/memory")
+def estimate_memory(req: RunRequest):
+    """`memory_plan` for a run before submitting it."""
+    return memory_plan(req)
+
+
+@app.post("/jobs/{job_id}/pause")
+def pause_job(job_id: str):
+    """Stops at the next timestep after checkpointing; resume by running the same request again."""
+    job = JOBS.get(job_id)
+    if not job:
+        raise request_error(404, "job_not_found", "job not found")
+    if not job.checkpoint:
+        raise request_error(409, "not_pausable", "job was started without a checkpoint key")
+    if job.status != "running":
+        raise request_error(409, "not_running", f"job is {job.status}")
+    job.pause_requested = True
+    return {"id": job.id}
+
+
 @app.get("/jobs/{job_id}/files/{kind}")
 def get_file(job_id: str, kind: str):
     job = JOBS.get(job_id)
     if not job:
-        raise HTTPException(404, "job not found")
+        raise request_error(404, "job_not_found", "job not found")
     p = job.outputs.get(kind)
     if not p:
-        raise HTTPException(404, "file kind not available")
+        raise request_error(404, "file_not_available", "file kind not available")
     return FileResponse(p)
 
 
+# Standard synthetic run for `/benchmark`: one turbine on a flat 2 km square, terrain-aware, a
+# full year at TIMESTEP_MIN. Changing it makes scores incomparable with earlier ones.
+BENCHMARK_SIDE_M = 2_000
+BENCHMARK_CELL_M = 10
+BENCHMARK_ORIGIN = (500_000.0, 5_000_000.0)
+BENCHMARK_EPSG = "EPSG:32632"
+
+
+def benchmark_request(root: Path, index: int) -> RunRequest:
+    dem = root / "flat_dem.asc"
+    if not dem.exists():
+        n = BENCHMARK_SIDE_M // BENCHMARK_CELL_M
+        row = " ".join(["100"] * n)
+        header = f"ncols {n}\nnrows {n}\nxllcorner {BENCHMARK_ORIGIN[0]}\nyllcorner {BENCHMARK_ORIGIN[1]}\ncellsize {BENCHMARK_CELL_M}\nNODATA_value -9999\n"
+        dem.write_text(header + "\n".join([row] * n) + "\n", encoding="utf-8")
+    project = root / f"run-{index}"
+    project.mkdir(exist_ok=True)
+    centre = BENCHMARK_SIDE_M / 2
+    turbine = Turbine(id="B1", x=BENCHMARK_ORIGIN[0] + centre, y=BENCHMARK_ORIGIN[1] + centre, hub_height_m=120, rotor_diameter_m=140)
+    return RunRequest(
+        project_path=str(project),
+        epsg=BENCHMARK_EPSG,
+        cellsize_m=BENCHMARK_CELL_M,
+        buffer_m=centre - BENCHMARK_CELL_M,
+        terrain_aware=True,
+        dem_path=str(dem),
+        turbines=[turbine],
+        output=OutputConfig(format="asc", dpi=72),
+    )
+
+
+@app.post("/benchmark")
+def benchmark(concurrency: int = 1):
+    """Wall time of `concurrency` copies of the standard synthetic run started together."""
+    if not 1 <= concurrency <= 8:
+        raise request_error(422, "invalid_concurrency", "concurrency must be between 1 and 8")
+    root = runtime_dir() / "benchmark"
+    root.mkdir(parents=True, exist_ok=True)
+    requests = [benchmark_request(root, i) for i in range(concurrency)]
+    jobs = [JobState(id=f"benchmark-{i}", status="running") for i in range(concurrency)]
+    # The DEM clip is cached before timing, as it would be for a primed project.
+    clipped_dem(Path(requests[0].dem_path), grid_extent(requests[0]))
+
+    def timed(job: JobState, req: RunRequest) -> None:
+        try:
+            rasterize(job, req)
+        except Exception as exc:  # noqa: BLE001
+            job.error = str(exc)
+
+    threads = [threading.Thread(target=timed, args=(job, req)) for job, req in zip(jobs, requests)]
+    started = time.perf_counter()
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join()
+    seconds = time.perf_counter() - started
+    failed = [job for job in jobs if job.status != "done"]
+    if failed:
+        raise request_error(500, "benchmark_failed", failed[0].error or "benchmark run did not finish")
+    return {"concurrency": concurrency, "seconds": round(seconds, 3), "cpu_count": os.cpu_count()}
+
+
 def find_free_port() -> int:
     import socket
 
@@ -361,19 +1283,76 @@ def find_free_port() -> int:
         return int(s.getsockname()[1])
 
 
-def write_port_file(runtime_dir: Path, port: int) -> None:
+def write_port_file(runtime_dir: Path, port: int, grpc_port: int | None = None) -> None:
+    """Ports with this process id and the shell's spawn token, by which the shell tells the
+    file from one left by a crash."""
     runtime_dir.mkdir(parents=True, exist_ok=True)
-    (runtime_dir / "port.json").write_text(json.dumps({"port": port}), encoding="utf-8")
+    ports = {"port": port, "pid": os.getpid(), "token": os.environ.get("WSS_ENGINE_TOKEN", "")}
+    if grpc_port is not None:
+        ports["grpc_port"] = grpc_port
+    # One engine runs per open project window; each gets its own port file name from the shell.
+    name = os.environ.get("WSS_PORT_FILE", "port.json")
+    (runtime_dir / name).write_text(json.dumps(ports), encoding="utf-8")
+
+
+LOOPBACK_HOSTS = {"127.0.0.1", "localhost", "::1"}
+PARENT_CHECK_S = 2
+
+
+def exit_with_parent(pid: int) -> None:
+    """Exits when the shell process `pid` is gone, so a crashed shell leaves no engine behind."""
+    import psutil
+
+    def watch():
+        while psutil.pid_exists(pid):
+            time.sleep(PARENT_CHECK_S)
+        os._exit(0)
+
+    threading.Thread(target=watch, daemon=True).start()
+
+
+def tls_options() -> dict:
+    """uvicorn TLS arguments from WSS_TLS_CERT / WSS_TLS_KEY (PEM), empty when unset."""
+    cert, key = os.environ.get("WSS_TLS_CERT"), os.environ.get("WSS_TLS_KEY")
+    if bool(cert) != bool(key):
+        raise SystemExit("WSS_TLS_CERT and WSS_TLS_KEY must be set together")
+    return {"ssl_certfile": cert, "ssl_keyfile": key} if cert else {}
 
 
 def run():
+    global SERVER
     import uvicorn
 
-    runtime = Path(os.environ.get("WSS_RUNTIME_DIR", Path.home() / ".windshadowstudio"))
-    port = find_free_port()
-    write_port_file(runtime, port)
+    runtime = runtime_dir()
+    # Remote deployments listen on WSS_HOST:WSS_PORT; dwelling coordinates must not travel in
+    # plaintext, so any non-loopback address needs a certificate, and anyone reaching the port
+    # could stop the engine or write to any project_path, so it also needs a token.
+    host = os.environ.get("WSS_HOST", "127.0.0.1")
+    tls = tls_options()
+    if host not in LOOPBACK_HOSTS and not tls:
+        raise SystemExit(f"refusing to listen on {host} without WSS_TLS_CERT and WSS_TLS_KEY")
+    if host not in LOOPBACK_HOSTS and not api_token():
+        raise SystemExit(f"refusing to listen on {host} without WSS_API_TOKEN")
+    port = int(os.environ.get("WSS_PORT") or 0) or find_free_port()
+    grpc_port = None
+    # Asked for by shells built with the `grpc` feature; HTTP stays available either way.
+    if os.environ.get("WSS_GRPC") == "1":
+        try:
+            from .grpc_server import serve
+
+            grpc_port = find_free_port()
+            grpc_server = serve(grpc_port)
+        except ImportError as exc:
+            grpc_port = None
+            print(f"gRPC unavailable ({exc}), HTTP only", flush=True)
+    if os.environ.get("WSS_PARENT_PID"):
+        exit_with_parent(int(os.environ["WSS_PARENT_PID"]))
+    SERVER = uvicorn.Server(uvicorn.Config(app, host=host, port=port, log_level="info", **tls))
+    write_port_file(runtime, port, grpc_port)
     print(f"ENGINE_PORT={port}", flush=True)
-    uvicorn.run(app, host="127.0.0.1", port=port, log_level="info")
+    SERVER.run()
+    if grpc_port is not None:
+        grpc_server.stop(grace=1)
 
 
 if __name__ == "__main__":
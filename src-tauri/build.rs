@@ -1,3 +1,5 @@
 fn main() {
+  #[cfg(feature = "grpc")]
+  tonic_build::configure().build_server(false).compile_protos(&["../proto/engine.proto"], &["../proto"]).expect("compile proto/engine.proto");
   tauri_build::build()
 }
@@ -0,0 +1,87 @@
+//! Registry of shell actions for the command palette: `list_actions` describes what can be run
+//! right now, `invoke_action` runs one by id through the same commands the buttons use.
+
+use crate::{archive, audit, backup, jobs, publish, recent, scenarios, settings, viewer::ViewerMode, windpro, ProjectConfig};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+#[derive(Serialize)]
+pub struct Action {
+    /// Stable id passed to `invoke_action`; parameterised actions carry the parameter after a `:`.
+    id: String,
+    title: String,
+    group: &'static str,
+    /// Accelerator hint in Tauri syntax (`CmdOrCtrl+S`); the window binds the keys.
+    shortcut: Option<&'static str>,
+    enabled: bool,
+}
+
+/// `(id, title, group, shortcut, needs a project, edits or sends data)`
+type StaticAction = (&'static str, &'static str, &'static str, Option<&'static str>, bool, bool);
+
+const STATIC_ACTIONS: [StaticAction; 12] = [
+    ("project.new", "New project", "project", Some("CmdOrCtrl+N"), false, true),
+    ("project.open", "Open project", "project", Some("CmdOrCtrl+O"), false, false),
+    ("project.demo", "Open demo project", "project", None, false, false),
+    ("project.save", "Save project", "project", Some("CmdOrCtrl+S"), true, true),
+    ("archive.import", "Import project archive", "project", None, false, true),
+    ("archive.export", "Export project archive", "export", None, true, true),
+    ("windpro.export", "Export to windPRO", "export", None, true, true),
+    ("audit.export", "Export audit log", "export", None, true, false),
+    ("backup.now", "Back up project now", "project", None, true, true),
+    ("run.calculate", "Run calculation", "run", Some("F5"), true, true),
+    ("run.scenarios", "Run all scenarios", "run", Some("Shift+F5"), true, true),
+    ("scenario.base", "Switch to base project", "scenario", None, true, false),
+];
+
+fn action(id: String, title: String, group: &'static str, shortcut: Option<&'static str>, enabled: bool) -> Action {
+    Action { id, title, group, shortcut, enabled }
+}
+
+/// Actions for the current state: static ones, one switch per scenario and one publish per target.
+#[tauri::command]
+pub fn list_actions(cfg: Option<ProjectConfig>, app: AppHandle, window: tauri::Window, viewer: tauri::State<ViewerMode>) -> Vec<Action> {
+    let open = cfg.as_ref().is_some_and(|c| !c.project_path.is_empty());
+    let editable = !viewer.is_on(window.label());
+    let mut out: Vec<Action> = STATIC_ACTIONS
+        .iter()
+        .map(|(id, title, group, shortcut, needs_project, edits)| action(id.to_string(), title.to_string(), group, *shortcut, (open || !needs_project) && (editable || !edits)))
+        .collect();
+    if let Some(c) = cfg.as_ref() {
+        out.iter_mut().filter(|a| a.id == "run.scenarios" || a.id == "scenario.base").for_each(|a| a.enabled &= !c.scenarios.is_empty());
+        out.extend(c.scenarios.iter().map(|s| action(format!("scenario.activate:{}", s.name), format!("Switch to scenario {}", s.name), "scenario", None, open)));
+    }
+    out.extend(settings::load(&app).publish_targets.into_iter().map(|t| action(format!("publish:{}", t.name), format!("Publish results to {}", t.name), "export", None, open && editable)));
+    out
+}
+
+fn to_value<T: Serialize>(result: Result<T, String>) -> Result<Value, String> {
+    result.and_then(|v| serde_json::to_value(v).map_err(|e| e.to_string()))
+}
+
+/// Runs an action and returns `{ action, result }`; `result` is what the underlying command returns.
+#[tauri::command(async)]
+pub fn invoke_action(id: String, cfg: Option<ProjectConfig>, window: tauri::Window, app: AppHandle) -> Result<Value, String> {
+    let viewer = || app.state::<ViewerMode>();
+    let project = || cfg.clone().filter(|c| !c.project_path.is_empty()).ok_or("open a project first".to_string());
+    let (name, param) = id.split_once(':').map_or((id.as_str(), None), |(n, p)| (n, Some(p.to_string())));
+    let result = match name {
+        "project.new" | "project.open" | "project.demo" => to_value(crate::choose_project(&name["project.".len()..], window, app.clone(), viewer())),
+        "project.save" => to_value(crate::project::save_project(project()?, None, app.clone(), window, viewer())),
+        "archive.import" => to_value(archive::import_archive(None, None, window, app.clone(), viewer())),
+        "archive.export" => to_value(archive::export_archive(project()?, None, app.clone(), window, viewer())),
+        "windpro.export" => to_value(windpro::export_windpro(project()?, app.clone(), window, viewer())),
+        "audit.export" => to_value(audit::export_audit_log(project()?.project_path)),
+        "backup.now" => to_value(backup::backup_now(app.clone(), project()?.project_path, window, viewer())),
+        "run.calculate" => to_value(jobs::submit_run(project()?, None, None, app.clone(), app.state(), window, viewer())),
+        "run.scenarios" => to_value(scenarios::run_scenarios(project()?, None, app.clone(), app.state(), window, viewer())),
+        "scenario.base" | "scenario.activate" => {
+            recent::set_active_scenario(app.clone(), project()?.project_path, param.clone())?;
+            Ok(json!({ "active_scenario": param }))
+        }
+        "publish" => to_value(publish::publish_results(app.clone(), project()?, param.ok_or("publish needs a target")?, None, window, viewer())),
+        _ => Err(format!("unknown action: {id}")),
+    }?;
+    Ok(json!({ "action": id, "result": result }))
+}
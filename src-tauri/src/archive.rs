@@ -0,0 +1,221 @@
+//! Self-contained project archives (`.wssproj.zip`): the project file with relative paths, the
+//! input rasters it references and `outputs/`. A password encrypts every entry with AES-256,
+//! since bundles with dwelling coordinates are routinely emailed.
+
+use crate::{audit, disk, project, viewer::ViewerMode, workspace, ProjectConfig};
+use serde::Serialize;
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+use tauri::{AppHandle, State};
+use zip::{result::ZipError, write::SimpleFileOptions, AesMode, CompressionMethod, ZipArchive, ZipWriter};
+
+const PROJECT_ENTRY: &str = project::PROJECT_FILE;
+const INPUTS_DIR: &str = "inputs";
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ArchiveImport {
+    /// The archive is encrypted; call again with the same `path` and a password.
+    PasswordRequired { path: String },
+    Imported { cfg: Box<ProjectConfig>, files: usize },
+}
+
+/// Archive entry name for a file inside the project folder, `None` for files elsewhere.
+fn entry_name(project: &Path, file: &Path) -> Option<String> {
+    let rel = file.strip_prefix(project).ok()?;
+    Some(rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+}
+
+/// `inputs/<file name>` for a file outside the project, suffixed `-2`, `-3`, ... while another
+/// file already took the name.
+fn outside_name(src: &Path, files: &[(String, PathBuf)]) -> String {
+    let stem = src.file_stem().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = src.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (1..)
+        .map(|i| if i == 1 { format!("{INPUTS_DIR}/{stem}{ext}") } else { format!("{INPUTS_DIR}/{stem}-{i}{ext}") })
+        .find(|name| !files.iter().any(|(n, _)| n == name))
+        .unwrap_or_default()
+}
+
+/// Files to pack as (entry name, path on disk), and the config rewritten to entry names.
+fn layout(cfg: &ProjectConfig) -> (ProjectConfig, Vec<(String, PathBuf)>) {
+    let project = PathBuf::from(&cfg.project_path);
+    let mut packed = cfg.clone();
+    let mut files: Vec<(String, PathBuf)> = vec![];
+    for path in [&mut packed.dem_path, &mut packed.dsm_path, &mut packed.landcover_path, &mut packed.buildings_path] {
+        if path.is_empty() {
+            continue;
+        }
+        let src = PathBuf::from(path.as_str());
+        let name = match files.iter().find(|(_, f)| *f == src) {
+            Some((name, _)) => name.clone(),
+            None => {
+                let name = entry_name(&project, &src).unwrap_or_else(|| outside_name(&src, &files));
+                files.push((name.clone(), src));
+                name
+            }
+        };
+        *path = name;
+    }
+    for r in &mut packed.external_results {
+        if let Some(name) = entry_name(&project, Path::new(&r.path)) {
+            r.path = name;
+        }
+    }
+    let outputs = walkdir::WalkDir::new(project.join("outputs")).into_iter().filter_map(Result::ok).filter(|e| e.file_type().is_file());
+    for e in outputs {
+        if let Some(name) = entry_name(&project, e.path()).filter(|n| !files.iter().any(|(f, _)| f == n)) {
+            files.push((name, e.into_path()));
+        }
+    }
+    let log = project.join(audit::LOG_FILE);
+    if log.is_file() {
+        files.push((audit::LOG_FILE.to_string(), log));
+    }
+    packed.project_path = String::new();
+    (packed, files)
+}
+
+pub fn export(cfg: &ProjectConfig, dest: &Path, password: Option<&str>) -> Result<usize, String> {
+    let (packed, files) = layout(cfg);
+    let mut zip = ZipWriter::new(fs::File::create(dest).map_err(|e| e.to_string())?);
+    let mut options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    if let Some(password) = password {
+        options = options.with_aes_encryption(AesMode::Aes256, password);
+    }
+    zip.start_file(PROJECT_ENTRY, options).map_err(|e| e.to_string())?;
+    zip.write_all(serde_json::to_string_pretty(&packed).map_err(|e| e.to_string())?.as_bytes()).map_err(|e| e.to_string())?;
+    for (name, path) in &files {
+        let data = fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        zip.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+        zip.write_all(&data).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(files.len() + 1)
+}
+
+fn is_encrypted(archive: &mut ZipArchive<fs::File>) -> bool {
+    (0..archive.len()).any(|i| archive.by_index_raw(i).is_ok_and(|f| f.encrypted()))
+}
+
+fn read_entry(archive: &mut ZipArchive<fs::File>, i: usize, password: Option<&str>) -> Result<(Option<PathBuf>, Vec<u8>), String> {
+    let encrypted = archive.by_index_raw(i).map_err(|e| e.to_string())?.encrypted();
+    let mut file = match (encrypted, password) {
+        (true, Some(p)) => archive.by_index_decrypt(i, p.as_bytes()),
+        (true, None) => return Err("the archive is password protected".to_string()),
+        (false, _) => archive.by_index(i),
+    }
+    .map_err(|e| match e {
+        ZipError::InvalidPassword => "wrong archive password".to_string(),
+        e => e.to_string(),
+    })?;
+    let mut data = vec![];
+    file.read_to_end(&mut data).map_err(|e| e.to_string())?;
+    Ok((file.enclosed_name(), data))
+}
+
+fn open(path: &Path) -> Result<ZipArchive<fs::File>, String> {
+    ZipArchive::new(fs::File::open(path).map_err(|e| e.to_string())?).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Extracts into `dest` and returns the config with absolute paths again, see `project::read`.
+pub fn import(path: &Path, dest: &Path, password: Option<&str>) -> Result<(ProjectConfig, usize), String> {
+    let mut archive = open(path)?;
+    let mut cfg: Option<ProjectConfig> = None;
+    for i in 0..archive.len() {
+        let (name, data) = read_entry(&mut archive, i, password)?;
+        // `enclosed_name` rejects absolute paths and `..`, so nothing lands outside `dest`.
+        let Some(name) = name else { continue };
+        if name == Path::new(PROJECT_ENTRY) {
+            cfg = Some(serde_json::from_slice(&data).map_err(|e| format!("{PROJECT_ENTRY}: {e}"))?);
+        }
+        let out = dest.join(&name);
+        if let Some(dir) = out.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        fs::write(&out, data).map_err(|e| format!("{}: {e}", out.display()))?;
+    }
+    // The extracted project file keeps the relative paths; `project::read` resolves them the
+    // same way when the project is reopened.
+    let mut cfg = cfg.ok_or(format!("not a project archive: {PROJECT_ENTRY} missing"))?;
+    project::resolve_paths(&mut cfg, dest);
+    Ok((cfg, archive.len()))
+}
+
+/// An empty password exports without encryption.
+#[tauri::command]
+pub fn export_archive(cfg: ProjectConfig, password: Option<String>, app: AppHandle, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Option<String>, String> {
+    viewer.check(window.label())?;
+    let name = Path::new(&cfg.project_path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or("project".to_string());
+    let Some(dest) = rfd::FileDialog::new().add_filter("Project archive", &["zip"]).set_file_name(format!("{name}.wssproj.zip")).save_file() else {
+        return Ok(None);
+    };
+    let bytes = layout(&cfg).1.iter().filter_map(|(_, p)| fs::metadata(p).ok()).map(|m| m.len()).sum();
+    disk::ensure_space(&app, dest.parent().unwrap_or(&dest), bytes, "the archive")?;
+    let password = password.filter(|p| !p.is_empty());
+    let files = export(&cfg, &dest, password.as_deref())?;
+    audit::record(&app, &cfg, "archive_exported", serde_json::json!({ "path": dest, "files": files, "encrypted": password.is_some() }));
+    Ok(Some(dest.display().to_string()))
+}
+
+/// Picks an archive (unless `path` is given from a previous `password_required` answer) and
+/// extracts it into a new folder next to the chosen destination, then opens it in `window`.
+#[tauri::command]
+pub fn import_archive(path: Option<String>, password: Option<String>, window: tauri::Window, app: AppHandle, viewer: State<ViewerMode>) -> Result<Option<ArchiveImport>, String> {
+    viewer.check(window.label())?;
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => match rfd::FileDialog::new().add_filter("Project archive", &["zip"]).pick_file() {
+            Some(p) => p,
+            None => return Ok(None),
+        },
+    };
+    let password = password.filter(|p| !p.is_empty());
+    if password.is_none() && is_encrypted(&mut open(&path)?) {
+        return Ok(Some(ArchiveImport::PasswordRequired { path: path.display().to_string() }));
+    }
+    let Some(folder) = rfd::FileDialog::new().pick_folder() else {
+        return Ok(None);
+    };
+    let stem = path.file_name().map(|n| n.to_string_lossy().trim_end_matches(".zip").trim_end_matches(".wssproj").to_string()).unwrap_or("project".to_string());
+    let dest = folder.join(stem);
+    if dest.join(PROJECT_ENTRY).exists() {
+        return Err(format!("{} already contains a project", dest.display()));
+    }
+    let (cfg, files) = import(&path, &dest, password.as_deref())?;
+    workspace::claim(&app, window.label(), &cfg.project_path)?;
+    crate::project_opened(&app, &viewer, &cfg, "open", window.label());
+    audit::note(&cfg.project_path, "archive_imported", serde_json::json!({ "from": path, "files": files }));
+    Ok(Some(ArchiveImport::Imported { cfg: Box::new(cfg), files }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outside_inputs_with_the_same_file_name_get_their_own_entries() {
+        let mut cfg: ProjectConfig = serde_json::from_value(serde_json::json!({
+            "project_path": "/projects/park",
+            "epsg": "EPSG:32632",
+            "cellsize_m": 10.0,
+            "buffer_m": 2000.0,
+            "terrain_aware": true,
+            "dem_path": "/data/a/terrain.tif",
+            "dsm_path": "/data/b/terrain.tif",
+            "turbines": [],
+            "output": { "format": "asc" },
+        }))
+        .unwrap();
+        cfg.landcover_path = cfg.dem_path.clone();
+
+        let (packed, files) = layout(&cfg);
+        assert_eq!(packed.dem_path, "inputs/terrain.tif");
+        assert_eq!(packed.dsm_path, "inputs/terrain-2.tif");
+        assert_eq!(packed.landcover_path, "inputs/terrain.tif");
+        assert_eq!(files, vec![("inputs/terrain.tif".to_string(), PathBuf::from("/data/a/terrain.tif")), ("inputs/terrain-2.tif".to_string(), PathBuf::from("/data/b/terrain.tif"))]);
+    }
+}
@@ -0,0 +1,156 @@
+//! Append-only audit log per project (`audit.log.jsonl`): who did what and when. Each line
+//! carries the SHA-256 of the previous one, so a line edited or removed in the middle breaks the
+//! chain visibly. The hash is not keyed: it catches accidental edits, not deliberate rewrites,
+//! and a truncated tail goes unnoticed.
+//!
+//! Editing happens in the window, so configuration changes are recorded as differences
+//! against the last configuration the shell saw for the project (or the saved project file).
+
+use crate::{project, ProjectConfig};
+use fs2::FileExt;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::{AppHandle, Manager};
+
+pub const LOG_FILE: &str = "audit.log.jsonl";
+/// Fields that change with every session or are not user edits.
+const UNTRACKED: [&str; 2] = ["project_path", "turbines"];
+
+/// Appends must not interleave: two entries linked to the same `prev` read as a broken chain.
+static APPENDING: Mutex<()> = Mutex::new(());
+
+#[derive(Default)]
+pub struct AuditState {
+    seen: Mutex<HashMap<String, Value>>,
+}
+
+fn log_path(project: &str) -> PathBuf {
+    Path::new(project).join(LOG_FILE)
+}
+
+fn user() -> String {
+    let name = std::env::var("USERNAME").or_else(|_| std::env::var("USER")).unwrap_or_default();
+    match std::env::var("COMPUTERNAME").or_else(|_| std::env::var("HOSTNAME")) {
+        Ok(host) if !host.is_empty() => format!("{name}@{host}"),
+        _ => name,
+    }
+}
+
+fn append(project: &str, action: &str, details: Value) -> Result<(), String> {
+    if project.is_empty() {
+        return Ok(());
+    }
+    let path = log_path(project);
+    let _appending = APPENDING.lock().unwrap_or_else(|e| e.into_inner());
+    let mut f = OpenOptions::new().create(true).read(true).append(true).open(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    // Also against another instance of the app (a headless render) writing the same log.
+    f.lock_exclusive().map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut raw = String::new();
+    f.read_to_string(&mut raw).map_err(|e| format!("{}: {e}", path.display()))?;
+    let prev = raw.lines().last().map(|l| hex::encode(Sha256::digest(l.as_bytes())));
+    let entry = json!({
+        "ts": chrono::Local::now().to_rfc3339(),
+        "user": user(),
+        "action": action,
+        "details": details,
+        "prev": prev,
+    });
+    let written = writeln!(f, "{entry}").map_err(|e| e.to_string());
+    let _ = f.unlock();
+    written
+}
+
+fn turbine_changes(before: &Value, after: &Value) -> Vec<Value> {
+    let index = |v: &Value| -> HashMap<String, Value> { v.as_array().into_iter().flatten().map(|t| (t["id"].as_str().unwrap_or_default().to_string(), t.clone())).collect() };
+    let (old, new) = (index(before), index(after));
+    let mut changes = vec![];
+    for (id, t) in &new {
+        match old.get(id) {
+            None => changes.push(json!({ "turbine": id, "change": "added", "to": t })),
+            Some(o) if o != t => changes.push(json!({ "turbine": id, "change": "modified", "from": o, "to": t })),
+            _ => {}
+        }
+    }
+    changes.extend(old.keys().filter(|id| !new.contains_key(*id)).map(|id| json!({ "turbine": id, "change": "removed" })));
+    changes.sort_by_key(|c| c["turbine"].as_str().unwrap_or_default().to_string());
+    changes
+}
+
+/// Logs what changed since the last configuration seen for this project.
+fn record_changes(app: &AppHandle, cfg: &ProjectConfig) -> Result<(), String> {
+    let current = serde_json::to_value(cfg).map_err(|e| e.to_string())?;
+    let state = app.state::<AuditState>();
+    let mut seen = state.seen.lock().map_err(|e| e.to_string())?;
+    let saved = || fs::read_to_string(Path::new(&cfg.project_path).join(project::PROJECT_FILE)).ok().and_then(|s| serde_json::from_str::<Value>(&s).ok());
+    let Some(before) = seen.get(&cfg.project_path).cloned().or_else(saved) else {
+        seen.insert(cfg.project_path.clone(), current);
+        return Ok(());
+    };
+    let turbines = turbine_changes(&before["turbines"], &current["turbines"]);
+    if !turbines.is_empty() {
+        append(&cfg.project_path, "turbines_changed", json!(turbines))?;
+    }
+    let settings: serde_json::Map<String, Value> = current
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(k, v)| !UNTRACKED.contains(&k.as_str()) && before.get(k.as_str()).unwrap_or(&Value::Null) != *v)
+        .map(|(k, v)| (k.clone(), json!({ "from": before[k.as_str()], "to": v })))
+        .collect();
+    if !settings.is_empty() {
+        append(&cfg.project_path, "settings_changed", Value::Object(settings))?;
+    }
+    seen.insert(cfg.project_path.clone(), current);
+    Ok(())
+}
+
+/// Logging failures are reported but never stop the action being audited.
+fn report(logged: Result<(), String>) {
+    if let Err(e) = logged {
+        eprintln!("audit log: {e}");
+    }
+}
+
+/// Records an action that carries no configuration.
+pub fn note(project: &str, action: &str, details: Value) {
+    report(append(project, action, details));
+}
+
+/// Records pending configuration changes, then the action itself.
+pub fn record(app: &AppHandle, cfg: &ProjectConfig, action: &str, details: Value) {
+    report(record_changes(app, cfg).and_then(|_| append(&cfg.project_path, action, details)));
+}
+
+#[tauri::command]
+pub fn get_audit_log(project_path: String) -> Result<Vec<Value>, String> {
+    let Ok(raw) = fs::read_to_string(log_path(&project_path)) else { return Ok(vec![]) };
+    raw.lines().map(|l| serde_json::from_str(l).map_err(|e| e.to_string())).collect()
+}
+
+/// Exports the log as CSV (`ts;user;action;details`) and reports whether the hash chain is intact.
+#[tauri::command]
+pub fn export_audit_log(project_path: String) -> Result<Option<String>, String> {
+    let raw = fs::read_to_string(log_path(&project_path)).map_err(|e| format!("no audit log: {e}"))?;
+    let Some(dest) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).set_file_name("audit_log.csv").save_file() else {
+        return Ok(None);
+    };
+    let mut w = csv::WriterBuilder::new().delimiter(b';').from_path(&dest).map_err(|e| e.to_string())?;
+    w.write_record(["ts", "user", "action", "details", "chain"]).map_err(|e| e.to_string())?;
+    let mut prev: Option<String> = None;
+    for line in raw.lines() {
+        let entry: Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        let chain = if entry["prev"].as_str().map(str::to_string) == prev { "ok" } else { "broken" };
+        let text = |k: &str| entry[k].as_str().unwrap_or_default().to_string();
+        w.write_record([text("ts"), text("user"), text("action"), entry["details"].to_string(), chain.to_string()]).map_err(|e| e.to_string())?;
+        prev = Some(hex::encode(Sha256::digest(line.as_bytes())));
+    }
+    w.flush().map_err(|e| e.to_string())?;
+    Ok(Some(dest.display().to_string()))
+}
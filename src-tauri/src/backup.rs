@@ -0,0 +1,172 @@
+//! Scheduled backups of the projects opened in this session (project file plus key results) to
+//! a secondary folder, typically a network share, with a retention limit per project.
+
+use crate::{disk, settings, viewer::ViewerMode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{Duration, SystemTime},
+};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const TICK: Duration = Duration::from_secs(60);
+const SNAPSHOT_FORMAT: &str = "%Y%m%d-%H%M%S";
+/// Result files worth keeping from `outputs/`; inputs (DEM, DSM) are usually archived elsewhere and large.
+const RESULT_EXTENSIONS: [&str; 5] = ["asc", "tif", "png", "pdf", "csv"];
+/// SHA-256 of every file in a snapshot by relative path, written next to them.
+const MANIFEST: &str = "manifest.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BackupSettings {
+    /// Destination root; each project gets `<path>/<project folder>/<timestamp>/`.
+    pub path: String,
+    pub interval_h: f64,
+    /// Snapshots kept per project, newest first.
+    pub keep: usize,
+}
+
+#[derive(Default)]
+pub struct BackupState {
+    projects: Mutex<BTreeSet<PathBuf>>,
+    last_run: Mutex<Option<SystemTime>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct BackupReport {
+    project: String,
+    /// `None` when nothing changed since the previous snapshot.
+    snapshot: Option<String>,
+    files: usize,
+    pruned: usize,
+}
+
+/// Registers a project for the scheduled backups.
+pub fn track(app: &AppHandle, project_path: &str) {
+    if let Ok(mut projects) = app.state::<BackupState>().projects.lock() {
+        projects.insert(PathBuf::from(project_path));
+    }
+}
+
+fn sources(project: &Path) -> Vec<PathBuf> {
+    let outputs = fs::read_dir(project.join("outputs")).into_iter().flatten().filter_map(Result::ok).map(|e| e.path());
+    [project.join("project.wssproj.json")]
+        .into_iter()
+        .chain(outputs.filter(|p| p.extension().and_then(|e| e.to_str()).is_some_and(|e| RESULT_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// What `files` hash to now, keyed like the snapshot layout.
+fn manifest(project: &Path, files: &[PathBuf]) -> Result<BTreeMap<String, String>, String> {
+    files
+        .iter()
+        .map(|f| {
+            let rel = f.strip_prefix(project).map_err(|e| e.to_string())?;
+            let data = fs::read(f).map_err(|e| format!("{}: {e}", f.display()))?;
+            Ok((rel.to_string_lossy().replace('\\', "/"), hex::encode(Sha256::digest(data))))
+        })
+        .collect()
+}
+
+/// Completed snapshot folders, oldest first (timestamps sort lexically).
+fn snapshots(dest: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dest).into_iter().flatten().filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_dir() && p.extension().is_none_or(|e| e != "partial")).collect();
+    dirs.sort();
+    dirs
+}
+
+fn backup_project(app: &AppHandle, project: &Path, cfg: &BackupSettings) -> Result<BackupReport, String> {
+    let name = project.file_name().map(|n| n.to_string_lossy().to_string()).ok_or("project folder has no name")?;
+    let dest = Path::new(&cfg.path).join(&name);
+    let files = sources(project);
+    if files.is_empty() {
+        return Err(format!("{}: nothing to back up", project.display()));
+    }
+    // Compared by content: copying or restoring a project keeps or rewinds mtimes, and a
+    // deleted result does not make the folder any newer.
+    let current = manifest(project, &files)?;
+    let last = snapshots(&dest).pop();
+    let previous: Option<BTreeMap<String, String>> = last.and_then(|dir| fs::read_to_string(dir.join(MANIFEST)).ok()).and_then(|raw| serde_json::from_str(&raw).ok());
+    let unchanged = previous.as_ref() == Some(&current);
+    let mut snapshot = None;
+    if !unchanged {
+        let bytes = files.iter().filter_map(|f| fs::metadata(f).ok()).map(|m| m.len()).sum();
+        disk::ensure_space(app, &dest, bytes, "the backup")?;
+        // Copied under a `.partial` name first, so an interrupted copy never counts as a snapshot.
+        let dir = dest.join(chrono::Local::now().format(SNAPSHOT_FORMAT).to_string());
+        let partial = dir.with_extension("partial");
+        fs::create_dir_all(partial.join("outputs")).map_err(|e| format!("{}: {e}", partial.display()))?;
+        for f in &files {
+            let rel = f.strip_prefix(project).map_err(|e| e.to_string())?;
+            fs::copy(f, partial.join(rel)).map_err(|e| format!("{}: {e}", f.display()))?;
+        }
+        fs::write(partial.join(MANIFEST), serde_json::to_string_pretty(&current).map_err(|e| e.to_string())?).map_err(|e| format!("{}: {e}", partial.display()))?;
+        fs::rename(&partial, &dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        snapshot = Some(dir.display().to_string());
+    }
+    let all = snapshots(&dest);
+    let excess = all.len().saturating_sub(cfg.keep.max(1));
+    for old in &all[..excess] {
+        fs::remove_dir_all(old).map_err(|e| format!("{}: {e}", old.display()))?;
+    }
+    Ok(BackupReport { project: project.display().to_string(), snapshot, files: files.len(), pruned: excess })
+}
+
+fn run_all(app: &AppHandle, cfg: &BackupSettings) {
+    let projects: Vec<PathBuf> = app.state::<BackupState>().projects.lock().map(|p| p.iter().cloned().collect()).unwrap_or_default();
+    for project in projects {
+        // An unreachable share is reported and retried on the next interval.
+        let _ = match backup_project(app, &project, cfg) {
+            Ok(report) => app.emit("backup://done", report),
+            Err(e) => app.emit("backup://failed", e),
+        };
+    }
+}
+
+/// Removes the `.partial` folders copies interrupted by a crash or a lost share left behind.
+fn remove_partials(cfg: &BackupSettings) {
+    let projects = fs::read_dir(&cfg.path).into_iter().flatten().filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_dir());
+    for dir in projects.flat_map(|p| fs::read_dir(p).into_iter().flatten().filter_map(Result::ok).map(|e| e.path())) {
+        if dir.is_dir() && dir.extension().is_some_and(|e| e == "partial") {
+            if let Err(e) = fs::remove_dir_all(&dir) {
+                eprintln!("backup: {}: {e}", dir.display());
+            }
+        }
+    }
+}
+
+/// Clears stale partial copies, then checks every minute whether the configured interval has elapsed.
+pub fn start_scheduler(app: AppHandle) {
+    // On the scheduler thread: the share may be slow or unreachable at startup.
+    thread::spawn(move || {
+        if let Some(cfg) = settings::load(&app).backup {
+            remove_partials(&cfg);
+        }
+        loop {
+            thread::sleep(TICK);
+            let Some(cfg) = settings::load(&app).backup else { continue };
+            let state = app.state::<BackupState>();
+            let due = state.last_run.lock().map(|l| l.is_none_or(|t| t.elapsed().unwrap_or_default().as_secs_f64() >= cfg.interval_h * 3600.0)).unwrap_or(false);
+            if !due {
+                continue;
+            }
+            if let Ok(mut last) = state.last_run.lock() {
+                *last = Some(SystemTime::now());
+            }
+            run_all(&app, &cfg);
+        }
+    });
+}
+
+#[tauri::command(async)]
+pub fn backup_now(app: AppHandle, project_path: String, window: tauri::Window, viewer: State<ViewerMode>) -> Result<BackupReport, String> {
+    viewer.check(window.label())?;
+    let cfg = settings::load(&app).backup.ok_or("backup destination not configured")?;
+    track(&app, &project_path);
+    backup_project(&app, Path::new(&project_path), &cfg)
+}
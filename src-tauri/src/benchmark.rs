@@ -0,0 +1,116 @@
+//! Hardware benchmark: the engine times its standard synthetic run (see `/benchmark` there) alone
+//! and, on multi-core machines, two copies at once. The single run gives the score and a
+//! throughput to size planned studies with; the pair shows whether more engine workers pay off.
+
+use crate::{
+    engine, eta,
+    jobs::{JobQueue, JobStatus},
+    settings, ProjectConfig,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+/// Seconds of the standard run that score 100; faster machines score proportionally higher.
+const REFERENCE_S: f64 = 30.0;
+/// The standard run as `eta::work` sees it: one turbine, 990 m buffer, 10 m cells.
+const STANDARD_RUN: (f64, f64) = (990.0, 10.0);
+/// Engine cell sizes, see `CELLSIZE_ALLOWED` there.
+const CELL_SIZES: [f64; 5] = [8.0, 10.0, 20.0, 25.0, 50.0];
+/// Runs estimated beyond this get a cell size warning.
+const SLOW_RUN_S: f64 = 3600.0;
+
+#[derive(Serialize)]
+pub struct CellEstimate {
+    cellsize_m: f64,
+    estimated_s: f64,
+}
+
+#[derive(Serialize)]
+pub struct BenchmarkReport {
+    score: f64,
+    seconds: f64,
+    /// Wall time of two standard runs at once, `None` on single-core machines.
+    pair_seconds: Option<f64>,
+    /// Throughput of the pair over a single run: 2 is perfect scaling, 1 none.
+    speedup: Option<f64>,
+    cores: usize,
+    current_workers: usize,
+    suggested_workers: usize,
+    /// Rough run times of the project at each cell size, scaled from the benchmark.
+    estimates: Vec<CellEstimate>,
+    /// Finest cell size expected to finish within an hour.
+    suggested_cellsize_m: Option<f64>,
+    warnings: Vec<String>,
+}
+
+fn seconds(res: &Value) -> Result<f64, String> {
+    res["seconds"].as_f64().filter(|s| *s > 0.0).ok_or_else(|| "engine returned no benchmark time".to_string())
+}
+
+fn suggested_workers(cores: usize, speedup: Option<f64>) -> usize {
+    match speedup {
+        Some(s) if s >= 1.5 => (cores / 2).clamp(2, 4),
+        Some(s) if s >= 1.2 => 2,
+        _ => 1,
+    }
+}
+
+/// Runs the benchmark; with `cfg`, estimates that project's run time per cell size.
+#[tauri::command(async)]
+pub fn run_benchmark(cfg: Option<ProjectConfig>, app: AppHandle) -> Result<BenchmarkReport, String> {
+    let mut warnings = vec![];
+    if app.state::<JobQueue>().snapshot().iter().any(|j| j.status == JobStatus::Running) {
+        warnings.push("runs were in progress during the benchmark, the score is lower than the machine's".to_string());
+    }
+    let client = engine::client(&app, cfg.as_ref().map_or("", |c| c.project_path.as_str()))?;
+    let single = seconds(&client.benchmark(1)?)?;
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let pair = if cores >= 2 { Some(seconds(&client.benchmark(2)?)?) } else { None };
+    let speedup = pair.map(|p| 2.0 * single / p);
+    let current_workers = settings::load(&app).engine_workers.max(1);
+    let suggested = suggested_workers(cores, speedup);
+    if current_workers > suggested {
+        warnings.push(format!("{current_workers} engine workers configured, {suggested} suggested: parallel runs slow each other down on this machine"));
+    }
+
+    let (buffer_m, cellsize_m) = STANDARD_RUN;
+    let rate = eta::work(&json!({ "turbines": [{ "x": 0.0, "y": 0.0 }], "buffer_m": buffer_m, "cellsize_m": cellsize_m })) / single;
+    let mut estimates = vec![];
+    let mut suggested_cellsize_m = None;
+    if let Some(cfg) = &cfg {
+        let mut value = serde_json::to_value(cfg).map_err(|e| e.to_string())?;
+        for cellsize_m in CELL_SIZES {
+            value["cellsize_m"] = cellsize_m.into();
+            let estimated_s = eta::work(&value) / rate;
+            if suggested_cellsize_m.is_none() && estimated_s <= SLOW_RUN_S {
+                suggested_cellsize_m = Some(cellsize_m);
+            }
+            estimates.push(CellEstimate { cellsize_m, estimated_s });
+        }
+        match estimates.iter().find(|e| e.cellsize_m == cfg.cellsize_m) {
+            Some(e) if e.estimated_s > SLOW_RUN_S => warnings.push(format!(
+                "at {} m cells the project is expected to take about {:.0} min here{}",
+                e.cellsize_m,
+                e.estimated_s / 60.0,
+                suggested_cellsize_m.map(|c| format!(", {c} m cells should finish within the hour")).unwrap_or_default()
+            )),
+            _ => {}
+        }
+        if suggested_cellsize_m.is_none() && !estimates.is_empty() {
+            warnings.push("even 50 m cells are expected to take over an hour: consider fewer turbines or a smaller buffer".to_string());
+        }
+    }
+    Ok(BenchmarkReport {
+        score: (100.0 * REFERENCE_S / single).round(),
+        seconds: single,
+        pair_seconds: pair,
+        speedup,
+        cores,
+        current_workers,
+        suggested_workers: suggested,
+        estimates,
+        suggested_cellsize_m,
+        warnings,
+    })
+}
@@ -0,0 +1,143 @@
+//! Building footprints (OSM or cadastre, as GeoJSON) burned into a height raster that the engine
+//! merges into the screening surface.
+
+use crate::{crs, raster, viewer::ViewerMode, ProjectConfig};
+use serde::Serialize;
+use serde_json::Value;
+use std::{fs, path::PathBuf};
+use tauri::State;
+
+pub const BUILDINGS_FILE: &str = "buildings_height.asc";
+
+/// Used when a footprint carries neither a height nor a level count.
+const DEFAULT_HEIGHT_M: f64 = 8.0;
+const LEVEL_HEIGHT_M: f64 = 3.0;
+
+#[derive(Serialize)]
+pub struct BuildingsRaster {
+    path: String,
+    buildings: usize,
+    default_height: usize,
+    skipped: usize,
+}
+
+fn number(v: &Value) -> Option<f64> {
+    match v {
+        Value::Number(n) => n.as_f64(),
+        // OSM tags are strings, sometimes with units ("12 m", "12.5").
+        Value::String(s) => s.trim().trim_end_matches('m').trim().replace(',', ".").parse().ok(),
+        _ => None,
+    }
+}
+
+/// Height from the usual OSM and cadastre attributes; `None` means "use the default".
+fn building_height(props: &Value) -> Option<f64> {
+    ["height", "building:height", "altezza", "h_edificio"]
+        .iter()
+        .find_map(|k| props.get(*k).and_then(number))
+        .or_else(|| ["building:levels", "levels", "piani"].iter().find_map(|k| props.get(*k).and_then(number)).map(|l| l * LEVEL_HEIGHT_M))
+        .filter(|h| *h > 0.0)
+}
+
+type Ring = Vec<(f64, f64)>;
+
+fn rings(geom: &Value) -> Vec<Vec<Ring>> {
+    let parse_ring = |r: &Value| -> Ring {
+        r.as_array().into_iter().flatten().filter_map(|p| Some((p.get(0)?.as_f64()?, p.get(1)?.as_f64()?))).collect()
+    };
+    let parse_polygon = |p: &Value| -> Vec<Ring> { p.as_array().into_iter().flatten().map(parse_ring).collect() };
+    match geom.get("type").and_then(Value::as_str) {
+        Some("Polygon") => vec![parse_polygon(&geom["coordinates"])],
+        Some("MultiPolygon") => geom["coordinates"].as_array().into_iter().flatten().map(parse_polygon).collect(),
+        _ => vec![],
+    }
+}
+
+/// Even-odd test over all rings, so holes (courtyards) stay open.
+fn inside(polygon: &[Ring], x: f64, y: f64) -> bool {
+    let mut hit = false;
+    for ring in polygon {
+        for (i, &(x1, y1)) in ring.iter().enumerate() {
+            let (x2, y2) = ring[(i + 1) % ring.len()];
+            if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+                hit = !hit;
+            }
+        }
+    }
+    hit
+}
+
+/// Rasterizes building footprints over the calculation area (turbine extent plus buffer) at the
+/// project cell size. Footprints in lon/lat (RFC 7946 GeoJSON, OSM exports) are projected to the
+/// project CRS; anything else is assumed to be in it already.
+pub fn rasterize(cfg: &ProjectConfig, geojson: &Value) -> Result<(raster::Grid, BuildingsRaster), String> {
+    if cfg.turbines.is_empty() {
+        return Err("the project has no turbines to define the calculation area".to_string());
+    }
+    let features = geojson.get("features").and_then(Value::as_array).ok_or("GeoJSON FeatureCollection expected")?;
+    let minx = cfg.turbines.iter().map(|t| t.x).fold(f64::INFINITY, f64::min) - cfg.buffer_m;
+    let maxx = cfg.turbines.iter().map(|t| t.x).fold(f64::NEG_INFINITY, f64::max) + cfg.buffer_m;
+    let miny = cfg.turbines.iter().map(|t| t.y).fold(f64::INFINITY, f64::min) - cfg.buffer_m;
+    let maxy = cfg.turbines.iter().map(|t| t.y).fold(f64::NEG_INFINITY, f64::max) + cfg.buffer_m;
+    let cell = cfg.cellsize_m;
+    let ncols = ((maxx - minx) / cell).ceil().max(1.0) as usize;
+    let nrows = ((maxy - miny) / cell).ceil().max(1.0) as usize;
+    let mut data = vec![0.0_f64; ncols * nrows];
+    let project_crs = crs::parse_epsg(&cfg.epsg);
+    let mut report = BuildingsRaster { path: String::new(), buildings: 0, default_height: 0, skipped: 0 };
+
+    for f in features {
+        let mut polygons = rings(&f["geometry"]);
+        if polygons.is_empty() {
+            report.skipped += 1;
+            continue;
+        }
+        if polygons.iter().flatten().flatten().all(|&(x, y)| crs::looks_geographic(x, y)) {
+            let target = project_crs.as_ref().map_err(Clone::clone)?;
+            for p in polygons.iter_mut().flatten().flatten() {
+                *p = target.project(p.0, p.1);
+            }
+        }
+        let height = building_height(&f["properties"]).unwrap_or_else(|| {
+            report.default_height += 1;
+            DEFAULT_HEIGHT_M
+        });
+        report.buildings += 1;
+        for polygon in &polygons {
+            let pts = polygon.iter().flatten();
+            let (bx0, bx1) = pts.clone().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+            let (by0, by1) = pts.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+            let c0 = ((bx0 - minx) / cell).floor().max(0.0) as usize;
+            let c1 = (((bx1 - minx) / cell).ceil().max(0.0) as usize).min(ncols);
+            // Rows count from the top (north), as in the ASC body.
+            let r0 = ((maxy - by1) / cell).floor().max(0.0) as usize;
+            let r1 = (((maxy - by0) / cell).ceil().max(0.0) as usize).min(nrows);
+            for row in r0..r1 {
+                let y = maxy - (row as f64 + 0.5) * cell;
+                for col in c0..c1 {
+                    let x = minx + (col as f64 + 0.5) * cell;
+                    if inside(polygon, x, y) {
+                        let v = &mut data[row * ncols + col];
+                        *v = v.max(height);
+                    }
+                }
+            }
+        }
+    }
+    let header = raster::GridHeader { ncols, nrows, xll: minx, yll: maxy - nrows as f64 * cell, cellsize: cell, nodata: Some(raster::ASC_NODATA), epsg: Some(cfg.epsg.clone()) };
+    Ok((raster::Grid { header, data }, report))
+}
+
+#[tauri::command]
+pub fn rasterize_buildings(cfg: ProjectConfig, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Option<BuildingsRaster>, String> {
+    viewer.check(window.label())?;
+    let Some(src) = rfd::FileDialog::new().add_filter("GeoJSON", &["geojson", "json"]).pick_file() else {
+        return Ok(None);
+    };
+    let geojson: Value = serde_json::from_str(&fs::read_to_string(src).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    let (grid, mut report) = rasterize(&cfg, &geojson)?;
+    let path = PathBuf::from(&cfg.project_path).join(BUILDINGS_FILE);
+    raster::write_asc(&path, &grid.header, &grid.data).map_err(|e| e.to_string())?;
+    report.path = path.display().to_string();
+    Ok(Some(report))
+}
@@ -0,0 +1,128 @@
+//! Isolines of a result grid by marching squares over cell centres, joined into polylines.
+//! Saddle cells are resolved with the mean of their four corners.
+
+use crate::raster::Grid;
+use std::collections::HashMap;
+
+pub struct Contour {
+    pub level: f64,
+    /// Polylines in world coordinates (the grid's CRS); closed rings repeat their first point.
+    pub lines: Vec<Vec<(f64, f64)>>,
+}
+
+/// A crossing point identified by the grid edge it lies on: horizontal edges between
+/// (row, col) and (row, col + 1), vertical ones between (row, col) and (row + 1, col).
+type EdgeKey = (bool, usize, usize);
+type Segment = ([EdgeKey; 2], [(f64, f64); 2]);
+
+#[derive(Clone, Copy)]
+enum Edge {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+/// Segments per case, corners as bits top-left 8, top-right 4, bottom-right 2, bottom-left 1;
+/// the saddles 5 and 10 are listed for a centre below the level.
+const CASES: [&[(Edge, Edge)]; 16] = {
+    use Edge::*;
+    [
+        &[],
+        &[(Left, Bottom)],
+        &[(Bottom, Right)],
+        &[(Left, Right)],
+        &[(Top, Right)],
+        &[(Top, Right), (Left, Bottom)],
+        &[(Top, Bottom)],
+        &[(Left, Top)],
+        &[(Left, Top)],
+        &[(Top, Bottom)],
+        &[(Left, Top), (Bottom, Right)],
+        &[(Top, Right)],
+        &[(Left, Right)],
+        &[(Bottom, Right)],
+        &[(Left, Bottom)],
+        &[],
+    ]
+};
+
+fn segments(grid: &Grid, level: f64) -> Vec<Segment> {
+    let h = &grid.header;
+    let value = |r: usize, c: usize| grid.data[r * h.ncols + c];
+    let centre = |r: usize, c: usize| (h.xll + (c as f64 + 0.5) * h.cellsize, h.yll + (h.nrows as f64 - r as f64 - 0.5) * h.cellsize);
+    let crossing = |(r0, c0): (usize, usize), (r1, c1): (usize, usize)| {
+        let (v0, v1) = (value(r0, c0), value(r1, c1));
+        let t = if v1 == v0 { 0.5 } else { (level - v0) / (v1 - v0) };
+        let (p0, p1) = (centre(r0, c0), centre(r1, c1));
+        (p0.0 + t * (p1.0 - p0.0), p0.1 + t * (p1.1 - p0.1))
+    };
+    let mut out = vec![];
+    for r in 0..h.nrows.saturating_sub(1) {
+        for c in 0..h.ncols.saturating_sub(1) {
+            let corners = [value(r, c), value(r, c + 1), value(r + 1, c + 1), value(r + 1, c)];
+            if corners.iter().any(|v| grid.is_nodata(*v)) {
+                continue;
+            }
+            let case = corners.iter().fold(0, |acc, v| (acc << 1) | usize::from(*v >= level));
+            let flip = matches!(case, 5 | 10) && corners.iter().sum::<f64>() / 4.0 >= level;
+            let point = |e: Edge| -> (EdgeKey, (f64, f64)) {
+                match e {
+                    Edge::Top => ((true, r, c), crossing((r, c), (r, c + 1))),
+                    Edge::Bottom => ((true, r + 1, c), crossing((r + 1, c), (r + 1, c + 1))),
+                    Edge::Left => ((false, r, c), crossing((r, c), (r + 1, c))),
+                    Edge::Right => ((false, r, c + 1), crossing((r, c + 1), (r + 1, c + 1))),
+                }
+            };
+            // A saddle with its centre above the level separates the other pair of corners.
+            let pairs = if flip { CASES[15 - case] } else { CASES[case] };
+            for &(a, b) in pairs {
+                let (ka, pa) = point(a);
+                let (kb, pb) = point(b);
+                out.push(([ka, kb], [pa, pb]));
+            }
+        }
+    }
+    out
+}
+
+/// Chains segments sharing grid edges into polylines.
+fn join(segments: Vec<Segment>) -> Vec<Vec<(f64, f64)>> {
+    let mut by_edge: HashMap<EdgeKey, Vec<usize>> = HashMap::new();
+    for (i, (keys, _)) in segments.iter().enumerate() {
+        for k in keys {
+            by_edge.entry(*k).or_default().push(i);
+        }
+    }
+    let mut used = vec![false; segments.len()];
+    let mut lines = vec![];
+    for start in 0..segments.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let (keys, points) = segments[start];
+        let mut line = vec![(keys[0], points[0]), (keys[1], points[1])];
+        // Forward from the end, then backward from the start.
+        for forward in [true, false] {
+            loop {
+                let tip = if forward { line[line.len() - 1].0 } else { line[0].0 };
+                let Some(&next) = by_edge[&tip].iter().find(|&&i| !used[i]) else { break };
+                used[next] = true;
+                let (k, p) = segments[next];
+                let far = if k[0] == tip { (k[1], p[1]) } else { (k[0], p[0]) };
+                if forward {
+                    line.push(far);
+                } else {
+                    line.insert(0, far);
+                }
+            }
+        }
+        lines.push(line.into_iter().map(|(_, p)| p).collect());
+    }
+    lines
+}
+
+pub fn trace(grid: &Grid, levels: &[f64]) -> Vec<Contour> {
+    levels.iter().map(|&level| Contour { level, lines: join(segments(grid, level)) }).collect()
+}
@@ -0,0 +1,406 @@
+//! `parse_coordinate`: the coordinate strings clients paste into turbine and receptor entry
+//! (DMS, decimal degrees with or without hemispheres, UTM with zone, Gauss-Boaga, OS grid
+//! references, labelled or bare grid pairs, Italian decimal commas) turned into project CRS
+//! coordinates.
+
+use crate::crs::{self, Crs};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct ParsedCoordinate {
+    /// Project CRS.
+    x: f64,
+    y: f64,
+    /// WGS84 degrees.
+    lon: f64,
+    lat: f64,
+    /// Notation recognised, e.g. `dms`, `utm 32T`, `gauss-boaga est`, `project grid`.
+    format: String,
+}
+
+/// `Lat` also stands for northing and `Lon` for easting.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Axis {
+    Lat,
+    Lon,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Tok {
+    Num(f64),
+    Deg,
+    Min,
+    Sec,
+    /// Hemisphere (with its sign) or axis label.
+    Label(Axis, Option<f64>),
+    Word(String),
+    Sep,
+}
+
+fn label(word: &str) -> Option<Tok> {
+    Some(match word {
+        "N" | "NORD" | "NORTH" => Tok::Label(Axis::Lat, Some(1.0)),
+        "S" | "SUD" | "SOUTH" => Tok::Label(Axis::Lat, Some(-1.0)),
+        "E" | "EST" | "EAST" => Tok::Label(Axis::Lon, Some(1.0)),
+        "W" | "O" | "OVEST" | "WEST" => Tok::Label(Axis::Lon, Some(-1.0)),
+        "LAT" | "LATITUDE" | "LATITUDINE" | "Y" | "NORTHING" => Tok::Label(Axis::Lat, None),
+        "LON" | "LNG" | "LONG" | "LONGITUDE" | "LONGITUDINE" | "X" | "EASTING" => Tok::Label(Axis::Lon, None),
+        _ => return None,
+    })
+}
+
+/// Digit groups of three after the first, as in `4.987.654` or `512.345`.
+fn thousands_groups(number: &str) -> bool {
+    let mut groups = number.trim_start_matches(['-', '+']).split('.');
+    groups.next().is_some_and(|g| (1..=3).contains(&g.len())) && groups.all(|g| g.len() == 3)
+}
+
+/// Numbers keep a decimal comma when the text has no dots (`45,5042 9,2008`); a number with
+/// several dots uses them as thousands separators (`4.987.654`), and then so do the other
+/// numbers grouped by three (`512.345 4.987.654`).
+fn tokenize(text: &str) -> Vec<Tok> {
+    let mut text = text.to_uppercase();
+    for datum in ["WGS84", "WGS 84", "WGS-84", "ETRS89", "ETRS 89", "ETRS-89"] {
+        text = text.replace(datum, " ");
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let digit_at = |i: usize| chars.get(i).is_some_and(|c| c.is_ascii_digit());
+    let decimal_comma = !text.contains('.') && (1..chars.len()).any(|i| chars[i] == ',' && digit_at(i - 1) && digit_at(i + 1));
+    let grouped = text.split(|c: char| !(c.is_ascii_digit() || c == '.')).any(|n| n.matches('.').count() > 1);
+    let mut out = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_digit() || ((c == '-' || c == '+' || c == '.') && digit_at(i + 1)) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || (decimal_comma && chars[i] == ',' && digit_at(i + 1))) {
+                i += 1;
+            }
+            let raw: String = chars[start..i].iter().collect::<String>().replace(',', ".");
+            let raw = if raw.matches('.').count() > 1 || (grouped && thousands_groups(&raw)) { raw.replace('.', "") } else { raw };
+            if let Ok(v) = raw.parse() {
+                out.push(Tok::Num(v));
+            }
+            continue;
+        }
+        if c.is_alphabetic() {
+            let start = i;
+            while i < chars.len() && chars[i].is_alphabetic() {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            out.push(label(&word).unwrap_or(Tok::Word(word)));
+            continue;
+        }
+        match c {
+            '°' | 'º' => out.push(Tok::Deg),
+            '\'' | '′' | '’' if chars.get(i + 1) == Some(&c) => {
+                out.push(Tok::Sec);
+                i += 1;
+            }
+            '\'' | '′' | '’' => out.push(Tok::Min),
+            '"' | '″' | '”' => out.push(Tok::Sec),
+            ',' | ';' | '/' | '|' => out.push(Tok::Sep),
+            _ => {}
+        }
+        i += 1;
+    }
+    // `EPSG:4326` and similar only name the system.
+    let mut clean = vec![];
+    let mut tokens = out.into_iter().peekable();
+    while let Some(t) = tokens.next() {
+        if t == Tok::Word("EPSG".to_string()) && matches!(tokens.peek(), Some(Tok::Num(_))) {
+            tokens.next();
+            continue;
+        }
+        clean.push(t);
+    }
+    clean
+}
+
+fn numbers(tokens: &[Tok]) -> Vec<f64> {
+    tokens.iter().filter_map(|t| if let Tok::Num(v) = t { Some(*v) } else { None }).collect()
+}
+
+/// Easting and northing of the two numbers in `tokens`, ordered by their labels, which may come
+/// before (`E 512345 N 4987654`) or after (`4987654 N 512345 E`) the values. Bare pairs rely on
+/// the northing being the larger value, true for every grid used in Italy, unless
+/// `northing_larger` is off (British grid eastings exceed northings in the south), which keeps
+/// them in the written easting, northing order.
+fn grid_pair(tokens: &[Tok], northing_larger: bool) -> (f64, f64) {
+    let prefix = tokens.iter().position(|t| matches!(t, Tok::Label(..))) < tokens.iter().position(|t| matches!(t, Tok::Num(_)));
+    let mut pairs: Vec<(f64, Option<Axis>)> = vec![];
+    let mut pending = None;
+    for t in tokens {
+        match t {
+            Tok::Label(axis, _) if prefix => pending = Some(*axis),
+            Tok::Label(axis, _) => {
+                if let Some(last) = pairs.last_mut().filter(|p| p.1.is_none()) {
+                    last.1 = Some(*axis);
+                }
+            }
+            Tok::Num(v) => pairs.push((*v, pending.take())),
+            _ => {}
+        }
+    }
+    match pairs.as_slice() {
+        [(a, Some(Axis::Lat)), (b, _)] | [(b, _), (a, Some(Axis::Lat))] => (*b, *a),
+        [(a, Some(Axis::Lon)), (b, _)] | [(b, _), (a, Some(Axis::Lon))] => (*a, *b),
+        [(a, _), (b, _)] if northing_larger => (a.min(*b), a.max(*b)),
+        [(a, _), (b, _)] => (*a, *b),
+        _ => (f64::NAN, f64::NAN),
+    }
+}
+
+/// `32T 512345 4987654`, `33 S 300000 6250000`, `UTM 32 N E 512345 N 4987654`: zone number, then
+/// a hemisphere `N`/`S` or a latitude band (C-M south, N-X north), then the grid pair. A band
+/// disagreeing with the hemisphere of a projected `project` CRS gives way to it, since bands and
+/// hemispheres are easily confused (band S covers Sicily); without either the project decides.
+fn utm(tokens: &[Tok], project: &Crs) -> Option<(Crs, String, f64, f64)> {
+    let nums = numbers(tokens);
+    let [zone, a, b] = nums.as_slice() else { return None };
+    if zone.fract() != 0.0 || !(1.0..=60.0).contains(zone) || a.abs() < 1000.0 || b.abs() < 1000.0 {
+        return None;
+    }
+    let at = tokens.iter().position(|t| *t == Tok::Num(*zone))?;
+    let project_south = project.is_south();
+    let (band, south, rest) = match tokens.get(at + 1) {
+        Some(Tok::Word(w)) if w.len() == 1 && w != "I" && ('C'..='X').contains(&w.chars().next()?) => {
+            let band_south = w.as_str() < "N";
+            (w.clone(), project_south.unwrap_or(band_south), &tokens[at + 2..])
+        }
+        // Unless it labels the northing that follows, as in `32 N 4987654 E 512345`.
+        Some(Tok::Label(Axis::Lat, Some(sign))) if !matches!(tokens.get(at + 2), Some(Tok::Num(_))) || matches!(tokens.get(at + 3), Some(Tok::Num(_))) => {
+            let south = *sign < 0.0;
+            ((if south { "S" } else { "N" }).to_string(), south, &tokens[at + 2..])
+        }
+        _ => (String::new(), project_south.unwrap_or(false), &tokens[at + 1..]),
+    };
+    let crs = crs::utm_zone(*zone as u32, south).ok()?;
+    let (e, n) = grid_pair(rest, true);
+    Some((crs, format!("utm {}{band}", *zone as u32), e, n))
+}
+
+#[derive(Default)]
+struct Component {
+    parts: Vec<f64>,
+    axis: Option<Axis>,
+    sign: f64,
+    units: bool,
+    /// A trailing hemisphere closed it.
+    closed: bool,
+}
+
+/// Two angles from DMS (`45°30'15.2"N 9°12'3"E`, `N 45 30 15 E 9 12 3`), decimal minutes or
+/// decimal degrees; without hemispheres or labels the order is latitude, longitude.
+fn geographic(tokens: &[Tok]) -> Result<(f64, f64, &'static str), String> {
+    let new = || Component { sign: 1.0, ..Default::default() };
+    let mut comps: Vec<Component> = vec![];
+    let mut cur = new();
+    let mut last = None;
+    for t in tokens {
+        match t {
+            Tok::Num(v) => {
+                if cur.closed || (last == Some(&Tok::Sec) && !cur.parts.is_empty()) {
+                    comps.push(std::mem::replace(&mut cur, new()));
+                }
+                cur.parts.push(*v);
+            }
+            Tok::Deg => {
+                if cur.parts.len() > 1 {
+                    let d = cur.parts.pop().unwrap_or_default();
+                    comps.push(std::mem::replace(&mut cur, new()));
+                    cur.parts.push(d);
+                }
+                cur.units = true;
+            }
+            Tok::Min | Tok::Sec => cur.units = true,
+            Tok::Label(axis, sign) => {
+                if !cur.parts.is_empty() && (cur.axis.is_some() || cur.closed) {
+                    comps.push(std::mem::replace(&mut cur, new()));
+                }
+                cur.axis = Some(*axis);
+                cur.sign = sign.unwrap_or(1.0);
+                cur.closed = !cur.parts.is_empty();
+            }
+            Tok::Sep if !cur.parts.is_empty() => comps.push(std::mem::replace(&mut cur, new())),
+            _ => {}
+        }
+        last = Some(t);
+    }
+    if !cur.parts.is_empty() {
+        comps.push(cur);
+    }
+    // `45 30 15 9 12 3` or `45.5 9.2` with only spaces between the values.
+    if let [only] = comps.as_mut_slice() {
+        if only.axis.is_none() && !only.units && only.parts.len() % 2 == 0 {
+            let second = only.parts.split_off(only.parts.len() / 2);
+            comps.push(Component { parts: second, ..new() });
+        }
+    }
+    let [a, b] = comps.as_slice() else {
+        return Err("expected two coordinates".to_string());
+    };
+    let angle = |c: &Component| -> Result<f64, String> {
+        let [d, rest @ ..] = c.parts.as_slice() else { return Err("missing degrees".to_string()) };
+        if rest.len() > 2 || rest.iter().any(|v| !(0.0..60.0).contains(v)) {
+            return Err("minutes and seconds must be between 0 and 60".to_string());
+        }
+        let value = d.abs() + rest.first().unwrap_or(&0.0) / 60.0 + rest.get(1).unwrap_or(&0.0) / 3600.0;
+        Ok(d.signum() * c.sign * value)
+    };
+    let (va, vb) = (angle(a)?, angle(b)?);
+    let (lat, lon) = match (a.axis, b.axis) {
+        (Some(Axis::Lon), _) | (None, Some(Axis::Lat)) => (vb, va),
+        (None, None) if va.abs() > 90.0 => (vb, va),
+        _ => (va, vb),
+    };
+    if lat.abs() > 90.0 || lon.abs() > 180.0 {
+        return Err(format!("latitude {lat} or longitude {lon} out of range"));
+    }
+    let format = if a.parts.len() > 1 || b.parts.len() > 1 { "dms" } else { "decimal degrees" };
+    Ok((lon, lat, format))
+}
+
+/// OS grid reference (`TQ 30017 80525`, `TQ3001780525`): the 100 km square letters, then as
+/// many easting digits as northing digits.
+fn os_grid_ref(text: &str) -> Option<(f64, f64)> {
+    let compact: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).map(|c| c.to_ascii_uppercase()).collect();
+    let [l1, l2, digits @ ..] = compact.as_slice() else { return None };
+    if digits.is_empty() || digits.len() % 2 != 0 || digits.len() > 10 || !digits.iter().all(char::is_ascii_digit) {
+        return None;
+    }
+    // Letters skip I; the first picks a 500 km square counted from S, the second a 100 km one in it.
+    let index = |c: char| c.is_ascii_uppercase().then(|| c as i32 - 'A' as i32).filter(|&i| i != 8).map(|i| if i > 8 { i - 1 } else { i });
+    let (a, b) = (index(*l1)?, index(*l2)?);
+    let (e100, n100) = ((a - 2).rem_euclid(5) * 5 + b % 5, 19 - (a / 5) * 5 - b / 5);
+    if !(0..7).contains(&e100) || !(0..13).contains(&n100) {
+        return None;
+    }
+    let half = digits.len() / 2;
+    let metres = |d: &[char]| d.iter().collect::<String>().parse::<f64>().ok().map(|v| v * 10f64.powi(5 - half as i32));
+    Some((e100 as f64 * 100_000.0 + metres(&digits[..half])?, n100 as f64 * 100_000.0 + metres(&digits[half..])?))
+}
+
+#[tauri::command]
+pub fn parse_coordinate(text: String, epsg: String) -> Result<ParsedCoordinate, String> {
+    let project = crs::parse_epsg(&epsg)?;
+    let code = epsg.trim().trim_start_matches("EPSG:").trim_start_matches("epsg:");
+    let project_is_gb = matches!(code, "3003" | "3004");
+    let tokens = tokenize(&text);
+    let nums = numbers(&tokens);
+    let done = |lon: f64, lat: f64, format: String| {
+        let (x, y) = project.project(lon, lat);
+        Ok(ParsedCoordinate { x, y, lon, lat, format })
+    };
+    if let Some((e, n)) = os_grid_ref(&text) {
+        let (lon, lat) = crs::british_grid().unproject(e, n);
+        return done(lon, lat, "os grid reference".to_string());
+    }
+    if let Some((crs, format, e, n)) = utm(&tokens, &project) {
+        let (lon, lat) = crs.unproject(e, n);
+        return done(lon, lat, format);
+    }
+    if nums.len() == 2 && nums.iter().all(|v| v.abs() >= 1000.0) {
+        let (e, n) = grid_pair(&tokens, code != "27700");
+        let gb_hint = tokens.iter().any(|t| matches!(t, Tok::Word(w) if ["GB", "GAUSS", "BOAGA", "MONTE", "MARIO"].contains(&w.as_str())));
+        // Gauss-Boaga eastings start with the zone digit (1 500 000 / 2 520 000 false eastings).
+        let gb_zone = match e {
+            e if (1_000_000.0..2_000_000.0).contains(&e) => Some(false),
+            e if (2_000_000.0..3_000_000.0).contains(&e) => Some(true),
+            _ => None,
+        };
+        if let Some(east) = gb_zone.filter(|_| (gb_hint || !project_is_gb) && (3_500_000.0..5_500_000.0).contains(&n)) {
+            let (lon, lat) = crs::gauss_boaga(east).unproject(e, n);
+            return done(lon, lat, format!("gauss-boaga {}", if east { "est" } else { "ovest" }));
+        }
+        if matches!(project, Crs::Geographic) {
+            return Err(format!("{text}: grid coordinates need a projected project CRS or a UTM zone"));
+        }
+        let (lon, lat) = project.unproject(e, n);
+        return Ok(ParsedCoordinate { x: e, y: n, lon, lat, format: "project grid".to_string() });
+    }
+    if nums.iter().any(|v| v.abs() >= 1000.0) {
+        return Err(format!("unrecognised coordinate: {text}"));
+    }
+    let (lon, lat, format) = geographic(&tokens).map_err(|e| format!("unrecognised coordinate: {text} ({e})"))?;
+    done(lon, lat, format.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(text: &str, epsg: &str) -> ParsedCoordinate {
+        parse_coordinate(text.to_string(), epsg.to_string()).unwrap_or_else(|e| panic!("{text}: {e}"))
+    }
+
+    #[test]
+    fn geographic_notations() {
+        // (text, lat, lon, format)
+        let cases = [
+            ("45°30'15.2\"N 9°12'3\"E", 45.504222, 9.200833, "dms"),
+            ("N 45 30 15.2 E 9 12 3", 45.504222, 9.200833, "dms"),
+            ("33°52'S 151°12'E", -33.866667, 151.2, "dms"),
+            ("45,5042 9,2008", 45.5042, 9.2008, "decimal degrees"),
+            ("45,5042; 9,2008", 45.5042, 9.2008, "decimal degrees"),
+            ("9.2008 E, 45.5042 N", 45.5042, 9.2008, "decimal degrees"),
+        ];
+        for (text, lat, lon, format) in cases {
+            let p = parsed(text, "EPSG:4326");
+            assert!((p.lat - lat).abs() < 1e-5 && (p.lon - lon).abs() < 1e-5, "{text}: {} {}", p.lat, p.lon);
+            assert_eq!(p.format, format, "{text}");
+        }
+    }
+
+    #[test]
+    fn utm_hemisphere_and_bands() {
+        // (text, project, south)
+        let cases = [
+            ("32T 514925 5034860", "EPSG:32632", false),
+            ("32 N 514925 5034860", "EPSG:32632", false),
+            ("UTM 32 N E 514925 N 5034860", "EPSG:4326", false),
+            ("32 N 5034860 E 514925", "EPSG:32632", false),
+            ("33 S 300000 6250000", "EPSG:32733", true),
+            ("33 S 300000 6250000", "EPSG:4326", true),
+            ("33H 300000 6250000", "EPSG:4326", true),
+            // Band C is south, the northern project grid wins.
+            ("32C 514925 5034860", "EPSG:32632", false),
+            ("33 300000 6250000", "EPSG:32733", true),
+        ];
+        for (text, epsg, south) in cases {
+            let p = parsed(text, epsg);
+            assert_eq!(p.lat < 0.0, south, "{text} in {epsg}: {}", p.lat);
+        }
+        let p = parsed("33 S 300000 6250000", "EPSG:32733");
+        assert!((p.x - 300_000.0).abs() < 0.01 && (p.y - 6_250_000.0).abs() < 0.01, "{} {}", p.x, p.y);
+    }
+
+    #[test]
+    fn grid_pairs() {
+        // (text, project, x, y)
+        let cases = [
+            ("512345 4987654", "EPSG:32632", 512_345.0, 4_987_654.0),
+            ("4987654 512345", "EPSG:32632", 512_345.0, 4_987_654.0),
+            ("512.345 4.987.654", "EPSG:32632", 512_345.0, 4_987_654.0),
+            ("N 4.987.654 E 512.345", "EPSG:32632", 512_345.0, 4_987_654.0),
+            ("530017 180525", "EPSG:27700", 530_017.0, 180_525.0),
+            ("TQ 30017 80525", "EPSG:27700", 530_017.0, 180_525.0),
+            ("tq3001780525", "EPSG:27700", 530_017.0, 180_525.0),
+            ("TQ 300 805", "EPSG:27700", 530_000.0, 180_500.0),
+        ];
+        for (text, epsg, x, y) in cases {
+            let p = parsed(text, epsg);
+            assert!((p.x - x).abs() < 0.01 && (p.y - y).abs() < 0.01, "{text}: {} {}", p.x, p.y);
+        }
+    }
+
+    #[test]
+    fn british_grid_reference_to_wgs84() {
+        // Trafalgar Square.
+        let p = parsed("TQ 30017 80525", "EPSG:4326");
+        assert!((p.lat - 51.508).abs() < 1e-3 && (p.lon + 0.128).abs() < 1e-3, "{} {}", p.lat, p.lon);
+        assert_eq!(p.format, "os grid reference");
+    }
+}
@@ -0,0 +1,297 @@
+//! Coordinate reference systems the shell can transform without PROJ: WGS84/ETRS89 geographic
+//! and the transverse Mercator grids used for wind projects in Europe (UTM on WGS84/ETRS89,
+//! ETRS-TMzn, RDN2008 UTM, Gauss-Boaga on Monte Mario, the British National Grid on OSGB36).
+//! ETRS89 and WGS84 are treated as identical, which is below the metre level and well within DEM
+//! and layout tolerances.
+//!
+//! Transverse Mercator uses the Krüger series to third order in n (millimetre accuracy
+//! within a zone). Monte Mario is reached with the 7-parameter Helmert shift for mainland Italy
+//! (EPSG:1660), OSGB36 with the one for Great Britain (EPSG:1314), both good to a few metres.
+
+#[derive(Clone, Copy, Debug)]
+struct Ellipsoid {
+    a: f64,
+    f: f64,
+}
+
+const WGS84: Ellipsoid = Ellipsoid { a: 6_378_137.0, f: 1.0 / 298.257_223_563 };
+const INTERNATIONAL_1924: Ellipsoid = Ellipsoid { a: 6_378_388.0, f: 1.0 / 297.0 };
+const AIRY_1830: Ellipsoid = Ellipsoid { a: 6_377_563.396, f: 1.0 / 299.324_964_6 };
+
+/// Local datum to WGS84, position vector convention: metres, arc seconds and ppm.
+#[derive(Clone, Copy, Debug)]
+struct Helmert {
+    t: [f64; 3],
+    r: [f64; 3],
+    ds_ppm: f64,
+}
+
+/// Monte Mario to WGS84, Italy mainland (EPSG:1660).
+const MONTE_MARIO: Helmert = Helmert { t: [-104.1, -49.1, -9.9], r: [0.971, -2.917, 0.714], ds_ppm: -11.68 };
+/// OSGB36 to WGS84, Great Britain (EPSG:1314).
+const OSGB36: Helmert = Helmert { t: [446.448, -125.157, 542.06], r: [0.15, 0.247, 0.842], ds_ppm: -20.489 };
+
+#[derive(Clone, Copy, Debug)]
+pub struct TransverseMercator {
+    lon0: f64,
+    k0: f64,
+    false_easting: f64,
+    false_northing: f64,
+    ellipsoid: Ellipsoid,
+    /// `None` for grids on WGS84/ETRS89.
+    datum: Option<Helmert>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Crs {
+    Geographic,
+    TransverseMercator(TransverseMercator),
+}
+
+fn utm(zone: u32, south: bool) -> Crs {
+    Crs::TransverseMercator(TransverseMercator {
+        lon0: (zone as f64 - 1.0) * 6.0 - 180.0 + 3.0,
+        k0: 0.9996,
+        false_easting: 500_000.0,
+        false_northing: if south { 10_000_000.0 } else { 0.0 },
+        ellipsoid: WGS84,
+        datum: None,
+    })
+}
+
+/// UTM zone and hemisphere on WGS84, as written in coordinate strings.
+pub fn utm_zone(zone: u32, south: bool) -> Result<Crs, String> {
+    if !(1..=60).contains(&zone) {
+        return Err(format!("invalid UTM zone: {zone}"));
+    }
+    Ok(utm(zone, south))
+}
+
+/// Gauss-Boaga zone 1 (Fuso Ovest, EPSG:3003) or zone 2 (Fuso Est, EPSG:3004).
+pub fn gauss_boaga(east: bool) -> Crs {
+    Crs::TransverseMercator(TransverseMercator {
+        lon0: if east { 15.0 } else { 9.0 },
+        k0: 0.9996,
+        false_easting: if east { 2_520_000.0 } else { 1_500_000.0 },
+        false_northing: 0.0,
+        ellipsoid: INTERNATIONAL_1924,
+        datum: Some(MONTE_MARIO),
+    })
+}
+
+/// British National Grid (EPSG:27700). Its origin at 49°N, 2°W is folded into the false northing.
+pub fn british_grid() -> Crs {
+    let mut tm = TransverseMercator { lon0: -2.0, k0: 0.999_601_271_7, false_easting: 400_000.0, false_northing: 0.0, ellipsoid: AIRY_1830, datum: Some(OSGB36) };
+    tm.false_northing = -100_000.0 - tm.forward(-2.0, 49.0).1;
+    Crs::TransverseMercator(tm)
+}
+
+/// Resolves `EPSG:<code>` (or a bare code) to a supported CRS.
+pub fn parse_epsg(epsg: &str) -> Result<Crs, String> {
+    let code: u32 = epsg.trim().trim_start_matches("EPSG:").trim_start_matches("epsg:").parse().map_err(|_| format!("invalid EPSG code: {epsg}"))?;
+    match code {
+        4326 | 4258 | 4937 | 4979 => Ok(Crs::Geographic),
+        32601..=32660 => Ok(utm(code - 32600, false)),
+        32701..=32760 => Ok(utm(code - 32700, true)),
+        25828..=25838 => Ok(utm(code - 25800, false)),
+        3038..=3051 => Ok(utm(code - 3038 + 26, false)),
+        6707..=6709 => Ok(utm(code - 6707 + 32, false)),
+        7791..=7793 => Ok(utm(code - 7791 + 32, false)),
+        3003 => Ok(gauss_boaga(false)),
+        3004 => Ok(gauss_boaga(true)),
+        27700 => Ok(british_grid()),
+        _ => Err(format!("EPSG:{code} is not supported for reprojection")),
+    }
+}
+
+/// True when coordinates are plausible as lon/lat degrees rather than projected metres.
+pub fn looks_geographic(x: f64, y: f64) -> bool {
+    x.abs() <= 180.0 && y.abs() <= 90.0
+}
+
+/// Geodesic between two WGS84 lon/lat points by Vincenty's inverse formula: distance in metres
+/// and forward azimuths in degrees clockwise from true north at both ends. `None` when the
+/// iteration does not converge, which only happens for nearly antipodal points.
+pub fn geodesic(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> Option<(f64, f64, f64)> {
+    let Ellipsoid { a, f } = WGS84;
+    let b = a * (1.0 - f);
+    let l = (lon2 - lon1).to_radians();
+    let u1 = ((1.0 - f) * lat1.to_radians().tan()).atan();
+    let u2 = ((1.0 - f) * lat2.to_radians().tan()).atan();
+    let (sin_u1, cos_u1, sin_u2, cos_u2) = (u1.sin(), u1.cos(), u2.sin(), u2.cos());
+    let mut lambda = l;
+    for _ in 0..200 {
+        let (sin_l, cos_l) = (lambda.sin(), lambda.cos());
+        let sin_sigma = ((cos_u2 * sin_l).powi(2) + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_l).powi(2)).sqrt();
+        if sin_sigma == 0.0 {
+            return Some((0.0, 0.0, 0.0));
+        }
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_l;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_l / sin_sigma;
+        let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+        // Zero on the equator, where the 2σm term vanishes.
+        let cos_2sm = if cos2_alpha == 0.0 { 0.0 } else { cos_sigma - 2.0 * sin_u1 * sin_u2 / cos2_alpha };
+        let c = f / 16.0 * cos2_alpha * (4.0 + f * (4.0 - 3.0 * cos2_alpha));
+        let previous = lambda;
+        lambda = l + (1.0 - c) * f * sin_alpha * (sigma + c * sin_sigma * (cos_2sm + c * cos_sigma * (-1.0 + 2.0 * cos_2sm * cos_2sm)));
+        if (lambda - previous).abs() < 1e-12 {
+            let u_sq = cos2_alpha * (a * a - b * b) / (b * b);
+            let big_a = 1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+            let big_b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+            let delta_sigma = big_b
+                * sin_sigma
+                * (cos_2sm + big_b / 4.0 * (cos_sigma * (-1.0 + 2.0 * cos_2sm * cos_2sm) - big_b / 6.0 * cos_2sm * (-3.0 + 4.0 * sin_sigma * sin_sigma) * (-3.0 + 4.0 * cos_2sm * cos_2sm)));
+            let (sin_l, cos_l) = (lambda.sin(), lambda.cos());
+            let az1 = (cos_u2 * sin_l).atan2(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_l).to_degrees();
+            let az2 = (cos_u1 * sin_l).atan2(-sin_u1 * cos_u2 + cos_u1 * sin_u2 * cos_l).to_degrees();
+            return Some((b * big_a * (sigma - delta_sigma), az1.rem_euclid(360.0), az2.rem_euclid(360.0)));
+        }
+    }
+    None
+}
+
+struct Series {
+    n: f64,
+    a_rect: f64,
+    alpha: [f64; 3],
+    beta: [f64; 3],
+    delta: [f64; 3],
+}
+
+fn series(ell: Ellipsoid) -> Series {
+    let n = ell.f / (2.0 - ell.f);
+    let (n2, n3) = (n * n, n * n * n);
+    Series {
+        n,
+        a_rect: ell.a / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0),
+        alpha: [n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0, 13.0 * n2 / 48.0 - 3.0 * n3 / 5.0, 61.0 * n3 / 240.0],
+        beta: [n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0, n2 / 48.0 + n3 / 15.0, 17.0 * n3 / 480.0],
+        delta: [2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3, 7.0 * n2 / 3.0 - 8.0 * n3 / 5.0, 56.0 * n3 / 15.0],
+    }
+}
+
+fn to_ecef(ell: Ellipsoid, lon: f64, lat: f64) -> [f64; 3] {
+    let e2 = ell.f * (2.0 - ell.f);
+    let (phi, lam) = (lat.to_radians(), lon.to_radians());
+    let nu = ell.a / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+    [nu * phi.cos() * lam.cos(), nu * phi.cos() * lam.sin(), nu * (1.0 - e2) * phi.sin()]
+}
+
+fn from_ecef(ell: Ellipsoid, p: [f64; 3]) -> (f64, f64) {
+    let e2 = ell.f * (2.0 - ell.f);
+    let r = p[0].hypot(p[1]);
+    let mut phi = p[2].atan2(r * (1.0 - e2));
+    for _ in 0..5 {
+        let nu = ell.a / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+        let h = r / phi.cos() - nu;
+        phi = p[2].atan2(r * (1.0 - e2 * nu / (nu + h)));
+    }
+    (p[1].atan2(p[0]).to_degrees(), phi.to_degrees())
+}
+
+impl Helmert {
+    /// Applies the shift, or its inverse with `sign` -1 (exact enough for arc-second rotations).
+    fn apply(&self, p: [f64; 3], sign: f64) -> [f64; 3] {
+        let [rx, ry, rz] = self.r.map(|r| sign * (r / 3600.0).to_radians());
+        let s = 1.0 + sign * self.ds_ppm * 1e-6;
+        [
+            sign * self.t[0] + s * (p[0] - rz * p[1] + ry * p[2]),
+            sign * self.t[1] + s * (rz * p[0] + p[1] - rx * p[2]),
+            sign * self.t[2] + s * (-ry * p[0] + rx * p[1] + p[2]),
+        ]
+    }
+}
+
+impl TransverseMercator {
+    /// WGS84 lon/lat to lon/lat on the grid's own datum.
+    fn wgs84_to_local(&self, lon: f64, lat: f64) -> (f64, f64) {
+        match self.datum {
+            Some(h) => from_ecef(self.ellipsoid, h.apply(to_ecef(WGS84, lon, lat), -1.0)),
+            None => (lon, lat),
+        }
+    }
+
+    fn local_to_wgs84(&self, lon: f64, lat: f64) -> (f64, f64) {
+        match self.datum {
+            Some(h) => from_ecef(WGS84, h.apply(to_ecef(self.ellipsoid, lon, lat), 1.0)),
+            None => (lon, lat),
+        }
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let s = series(self.ellipsoid);
+        let xi = (y - self.false_northing) / (self.k0 * s.a_rect);
+        let eta = (x - self.false_easting) / (self.k0 * s.a_rect);
+        let (mut xi_p, mut eta_p) = (xi, eta);
+        for (j, b) in s.beta.iter().enumerate() {
+            let k = 2.0 * (j as f64 + 1.0);
+            xi_p -= b * (k * xi).sin() * (k * eta).cosh();
+            eta_p -= b * (k * xi).cos() * (k * eta).sinh();
+        }
+        let chi = (xi_p.sin() / eta_p.cosh()).asin();
+        let phi = chi + s.delta.iter().enumerate().map(|(j, d)| d * (2.0 * (j as f64 + 1.0) * chi).sin()).sum::<f64>();
+        (self.lon0 + eta_p.sinh().atan2(xi_p.cos()).to_degrees(), phi.to_degrees())
+    }
+
+    fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let s = series(self.ellipsoid);
+        let (phi, dlam) = (lat.to_radians(), (lon - self.lon0).to_radians());
+        let c = 2.0 * s.n.sqrt() / (1.0 + s.n);
+        let t = (phi.sin().atanh() - c * (c * phi.sin()).atanh()).sinh();
+        let xi_p = t.atan2(dlam.cos());
+        let eta_p = (dlam.sin() / (1.0 + t * t).sqrt()).atanh();
+        let (mut xi, mut eta) = (xi_p, eta_p);
+        for (j, a) in s.alpha.iter().enumerate() {
+            let k = 2.0 * (j as f64 + 1.0);
+            xi += a * (k * xi_p).sin() * (k * eta_p).cosh();
+            eta += a * (k * xi_p).cos() * (k * eta_p).sinh();
+        }
+        (self.false_easting + self.k0 * s.a_rect * eta, self.false_northing + self.k0 * s.a_rect * xi)
+    }
+}
+
+impl Crs {
+    /// Whether a grid has the 10 000 km false northing of southern UTM zones; `None` for lon/lat.
+    pub fn is_south(&self) -> Option<bool> {
+        match self {
+            Crs::Geographic => None,
+            Crs::TransverseMercator(tm) => Some(tm.false_northing == 10_000_000.0),
+        }
+    }
+
+    /// WGS84 lon/lat degrees to this CRS.
+    pub fn project(&self, lon: f64, lat: f64) -> (f64, f64) {
+        match self {
+            Crs::Geographic => (lon, lat),
+            Crs::TransverseMercator(tm) => {
+                let (lon, lat) = tm.wgs84_to_local(lon, lat);
+                tm.forward(lon, lat)
+            }
+        }
+    }
+
+    /// This CRS to WGS84 lon/lat degrees.
+    pub fn unproject(&self, x: f64, y: f64) -> (f64, f64) {
+        match self {
+            Crs::Geographic => (x, y),
+            Crs::TransverseMercator(tm) => {
+                let (lon, lat) = tm.inverse(x, y);
+                tm.local_to_wgs84(lon, lat)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn british_grid_matches_the_os_worked_example() {
+        // OS "A guide to coordinate systems in Great Britain", on the OSGB36 datum.
+        let Crs::TransverseMercator(tm) = british_grid() else { unreachable!() };
+        let (e, n) = tm.forward(1.0 + 43.0 / 60.0 + 4.5177 / 3600.0, 52.0 + 39.0 / 60.0 + 27.2531 / 3600.0);
+        assert!((e - 651_409.903).abs() < 0.01 && (n - 313_177.270).abs() < 0.01, "{e} {n}");
+    }
+}
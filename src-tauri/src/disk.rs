@@ -0,0 +1,75 @@
+//! Free space checks before runs and exports, so a long calculation does not die on a full disk
+//! hours in. Sizes are estimated from the grid the engine will allocate.
+
+use crate::{eta, ProjectConfig};
+use serde_json::json;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+
+/// ASCII grid cell as written by the engine (`%.2f` and a separator).
+const ASC_BYTES_PER_CELL: f64 = 10.0;
+/// Float32 GeoTIFF cell.
+const TIF_BYTES_PER_CELL: f64 = 4.0;
+/// Preview PNG, PDF report text and project state.
+const FIXED_OUTPUT_BYTES: f64 = 4.0 * 1024.0 * 1024.0;
+/// Print map pixel, written as `map.png` and embedded in the report (compressed, so generous).
+const PRINT_BYTES_PER_PIXEL: f64 = 2.0;
+/// Estimates are rough; refuse only below this multiple of the estimate.
+const MARGIN: f64 = 1.5;
+/// Warn when less than this would remain after the run.
+const LOW_SPACE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Outputs of one run (grids in the chosen formats, external share, one grid per meteorological
+/// year, preview, print map and report) and the checkpoint it keeps in the runtime dir while
+/// running (two float32 grids plus one per year).
+pub fn run_bytes(cfg: &ProjectConfig) -> (u64, u64) {
+    let cells = eta::grid_cells(&serde_json::to_value(cfg).unwrap_or_default());
+    // The engine computes every configured year apart, a single one included, see `meteo_years`.
+    let years = cfg.realistic.as_ref().map_or(0.0, |r| r.years.len() as f64);
+    let format = cfg.output["format"].as_str().unwrap_or("both");
+    let (page_w_mm, page_h_mm) = if cfg.output["page_size"].as_str() == Some("A3") { (420.0, 297.0) } else { (297.0, 210.0) };
+    let dpi = cfg.output["dpi"].as_f64().unwrap_or(150.0);
+    let mut outputs = FIXED_OUTPUT_BYTES + page_w_mm * page_h_mm / (25.4 * 25.4) * dpi * dpi * PRINT_BYTES_PER_PIXEL;
+    if format != "geotiff" {
+        outputs += cells * ASC_BYTES_PER_CELL;
+    }
+    if format != "asc" {
+        outputs += cells * TIF_BYTES_PER_CELL;
+    }
+    if !cfg.external_groups.is_empty() {
+        outputs += cells * ASC_BYTES_PER_CELL;
+    }
+    outputs += years * cells * ASC_BYTES_PER_CELL;
+    (outputs as u64, (cells * (8.0 + 4.0 * years)) as u64)
+}
+
+fn human(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.1} GB", b as f64 / (1u64 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.0} MB", b as f64 / (1u64 << 20) as f64),
+        b => format!("{} KB", b.div_ceil(1024)),
+    }
+}
+
+/// Refuses when the volume holding `dir` clearly cannot take `needed` bytes, and emits `disk://low`
+/// when it would be left almost full. Volumes that cannot be queried are not checked.
+pub fn ensure_space(app: &AppHandle, dir: &Path, needed: u64, what: &str) -> Result<(), String> {
+    let Some(existing) = dir.ancestors().find(|p| p.exists()) else { return Ok(()) };
+    let Ok(available) = fs2::available_space(existing) else { return Ok(()) };
+    if (needed as f64 * MARGIN) as u64 > available {
+        return Err(format!("not enough disk space on {}: {what} needs about {}, {} free", existing.display(), human(needed), human(available)));
+    }
+    if available - needed < LOW_SPACE_BYTES {
+        let _ = app.emit("disk://low", json!({ "path": existing.display().to_string(), "what": what, "needed": needed, "available": available }));
+    }
+    Ok(())
+}
+
+/// Checks the project volume for `runs` runs of `cfg` and the runtime volume for their checkpoints
+/// (one per engine worker at a time, but paused runs keep theirs).
+pub fn ensure_run_space(app: &AppHandle, cfg: &ProjectConfig, runs: usize) -> Result<(), String> {
+    let (outputs, checkpoint) = run_bytes(cfg);
+    let what = if runs == 1 { "the run".to_string() } else { format!("{runs} runs") };
+    ensure_space(app, Path::new(&cfg.project_path), outputs * runs as u64, &what)?;
+    ensure_space(app, &crate::engine::runtime_dir(app), checkpoint * runs as u64, &format!("checkpoints of {what}"))
+}
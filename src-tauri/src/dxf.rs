@@ -0,0 +1,625 @@
+//! DXF interchange with CAD: the layout (turbines, receptors) and shadow hour contours as
+//! layered R12 ASCII geometry in the project CRS, which every AutoCAD release and civil design
+//! package opens, with metres as drawing units (R12 has no `$INSUNITS`, so CAD treats the
+//! drawing as unitless and inserts it 1:1). Surveyors' site boundaries and exclusion zones
+//! come back the other way as closed polylines on chosen layers.
+
+use crate::{audit, contours, crs, results, settings, viewer::ViewerMode, ProjectConfig};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    f64::consts::PI,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::{AppHandle, Manager, State};
+
+/// Realistic and astronomical annual limits of the German shadow flicker guideline, the usual
+/// reference in Italian permitting; the configured compliance limit is always added.
+const DEFAULT_LEVELS_H: [f64; 2] = [8.0, 30.0];
+const TEXT_HEIGHT_M: f64 = 10.0;
+const RECEPTOR_MARK_M: f64 = 5.0;
+
+// AutoCAD colour index.
+const RED: u8 = 1;
+const YELLOW: u8 = 2;
+const GREEN: u8 = 3;
+const CYAN: u8 = 4;
+const BLUE: u8 = 5;
+const MAGENTA: u8 = 6;
+const WHITE: u8 = 7;
+
+/// R12 drawing assembled entity by entity; layers are declared as they are first used.
+#[derive(Default)]
+pub struct Drawing {
+    layers: Vec<(String, u8)>,
+    entities: String,
+}
+
+impl Drawing {
+    fn pair(&mut self, code: u16, value: impl std::fmt::Display) {
+        let _ = write!(self.entities, "{code:>3}\n{value}\n");
+    }
+
+    fn entity(&mut self, kind: &str, layer: &str, color: u8) {
+        if !self.layers.iter().any(|(l, _)| l == layer) {
+            self.layers.push((layer.to_string(), color));
+        }
+        self.pair(0, kind);
+        self.pair(8, layer);
+    }
+
+    fn xy(&mut self, code: u16, (x, y): (f64, f64)) {
+        self.pair(code, format!("{x:.3}"));
+        self.pair(code + 10, format!("{y:.3}"));
+        self.pair(code + 20, "0.0");
+    }
+
+    pub fn point(&mut self, layer: &str, color: u8, p: (f64, f64)) {
+        self.entity("POINT", layer, color);
+        self.xy(10, p);
+    }
+
+    pub fn circle(&mut self, layer: &str, color: u8, centre: (f64, f64), radius: f64) {
+        self.entity("CIRCLE", layer, color);
+        self.xy(10, centre);
+        self.pair(40, format!("{radius:.3}"));
+    }
+
+    pub fn text(&mut self, layer: &str, color: u8, at: (f64, f64), text: &str) {
+        self.entity("TEXT", layer, color);
+        self.xy(10, at);
+        self.pair(40, TEXT_HEIGHT_M);
+        self.pair(1, text.replace(['\n', '\r'], " "));
+    }
+
+    /// Closed rings given with their first point repeated are written as closed polylines.
+    pub fn polyline(&mut self, layer: &str, color: u8, points: &[(f64, f64)]) {
+        let closed = points.len() > 2 && points.first() == points.last();
+        let points = if closed { &points[..points.len() - 1] } else { points };
+        self.entity("POLYLINE", layer, color);
+        self.pair(66, 1);
+        self.xy(10, (0.0, 0.0));
+        self.pair(70, if closed { 1 } else { 0 });
+        for &p in points {
+            self.entity("VERTEX", layer, color);
+            self.xy(10, p);
+        }
+        self.entity("SEQEND", layer, color);
+    }
+
+    pub fn finish(self) -> String {
+        let mut out = String::new();
+        let mut pair = |code: u16, value: &dyn std::fmt::Display| {
+            let _ = write!(out, "{code:>3}\n{value}\n");
+        };
+        for (code, value) in [(0, "SECTION"), (2, "HEADER"), (9, "$ACADVER"), (1, "AC1009"), (0, "ENDSEC")] {
+            pair(code, &value);
+        }
+        for (code, value) in [(0, "SECTION"), (2, "TABLES"), (0, "TABLE"), (2, "LAYER")] {
+            pair(code, &value);
+        }
+        pair(70, &self.layers.len());
+        for (name, color) in &self.layers {
+            pair(0, &"LAYER");
+            pair(2, name);
+            pair(70, &0);
+            pair(62, color);
+            pair(6, &"CONTINUOUS");
+        }
+        for (code, value) in [(0, "ENDTAB"), (0, "ENDSEC"), (0, "SECTION"), (2, "ENTITIES")] {
+            pair(code, &value);
+        }
+        out.push_str(&self.entities);
+        out.push_str("  0\nENDSEC\n  0\nEOF\n");
+        out
+    }
+}
+
+fn contour_color(i: usize) -> u8 {
+    [YELLOW, MAGENTA, GREEN, CYAN][i % 4]
+}
+
+fn layout(cfg: &ProjectConfig, levels: &[f64], warnings: &mut Vec<String>) -> (Drawing, usize) {
+    let mut d = Drawing::default();
+    for t in &cfg.turbines {
+        d.point("WSS_TURBINES", RED, (t.x, t.y));
+        d.circle("WSS_ROTORS", RED, (t.x, t.y), t.rotor_diameter_m / 2.0);
+        d.text("WSS_TURBINE_LABELS", RED, (t.x + t.rotor_diameter_m / 2.0, t.y), &format!("{} H{:.0} D{:.0}", t.id, t.hub_height_m, t.rotor_diameter_m));
+    }
+    for g in &cfg.external_groups {
+        for t in &g.turbines {
+            d.point("WSS_EXTERNAL_TURBINES", CYAN, (t.x, t.y));
+            d.text("WSS_EXTERNAL_TURBINES", CYAN, (t.x, t.y), &format!("{} ({})", t.id, g.name));
+        }
+    }
+    for r in &cfg.receptors {
+        d.point("WSS_RECEPTORS", BLUE, (r.x, r.y));
+        let m = RECEPTOR_MARK_M;
+        d.polyline("WSS_RECEPTORS", BLUE, &[(r.x - m, r.y - m), (r.x + m, r.y - m), (r.x + m, r.y + m), (r.x - m, r.y + m), (r.x - m, r.y - m)]);
+        d.text("WSS_RECEPTOR_LABELS", BLUE, (r.x + m, r.y + m), &r.id);
+    }
+    if let Some(boundary) = &cfg.site_boundary {
+        for ring in geojson_rings(boundary) {
+            d.polyline("WSS_SITE_BOUNDARY", WHITE, &ring);
+        }
+    }
+    let mut lines = 0;
+    match results::own_grid(cfg) {
+        Some(grid) => {
+            for (i, contour) in contours::trace(&grid, levels).iter().enumerate() {
+                // R12 layer names allow no dots.
+                let layer = format!("WSS_CONTOUR_{}H", contour.level).replace('.', "_");
+                for line in contour.lines.iter().filter(|l| l.len() > 1) {
+                    d.polyline(&layer, contour_color(i), line);
+                    lines += 1;
+                }
+            }
+        }
+        None => warnings.push("no result grid in outputs/: contours not exported, run the calculation first".to_string()),
+    }
+    (d, lines)
+}
+
+#[derive(Serialize)]
+pub struct DxfExport {
+    path: String,
+    contour_lines: usize,
+    warnings: Vec<String>,
+}
+
+/// `levels` are shadow hours per year, default 8 and 30.
+#[tauri::command]
+pub fn export_dxf(cfg: ProjectConfig, levels: Option<Vec<f64>>, app: AppHandle, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Option<DxfExport>, String> {
+    viewer.check(window.label())?;
+    let levels = contour_levels(levels, settings::load(&app).compliance_limit_h);
+    let Some(path) = rfd::FileDialog::new().add_filter("DXF", &["dxf"]).set_directory(&cfg.project_path).set_file_name("layout.dxf").save_file() else {
+        return Ok(None);
+    };
+    let export = write_layout(&cfg, &levels, &path)?;
+    audit::record(&app, &cfg, "dxf_exported", json!({ "path": export.path, "levels_h": levels }));
+    Ok(Some(export))
+}
+
+/// The requested levels, or the guideline ones, always with the compliance limit, ascending.
+pub fn contour_levels(levels: Option<Vec<f64>>, limit: f64) -> Vec<f64> {
+    let mut levels = levels.filter(|l| !l.is_empty()).unwrap_or(DEFAULT_LEVELS_H.to_vec());
+    if !levels.contains(&limit) {
+        levels.push(limit);
+    }
+    levels.sort_by(f64::total_cmp);
+    levels
+}
+
+pub fn write_layout(cfg: &ProjectConfig, levels: &[f64], path: &Path) -> Result<DxfExport, String> {
+    let mut warnings = vec![];
+    let (drawing, contour_lines) = layout(cfg, levels, &mut warnings);
+    fs::write(path, drawing.finish()).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(DxfExport { path: path.display().to_string(), contour_lines, warnings })
+}
+
+pub const BOUNDARY_FILE: &str = "site_boundary.geojson";
+pub const EXCLUSIONS_FILE: &str = "exclusion_zones.geojson";
+
+/// Arcs (polyline bulges) are densified with at most this angle per chord.
+const ARC_STEP_RAD: f64 = 5.0 * PI / 180.0;
+
+/// Metres per drawing unit for a `$INSUNITS` code or a unit name; 0 (unitless) has none.
+fn unit_scale(unit: &str) -> Option<f64> {
+    Some(match unit.trim().to_lowercase().as_str() {
+        "1" | "in" | "inch" => 0.0254,
+        "2" | "ft" | "foot" | "feet" => 0.3048,
+        "3" | "mi" | "mile" => 1609.344,
+        "4" | "mm" => 0.001,
+        "5" | "cm" => 0.01,
+        "6" | "m" => 1.0,
+        "7" | "km" => 1000.0,
+        "10" | "yd" | "yard" => 0.9144,
+        "14" | "dm" => 0.1,
+        "21" | "us_ft" | "usft" => 1200.0 / 3937.0,
+        _ => return None,
+    })
+}
+
+pub type Ring = Vec<(f64, f64)>;
+
+/// A polyline read from the ENTITIES section.
+struct Polyline {
+    layer: String,
+    points: Ring,
+    /// Bulge (tan of a quarter of the arc angle) of the segment starting at each point.
+    bulges: Vec<f64>,
+    closed: bool,
+}
+
+impl Polyline {
+    fn new(layer: String, closed: bool) -> Self {
+        Polyline { layer, points: vec![], bulges: vec![], closed }
+    }
+
+    /// Closed by flag or by a repeated first point; the ring is returned without repetition and
+    /// with arcs densified.
+    fn ring(&self) -> Option<Ring> {
+        let repeated = self.points.len() > 3 && self.points.first() == self.points.last();
+        if !self.closed && !repeated {
+            return None;
+        }
+        let n = if repeated { self.points.len() - 1 } else { self.points.len() };
+        if n < 3 {
+            return None;
+        }
+        let mut ring = vec![];
+        for i in 0..n {
+            let (p, q) = (self.points[i], self.points[(i + 1) % n]);
+            ring.push(p);
+            let bulge = self.bulges.get(i).copied().unwrap_or(0.0);
+            if bulge != 0.0 {
+                ring.extend(arc(p, q, bulge));
+            }
+        }
+        Some(ring)
+    }
+}
+
+/// Intermediate points of the arc from `p` to `q`, counter-clockwise for positive bulges.
+fn arc(p: (f64, f64), q: (f64, f64), bulge: f64) -> Vec<(f64, f64)> {
+    let theta = 4.0 * bulge.atan();
+    let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+    let chord = dx.hypot(dy);
+    if chord == 0.0 {
+        return vec![];
+    }
+    // The centre sits on the chord's left normal for counter-clockwise arcs.
+    let offset = chord / 2.0 / (theta / 2.0).tan();
+    let centre = ((p.0 + q.0) / 2.0 - dy / chord * offset, (p.1 + q.1) / 2.0 + dx / chord * offset);
+    let radius = (p.0 - centre.0).hypot(p.1 - centre.1);
+    let start = (p.1 - centre.1).atan2(p.0 - centre.0);
+    let steps = (theta.abs() / ARC_STEP_RAD).ceil() as usize;
+    (1..steps).map(|i| start + theta * i as f64 / steps as f64).map(|a| (centre.0 + radius * a.cos(), centre.1 + radius * a.sin())).collect()
+}
+
+/// Every ring of a GeoJSON Polygon or MultiPolygon, as written (closed).
+pub fn geojson_rings(geometry: &Value) -> Vec<Ring> {
+    let ring = |r: &Value| -> Ring { r.as_array().into_iter().flatten().filter_map(|p| Some((p.get(0)?.as_f64()?, p.get(1)?.as_f64()?))).collect() };
+    let polygon = |p: &Value| -> Vec<Ring> { p.as_array().into_iter().flatten().map(ring).collect() };
+    match geometry.get("type").and_then(Value::as_str) {
+        Some("Polygon") => polygon(&geometry["coordinates"]),
+        Some("MultiPolygon") => geometry["coordinates"].as_array().into_iter().flatten().flat_map(polygon).collect(),
+        _ => vec![],
+    }
+}
+
+fn area(ring: &[(f64, f64)]) -> f64 {
+    let twice: f64 = (0..ring.len()).map(|i| (ring[i], ring[(i + 1) % ring.len()])).map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1).sum();
+    twice.abs() / 2.0
+}
+
+fn inside(ring: &[(f64, f64)], (x, y): (f64, f64)) -> bool {
+    let mut hit = false;
+    for (i, &(x1, y1)) in ring.iter().enumerate() {
+        let (x2, y2) = ring[(i + 1) % ring.len()];
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            hit = !hit;
+        }
+    }
+    hit
+}
+
+/// Polylines and the `$INSUNITS` code of an ASCII DXF, any release.
+struct Parsed {
+    insunits: Option<String>,
+    polylines: Vec<Polyline>,
+    /// Entity types other than polylines, by count, reported as ignored.
+    ignored: BTreeMap<String, usize>,
+}
+
+/// Group code and value pairs of one entity or header variable.
+type Record<'a> = (&'a str, Vec<(i32, &'a str)>);
+
+/// Records of each section by name; a record starts at every group code 0 (or 9 in the header).
+fn sections(text: &str) -> Result<BTreeMap<String, Vec<Record<'_>>>, String> {
+    if text.starts_with("AutoCAD Binary DXF") {
+        return Err("binary DXF is not supported, save as ASCII DXF".to_string());
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    let mut out: BTreeMap<String, Vec<Record>> = BTreeMap::new();
+    let mut section: Option<String> = None;
+    let mut pending_section = false;
+    for pair in lines.chunks_exact(2) {
+        let code: i32 = pair[0].trim().parse().map_err(|_| format!("invalid group code: {}", pair[0].trim()))?;
+        let value = pair[1].trim();
+        match (code, value) {
+            (0, "SECTION") => pending_section = true,
+            (2, name) if pending_section => {
+                pending_section = false;
+                section = Some(name.to_string());
+            }
+            (0, "ENDSEC" | "EOF") => section = None,
+            _ => {
+                let Some(records) = section.as_ref().map(|s| out.entry(s.clone()).or_default()) else { continue };
+                let starts = code == 0 || (code == 9 && section.as_deref() == Some("HEADER"));
+                match records.last_mut() {
+                    Some(last) if !starts => last.1.push((code, value)),
+                    _ => records.push((value, vec![])),
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn number(code: i32, value: &str) -> Result<f64, String> {
+    value.parse().map_err(|_| format!("invalid number for group {code}: {value}"))
+}
+
+fn layer(fields: &[(i32, &str)]) -> String {
+    fields.iter().find(|(c, _)| *c == 8).map_or("0", |(_, v)| *v).to_string()
+}
+
+fn flags(fields: &[(i32, &str)]) -> u32 {
+    fields.iter().find(|(c, _)| *c == 70).and_then(|(_, v)| v.parse().ok()).unwrap_or(0)
+}
+
+/// (x, y, bulge) of a VERTEX.
+fn vertex(fields: &[(i32, &str)]) -> Result<Option<(f64, f64, f64)>, String> {
+    let get = |code: i32| fields.iter().find(|(c, _)| *c == code).map(|(_, v)| number(code, v)).transpose();
+    Ok(match (get(10)?, get(20)?) {
+        (Some(x), Some(y)) => Some((x, y, get(42)?.unwrap_or(0.0))),
+        _ => None,
+    })
+}
+
+fn parse(text: &str) -> Result<Parsed, String> {
+    let sections = sections(text)?;
+    let insunits = sections.get("HEADER").into_iter().flatten().find(|(name, _)| *name == "$INSUNITS").and_then(|(_, f)| f.first()).map(|(_, v)| v.to_string());
+    let mut out = Parsed { insunits, polylines: vec![], ignored: BTreeMap::new() };
+    let mut entities = sections.get("ENTITIES").into_iter().flatten().peekable();
+    while let Some((kind, fields)) = entities.next() {
+        match *kind {
+            "LWPOLYLINE" => {
+                let mut p = Polyline::new(layer(fields), flags(fields) & 1 != 0);
+                for &(code, value) in fields {
+                    match code {
+                        10 => {
+                            p.points.push((number(code, value)?, 0.0));
+                            p.bulges.push(0.0);
+                        }
+                        20 => p.points.last_mut().ok_or("LWPOLYLINE y before x")?.1 = number(code, value)?,
+                        42 => *p.bulges.last_mut().ok_or("LWPOLYLINE bulge before vertex")? = number(code, value)?,
+                        _ => {}
+                    }
+                }
+                out.polylines.push(p);
+            }
+            "POLYLINE" => {
+                let f = flags(fields);
+                let mut p = Polyline::new(layer(fields), f & 1 != 0);
+                while let Some((_, v)) = entities.next_if(|(k, _)| *k == "VERTEX") {
+                    if let Some((x, y, bulge)) = vertex(v)? {
+                        p.points.push((x, y));
+                        p.bulges.push(bulge);
+                    }
+                }
+                entities.next_if(|(k, _)| *k == "SEQEND");
+                // 3D and polyface meshes are not outlines.
+                if f & (16 | 64) == 0 {
+                    out.polylines.push(p);
+                } else {
+                    *out.ignored.entry("POLYLINE mesh".to_string()).or_default() += 1;
+                }
+            }
+            other => *out.ignored.entry(other.to_string()).or_default() += 1,
+        }
+    }
+    Ok(out)
+}
+
+/// Metres per drawing unit: `units` when given, else `$INSUNITS`, else metres with a warning.
+fn scale(parsed: &Parsed, units: Option<&str>, warnings: &mut Vec<String>) -> Result<f64, String> {
+    if let Some(u) = units.filter(|u| !u.trim().is_empty()) {
+        return unit_scale(u).ok_or(format!("unknown drawing unit: {u}"));
+    }
+    match parsed.insunits.as_deref().and_then(unit_scale) {
+        Some(s) => Ok(s),
+        None => {
+            warnings.push("the drawing declares no units: metres assumed".to_string());
+            Ok(1.0)
+        }
+    }
+}
+
+/// Closed rings in the project CRS per layer. Drawings in lon/lat, as some GIS exports are, are
+/// projected; otherwise coordinates are scaled to metres and assumed to be in the project CRS.
+fn rings(parsed: &Parsed, scale: f64, epsg: &str, warnings: &mut Vec<String>) -> Result<BTreeMap<String, Vec<Ring>>, String> {
+    let mut out: BTreeMap<String, Vec<Ring>> = BTreeMap::new();
+    for p in &parsed.polylines {
+        if let Some(ring) = p.ring() {
+            out.entry(p.layer.clone()).or_default().push(ring);
+        }
+    }
+    let all = || out.values().flatten().flatten();
+    if all().next().is_some() && all().all(|&(x, y)| crs::looks_geographic(x, y)) {
+        let project = crs::parse_epsg(epsg)?;
+        warnings.push(format!("coordinates look like lon/lat: projected from WGS84 to {epsg}"));
+        out.values_mut().flatten().flatten().for_each(|p| *p = project.project(p.0, p.1));
+    } else if scale != 1.0 {
+        out.values_mut().flatten().flatten().for_each(|p| *p = (p.0 * scale, p.1 * scale));
+    }
+    Ok(out)
+}
+
+/// Rings grouped into polygons, largest first: a ring inside a larger one is its hole, a ring
+/// inside a hole starts a new polygon (an island).
+fn polygons(mut rings: Vec<Ring>) -> Vec<Vec<Ring>> {
+    rings.sort_by(|a, b| area(b).total_cmp(&area(a)));
+    let mut out: Vec<Vec<Ring>> = vec![];
+    for ring in rings {
+        let probe = ring[0];
+        match out.iter_mut().find(|p| inside(&p[0], probe)) {
+            Some(p) if !p[1..].iter().any(|hole| inside(hole, probe)) => p.push(ring),
+            _ => out.push(vec![ring]),
+        }
+    }
+    out
+}
+
+fn polygon_area(polygon: &[Ring]) -> f64 {
+    area(&polygon[0]) - polygon[1..].iter().map(|h| area(h)).sum::<f64>()
+}
+
+/// RFC 7946 rings: closed, shells counter-clockwise and holes clockwise.
+fn coordinates(polygon: &[Ring]) -> Value {
+    let rings: Vec<Value> = polygon
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            let signed: f64 = (0..ring.len()).map(|j| (ring[j], ring[(j + 1) % ring.len()])).map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1).sum();
+            let mut r = ring.clone();
+            if (signed > 0.0) != (i == 0) {
+                r.reverse();
+            }
+            r.push(r[0]);
+            json!(r.iter().map(|&(x, y)| [x, y]).collect::<Vec<_>>())
+        })
+        .collect();
+    json!(rings)
+}
+
+fn geometry(polygons: &[Vec<Ring>]) -> Option<Value> {
+    match polygons {
+        [] => None,
+        [one] => Some(json!({ "type": "Polygon", "coordinates": coordinates(one) })),
+        many => Some(json!({ "type": "MultiPolygon", "coordinates": many.iter().map(|p| coordinates(p)).collect::<Vec<_>>() })),
+    }
+}
+
+pub fn feature_collection(epsg: &str, features: Vec<Value>) -> Value {
+    let code = epsg.trim_start_matches("EPSG:");
+    json!({ "type": "FeatureCollection", "crs": { "type": "name", "properties": { "name": format!("urn:ogc:def:crs:EPSG::{code}") } }, "features": features })
+}
+
+fn read_dxf(path: &Path) -> Result<Parsed, String> {
+    let text = String::from_utf8_lossy(&fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?).to_string();
+    parse(&text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+#[derive(Serialize)]
+pub struct DxfLayer {
+    name: String,
+    closed: usize,
+    open: usize,
+    /// Area of the layer's closed polylines in square metres, by the declared units.
+    area_m2: f64,
+}
+
+#[derive(Serialize)]
+pub struct DxfLayers {
+    path: String,
+    /// `$INSUNITS` code, `None` when the drawing declares none.
+    units: Option<String>,
+    layers: Vec<DxfLayer>,
+}
+
+/// Picks a DXF and lists its layers with polyline counts, to choose boundary and exclusion layers.
+#[tauri::command]
+pub fn list_dxf_layers() -> Result<Option<DxfLayers>, String> {
+    let Some(path) = rfd::FileDialog::new().add_filter("DXF", &["dxf"]).pick_file() else {
+        return Ok(None);
+    };
+    let parsed = read_dxf(&path)?;
+    let s = parsed.insunits.as_deref().and_then(unit_scale).unwrap_or(1.0);
+    let mut layers: BTreeMap<&str, DxfLayer> = BTreeMap::new();
+    for p in &parsed.polylines {
+        let l = layers.entry(&p.layer).or_insert_with(|| DxfLayer { name: p.layer.clone(), closed: 0, open: 0, area_m2: 0.0 });
+        match p.ring() {
+            Some(ring) => {
+                l.closed += 1;
+                l.area_m2 += area(&ring) * s * s;
+            }
+            None => l.open += 1,
+        }
+    }
+    Ok(Some(DxfLayers { path: path.display().to_string(), units: parsed.insunits.clone(), layers: layers.into_values().collect() }))
+}
+
+#[derive(Serialize)]
+pub struct DxfZones {
+    /// The project with `site_boundary` replaced when a boundary layer was read.
+    cfg: ProjectConfig,
+    /// GeoJSON geometries in the project CRS.
+    boundary: Option<Value>,
+    exclusion_zones: Option<Value>,
+    boundary_area_m2: f64,
+    exclusion_count: usize,
+    files: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Reads closed polylines of `boundary_layer` as the site boundary and of `exclusion_layers` as
+/// exclusion zones and writes them to the project folder as GeoJSON; the boundary also becomes
+/// the project's `site_boundary`. `units` overrides the drawing's `$INSUNITS` (`m`, `cm`, `mm`,
+/// `km`, `ft`, `us_ft`, `in`).
+#[tauri::command]
+pub fn import_dxf_zones(
+    mut cfg: ProjectConfig,
+    path: String,
+    boundary_layer: Option<String>,
+    exclusion_layers: Vec<String>,
+    units: Option<String>,
+    window: tauri::Window,
+    viewer: State<ViewerMode>,
+) -> Result<DxfZones, String> {
+    viewer.check(window.label())?;
+    let parsed = read_dxf(Path::new(&path))?;
+    let mut warnings = vec![];
+    let s = scale(&parsed, units.as_deref(), &mut warnings)?;
+    let mut by_layer = rings(&parsed, s, &cfg.epsg, &mut warnings)?;
+    if !parsed.ignored.is_empty() {
+        let kinds: Vec<String> = parsed.ignored.iter().map(|(k, n)| format!("{n} {k}")).collect();
+        warnings.push(format!("ignored entities: {}", kinds.join(", ")));
+    }
+    let mut take = |layer: &str, warnings: &mut Vec<String>| {
+        let rings = by_layer.remove(layer).unwrap_or_default();
+        if rings.is_empty() {
+            warnings.push(format!("layer {layer}: no closed polylines"));
+        }
+        rings
+    };
+    let project = PathBuf::from(&cfg.project_path);
+    let mut files = vec![];
+    let mut write = |name: &str, features: Vec<Value>| -> Result<(), String> {
+        let file = project.join(name);
+        let text = serde_json::to_string_pretty(&feature_collection(&cfg.epsg, features)).map_err(|e| e.to_string())?;
+        fs::write(&file, text).map_err(|e| format!("{}: {e}", file.display()))?;
+        files.push(file.display().to_string());
+        Ok(())
+    };
+
+    let (mut boundary, mut boundary_area_m2) = (None, 0.0);
+    if let Some(layer) = boundary_layer.filter(|l| !l.is_empty()) {
+        let polys = polygons(take(&layer, &mut warnings));
+        boundary_area_m2 = polys.iter().map(|p| polygon_area(p)).sum();
+        boundary = geometry(&polys);
+        if let Some(g) = &boundary {
+            write(BOUNDARY_FILE, vec![json!({ "type": "Feature", "properties": { "layer": layer, "area_m2": boundary_area_m2 }, "geometry": g })])?;
+        }
+    }
+    let mut zones: Vec<Vec<Ring>> = vec![];
+    let mut features = vec![];
+    for layer in &exclusion_layers {
+        for p in polygons(take(layer, &mut warnings)) {
+            features.push(json!({ "type": "Feature", "properties": { "layer": layer, "area_m2": polygon_area(&p) }, "geometry": geometry(std::slice::from_ref(&p)) }));
+            zones.push(p);
+        }
+    }
+    if !features.is_empty() {
+        write(EXCLUSIONS_FILE, features)?;
+    }
+    audit::record(window.app_handle(), &cfg, "dxf_zones_imported", json!({ "path": path, "boundary_area_m2": boundary_area_m2, "exclusion_zones": zones.len() }));
+    if boundary.is_some() {
+        cfg.site_boundary = boundary.clone();
+    }
+    Ok(DxfZones { cfg, boundary, exclusion_zones: geometry(&zones), boundary_area_m2, exclusion_count: zones.len(), files, warnings })
+}
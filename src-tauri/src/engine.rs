@@ -0,0 +1,281 @@
+//! HTTP client for the local engine (FastAPI on `127.0.0.1:<port>`, see `start_engine`), or for
+//! a remote one over HTTPS when `Settings::remote_engine` is set (see `tls`).
+//!
+//! Requests are retried on connection errors and 502-504 with exponential backoff. Runs carry an
+//! `Idempotency-Key`, so a retried submission whose first response was lost returns the same
+//! engine job instead of starting a second one.
+//!
+//! Failures come back as `EngineError`, the schema shared with the engine (`error_detail` there):
+//! job errors from the snapshot, rejected requests from the error body.
+//!
+//! With the `grpc` feature the same calls go over gRPC when the engine advertises a `grpc_port`
+//! in `port.json` (see `engine_grpc`), falling back to HTTP otherwise.
+
+use crate::{settings::RemoteEngine, tls, workspace, EngineState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fmt,
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Manager};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(700);
+pub(crate) const RETRIES: u32 = 5;
+pub(crate) const FIRST_BACKOFF: Duration = Duration::from_millis(250);
+/// Several synthetic runs at once on a slow machine take minutes.
+const BENCHMARK_TIMEOUT: Duration = Duration::from_secs(900);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a job waits for its engine to come back from `restart_engine`.
+const RESTART_WAIT: Duration = Duration::from_secs(15);
+
+/// An engine failure: a stable `code`, the `stage` it happened in (`validate`, `dem`, `raycast`,
+/// `aggregate`, `export`; `request` for rejected calls, `shell` when the engine was not reached)
+/// and the code's parameters in `details`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EngineError {
+    pub code: String,
+    pub stage: String,
+    pub message: String,
+    #[serde(default)]
+    pub details: Value,
+}
+
+impl EngineError {
+    /// The failure of a job snapshot; older engines only report the message.
+    pub fn from_snapshot(state: &Value) -> Self {
+        serde_json::from_value(state["error_detail"].clone()).unwrap_or_else(|_| EngineError {
+            code: "internal".to_string(),
+            stage: state["stage"].as_str().unwrap_or("unknown").to_string(),
+            message: state["error"].as_str().unwrap_or("engine job failed").to_string(),
+            details: Value::Null,
+        })
+    }
+}
+
+impl From<String> for EngineError {
+    fn from(message: String) -> Self {
+        EngineError { code: "engine_unavailable".to_string(), stage: "shell".to_string(), message, details: Value::Null }
+    }
+}
+
+impl From<ureq::Error> for EngineError {
+    /// Error bodies carry the schema in `detail`; anything else keeps ureq's description.
+    fn from(e: ureq::Error) -> Self {
+        match e {
+            ureq::Error::Status(status, res) => {
+                let body: Value = res.into_json().unwrap_or_default();
+                serde_json::from_value(body["detail"].clone()).unwrap_or_else(|_| EngineError {
+                    code: format!("http_{status}"),
+                    stage: "request".to_string(),
+                    message: body["detail"].as_str().map_or_else(|| format!("engine returned HTTP {status}"), str::to_string),
+                    details: Value::Null,
+                })
+            }
+            e => e.to_string().into(),
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}, {})", self.message, self.code, self.stage)
+    }
+}
+
+impl From<EngineError> for String {
+    fn from(e: EngineError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Retries transient failures; anything else (a 4xx, a 500) is returned at once.
+fn with_retry<T>(mut request: impl FnMut() -> Result<T, ureq::Error>) -> Result<T, ureq::Error> {
+    let mut backoff = FIRST_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        match request() {
+            Err(ureq::Error::Transport(_) | ureq::Error::Status(502..=504, _)) if attempt < RETRIES => {
+                attempt += 1;
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Header carrying a remote engine's `WSS_API_TOKEN`.
+const TOKEN_HEADER: &str = "X-WSS-Token";
+
+pub struct EngineClient {
+    base: String,
+    agent: ureq::Agent,
+    token: Option<String>,
+    #[cfg(feature = "grpc")]
+    grpc: Option<crate::engine_grpc::GrpcClient>,
+}
+
+impl EngineClient {
+    pub fn new(port: u16) -> Self {
+        let agent = ureq::AgentBuilder::new().timeout_connect(Duration::from_secs(2)).timeout(Duration::from_secs(30)).build();
+        EngineClient {
+            base: format!("http://127.0.0.1:{port}"),
+            agent,
+            token: None,
+            #[cfg(feature = "grpc")]
+            grpc: None,
+        }
+    }
+
+    /// Client for an engine on another machine; its TLS trust comes from the settings.
+    pub fn remote(remote: &RemoteEngine) -> Result<Self, String> {
+        tls::check_url(&remote.url)?;
+        let agent = ureq::AgentBuilder::new().tls_config(tls::client_config(remote)?).timeout_connect(Duration::from_secs(10)).timeout(Duration::from_secs(60)).build();
+        Ok(EngineClient {
+            base: remote.url.trim().trim_end_matches('/').to_string(),
+            agent,
+            token: Some(remote.token.trim().to_string()).filter(|t| !t.is_empty()),
+            #[cfg(feature = "grpc")]
+            grpc: None,
+        })
+    }
+
+    fn get(&self, url: &str) -> ureq::Request {
+        self.authorized(self.agent.get(url))
+    }
+
+    fn post(&self, url: &str) -> ureq::Request {
+        self.authorized(self.agent.post(url))
+    }
+
+    fn authorized(&self, request: ureq::Request) -> ureq::Request {
+        match &self.token {
+            Some(token) => request.set(TOKEN_HEADER, token),
+            None => request,
+        }
+    }
+
+    /// Starts a run and returns the engine's job id; the same `key` always maps to the same job.
+    pub fn submit_run(&self, cfg: &Value, key: &str) -> Result<String, EngineError> {
+        #[cfg(feature = "grpc")]
+        if let Some(g) = &self.grpc {
+            return g.submit_run(cfg, key);
+        }
+        let url = format!("{}/jobs/run", self.base);
+        let res: Value = with_retry(|| self.post(&url).set("Idempotency-Key", key).send_json(cfg))?.into_json().map_err(|e| e.to_string())?;
+        Ok(res["id"].as_str().map(str::to_string).ok_or_else(|| "engine returned no job id".to_string())?)
+    }
+
+    pub fn same_engine(&self, other: &EngineClient) -> bool {
+        self.base == other.base
+    }
+
+    /// Asks a local engine to stop serving, see `stop_engine`; no retries, a hung engine is killed.
+    pub fn shutdown(&self) -> Result<(), EngineError> {
+        self.post(&format!("{}/shutdown", self.base)).timeout(SHUTDOWN_TIMEOUT).call()?;
+        Ok(())
+    }
+
+    /// The engine's job snapshot (status, progress, logs, outputs, stats); `None` when the engine
+    /// does not know the job, i.e. it was restarted since.
+    pub fn job(&self, id: &str) -> Result<Option<Value>, EngineError> {
+        #[cfg(feature = "grpc")]
+        if let Some(g) = &self.grpc {
+            return g.watch(id, true, |_| {});
+        }
+        match with_retry(|| self.get(&format!("{}/jobs/{id}", self.base)).call()) {
+            Ok(res) => Ok(res.into_json().map(Some).map_err(|e| e.to_string())?),
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Asks the engine to checkpoint and stop; the job reports `paused` shortly after.
+    pub fn pause(&self, id: &str) -> Result<(), EngineError> {
+        #[cfg(feature = "grpc")]
+        if let Some(g) = &self.grpc {
+            return g.pause(id);
+        }
+        with_retry(|| self.post(&format!("{}/jobs/{id}/pause", self.base)).call()).map(|_| ()).map_err(EngineError::from)
+    }
+
+    /// The engine's memory estimate for `cfg` against its free RAM, see `jobs::MemoryPlan`.
+    pub fn memory_plan(&self, cfg: &Value) -> Result<Value, EngineError> {
+        let url = format!("{}/jobs/memory", self.base);
+        Ok(with_retry(|| self.post(&url).send_json(cfg))?.into_json().map_err(|e| e.to_string())?)
+    }
+
+    /// Times `concurrency` copies of the engine's standard synthetic run started together; over
+    /// HTTP on both transports, it is not a job.
+    pub fn benchmark(&self, concurrency: usize) -> Result<Value, EngineError> {
+        let url = format!("{}/benchmark", self.base);
+        let res = self.post(&url).query("concurrency", &concurrency.to_string()).timeout(BENCHMARK_TIMEOUT).call()?;
+        Ok(res.into_json().map_err(|e| e.to_string())?)
+    }
+
+    /// Polls the job until the engine reports `done`, `error` or `paused`, passing every snapshot
+    /// to `on_update`; `None` if the engine lost the job.
+    pub fn wait(&self, id: &str, mut on_update: impl FnMut(&Value)) -> Result<Option<Value>, EngineError> {
+        #[cfg(feature = "grpc")]
+        if let Some(g) = &self.grpc {
+            return g.watch(id, false, on_update);
+        }
+        loop {
+            let Some(state) = self.job(id)? else { return Ok(None) };
+            on_update(&state);
+            if matches!(state["status"].as_str(), Some("done" | "error" | "paused")) {
+                return Ok(Some(state));
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Shared with the engine (`WSS_RUNTIME_DIR`): port file, checkpoints, paused jobs.
+pub fn runtime_dir(app: &AppHandle) -> PathBuf {
+    crate::portable::data_dir(app).join("runtime")
+}
+
+/// Waits up to `RESTART_WAIT` for `project_path` to be served by another engine than `old`: one
+/// restarted on a new port, or the main one once its window's engine has stopped.
+pub fn replaced(app: &AppHandle, project_path: &str, old: &EngineClient) -> bool {
+    let deadline = Instant::now() + RESTART_WAIT;
+    loop {
+        if client(app, project_path).is_ok_and(|c| !c.same_engine(old)) {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Port file of the engine of a project handle, see `workspace`.
+pub fn port_file(app: &AppHandle, handle: &str) -> PathBuf {
+    runtime_dir(app).join(if handle == workspace::MAIN { "port.json".to_string() } else { format!("port-{handle}.json") })
+}
+
+/// Client of the engine running `project_path`: its window's, or the main one.
+pub fn client(app: &AppHandle, project_path: &str) -> Result<EngineClient, String> {
+    if let Some(remote) = crate::settings::load(app).remote_engine {
+        return EngineClient::remote(&remote);
+    }
+    let ports = app.state::<EngineState>().ports.lock().map_err(|e| e.to_string())?.clone();
+    #[cfg_attr(not(feature = "grpc"), allow(unused_variables))]
+    let (handle, port) = ports.get_key_value(&workspace::handle_for(app, project_path)).or(ports.get_key_value(workspace::MAIN)).map(|(h, p)| (h.clone(), *p)).ok_or_else(|| "engine port unavailable".to_string())?;
+    #[cfg_attr(not(feature = "grpc"), allow(unused_mut))]
+    let mut client = EngineClient::new(port);
+    #[cfg(feature = "grpc")]
+    {
+        let ports: Option<Value> = std::fs::read_to_string(port_file(app, &handle)).ok().and_then(|raw| serde_json::from_str(&raw).ok());
+        if let Some(grpc_port) = ports.and_then(|p| p["grpc_port"].as_u64()) {
+            // A broken gRPC listener should not stop runs: HTTP serves the same jobs.
+            client.grpc = crate::engine_grpc::GrpcClient::connect(grpc_port as u16).ok();
+        }
+    }
+    Ok(client)
+}
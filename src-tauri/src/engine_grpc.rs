@@ -0,0 +1,133 @@
+//! gRPC client for the engine (`proto/engine.proto`, feature `grpc`). Job snapshots arrive as a
+//! stream when they change, carrying only new log lines, instead of being polled as JSON. Calls
+//! block on a private runtime so `EngineClient` can use it in place of HTTP, with the same retries.
+
+use crate::engine::{EngineError, FIRST_BACKOFF, RETRIES};
+use serde_json::{json, Value};
+use std::{thread, time::Duration};
+use tonic::transport::{Channel, Endpoint};
+
+// Generated; the file transfer calls are not needed while the engine shares our disk.
+#[allow(dead_code)]
+mod pb {
+    tonic::include_proto!("wss.engine");
+}
+
+use pb::engine_client::EngineClient;
+
+/// Log lines kept per snapshot, as the HTTP API returns.
+const LOG_TAIL: usize = 400;
+
+pub struct GrpcClient {
+    runtime: tokio::runtime::Runtime,
+    channel: Channel,
+}
+
+/// Rejected calls carry the HTTP API's error schema as JSON in the `error-detail` trailer.
+fn status(s: tonic::Status) -> EngineError {
+    s.metadata()
+        .get("error-detail")
+        .and_then(|d| d.to_str().ok())
+        .and_then(|d| serde_json::from_str(d).ok())
+        .unwrap_or_else(|| format!("engine: {}", s.message()).into())
+}
+
+/// `engine::with_retry` for gRPC: an unreachable or overloaded engine is tried again.
+fn with_retry<T>(mut call: impl FnMut() -> Result<T, tonic::Status>) -> Result<T, tonic::Status> {
+    let mut backoff = FIRST_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        match call() {
+            Err(s) if matches!(s.code(), tonic::Code::Unavailable | tonic::Code::DeadlineExceeded) && attempt < RETRIES => {
+                attempt += 1;
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// The snapshot in the HTTP API's JSON shape, so job handling is the same on both transports.
+fn to_value(s: pb::JobSnapshot, logs: &[String]) -> Value {
+    let bounds = (s.overlay_bounds.len() == 4).then(|| json!([[s.overlay_bounds[0], s.overlay_bounds[1]], [s.overlay_bounds[2], s.overlay_bounds[3]]]));
+    json!({
+        "id": s.id,
+        "status": s.status,
+        "progress_pct": s.progress_pct,
+        "progress_message": s.progress_message,
+        "error": (!s.error.is_empty()).then_some(s.error),
+        "error_detail": s.error_detail.map(|d| json!({
+            "code": d.code,
+            "stage": d.stage,
+            "message": d.message,
+            "details": serde_json::from_str::<Value>(&d.details_json).unwrap_or_default(),
+        })),
+        "stage": s.stage,
+        "profile": serde_json::from_str::<Value>(&s.profile_json).ok(),
+        "interannual": serde_json::from_str::<Value>(&s.interannual_json).ok(),
+        "cumulative": serde_json::from_str::<Value>(&s.cumulative_json).ok(),
+        "logs": logs,
+        "outputs": s.outputs,
+        "overlay_bounds": bounds,
+        "stats": (!s.stats.is_empty()).then_some(s.stats),
+    })
+}
+
+impl GrpcClient {
+    pub fn connect(port: u16) -> Result<Self, String> {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|e| e.to_string())?;
+        let endpoint = Endpoint::from_shared(format!("http://127.0.0.1:{port}")).map_err(|e| e.to_string())?.connect_timeout(Duration::from_secs(2));
+        let channel = runtime.block_on(endpoint.connect()).map_err(|e| e.to_string())?;
+        Ok(GrpcClient { runtime, channel })
+    }
+
+    fn client(&self) -> EngineClient<Channel> {
+        EngineClient::new(self.channel.clone())
+    }
+
+    pub fn submit_run(&self, cfg: &Value, key: &str) -> Result<String, EngineError> {
+        let config_json = cfg.to_string();
+        with_retry(|| {
+            let request = pb::RunRequest { config_json: config_json.clone(), idempotency_key: key.to_string() };
+            self.runtime.block_on(self.client().submit_run(request))
+        })
+        .map(|r| r.into_inner().id)
+        .map_err(status)
+    }
+
+    pub fn pause(&self, id: &str) -> Result<(), EngineError> {
+        with_retry(|| self.runtime.block_on(self.client().pause_job(pb::JobRef { id: id.to_string() }))).map(|_| ()).map_err(status)
+    }
+
+    /// Follows the job's stream until it finishes, or just its first snapshot with `first_only`.
+    /// `None` when the engine does not know the job.
+    /// Opening the stream is retried; a stream that breaks later fails the call.
+    pub fn watch(&self, id: &str, first_only: bool, mut on_update: impl FnMut(&Value)) -> Result<Option<Value>, EngineError> {
+        let mut stream = match with_retry(|| self.runtime.block_on(self.client().watch_job(pb::JobRef { id: id.to_string() }))) {
+            Ok(r) => r.into_inner(),
+            Err(s) if s.code() == tonic::Code::NotFound => return Ok(None),
+            Err(s) => return Err(status(s)),
+        };
+        self.runtime.block_on(async {
+            let mut logs: Vec<String> = vec![];
+            loop {
+                let snapshot = match stream.message().await {
+                    Ok(Some(s)) => s,
+                    Ok(None) => return Err("engine closed the job stream".to_string().into()),
+                    Err(s) if s.code() == tonic::Code::NotFound => return Ok(None),
+                    Err(s) => return Err(status(s)),
+                };
+                logs.extend(snapshot.new_logs.iter().cloned());
+                let excess = logs.len().saturating_sub(LOG_TAIL);
+                logs.drain(..excess);
+                let finished = matches!(snapshot.status.as_str(), "done" | "error" | "paused");
+                let value = to_value(snapshot, &logs);
+                on_update(&value);
+                if finished || first_only {
+                    return Ok(Some(value));
+                }
+            }
+        })
+    }
+}
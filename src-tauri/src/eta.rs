@@ -0,0 +1,187 @@
+//! Run time estimates: the engine's progress is turned into a smoothed throughput per stage
+//! (timestep loop, then outputs) and blended with the durations of earlier runs of similar size
+//! from `run_history.json`, which dominate until enough of the current run has been measured.
+
+use crate::{
+    jobs::{JobQueue, JobStatus},
+    profile::StageTiming,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{fs, path::PathBuf, time::Instant};
+use tauri::{AppHandle, Manager};
+
+pub const TRAY_ID: &str = "main";
+const APP_NAME: &str = "Wind Shadow Studio";
+/// The engine reports the timestep loop up to this percentage, then writes the outputs.
+const COMPUTE_END_PCT: f64 = 85.0;
+/// Weight of the latest sample in the throughput average.
+const SMOOTHING: f64 = 0.2;
+/// Progress after which the measured throughput alone drives the estimate.
+const CALIBRATED_PCT: f64 = 20.0;
+/// Engine limit on the calculation area side, see `MAX_AREA_M` there.
+const MAX_AREA_M: f64 = 12_000.0;
+const HISTORY_LEN: usize = 200;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct HistoryEntry {
+    finished_at: chrono::DateTime<chrono::Local>,
+    /// Grid cells times shadow sources, see `work`.
+    work: f64,
+    compute_s: f64,
+    outputs_s: f64,
+    /// Engine stage timings of profiled runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    profile: Option<Vec<StageTiming>>,
+}
+
+fn history_path(app: &AppHandle) -> PathBuf {
+    crate::portable::data_dir(app).join("run_history.json")
+}
+
+fn load_history(app: &AppHandle) -> Vec<HistoryEntry> {
+    fs::read_to_string(history_path(app)).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+/// Grid cells of the buffered extent of the project turbines, each side clamped like the engine
+/// does; also the base of the disk estimates in `disk`.
+pub fn grid_cells(cfg: &Value) -> f64 {
+    let xy: Vec<(f64, f64)> = cfg["turbines"].as_array().into_iter().flatten().filter_map(|t| Some((t["x"].as_f64()?, t["y"].as_f64()?))).collect();
+    if xy.is_empty() {
+        return 0.0;
+    }
+    let (buffer, cell) = (cfg["buffer_m"].as_f64().unwrap_or(2000.0), cfg["cellsize_m"].as_f64().unwrap_or(10.0).max(1.0));
+    let span = |v: Vec<f64>| (v.iter().cloned().fold(f64::MIN, f64::max) - v.iter().cloned().fold(f64::MAX, f64::min) + 2.0 * buffer).min(MAX_AREA_M);
+    (span(xy.iter().map(|p| p.0).collect()) / cell).ceil() * (span(xy.iter().map(|p| p.1).collect()) / cell).ceil()
+}
+
+/// Run size as the engine sees it: grid cells times turbines, project and external.
+pub fn work(cfg: &Value) -> f64 {
+    let own = cfg["turbines"].as_array().into_iter().flatten();
+    let external = cfg["external_groups"].as_array().into_iter().flatten().flat_map(|g| g["turbines"].as_array().into_iter().flatten());
+    grid_cells(cfg) * (own.count() + external.count()) as f64
+}
+
+fn median(mut v: Vec<f64>) -> Option<f64> {
+    v.sort_by(f64::total_cmp);
+    let n = v.len();
+    (n > 0).then(|| if n % 2 == 1 { v[n / 2] } else { (v[n / 2 - 1] + v[n / 2]) / 2.0 })
+}
+
+/// Stage durations expected from earlier runs within a factor of two in size (any run if none),
+/// scaled linearly to this run's size.
+fn prior(history: &[HistoryEntry], work: f64) -> Option<(f64, f64)> {
+    if work <= 0.0 {
+        return None;
+    }
+    let similar: Vec<&HistoryEntry> = history.iter().filter(|h| h.work > 0.0 && (0.5..=2.0).contains(&(work / h.work))).collect();
+    let pool: Vec<&HistoryEntry> = if similar.is_empty() { history.iter().filter(|h| h.work > 0.0).collect() } else { similar };
+    Some((median(pool.iter().map(|h| h.compute_s * work / h.work).collect())?, median(pool.iter().map(|h| h.outputs_s * work / h.work).collect())?))
+}
+
+/// Tracks one engine attempt of a job.
+pub struct Estimator {
+    work: f64,
+    prior: Option<(f64, f64)>,
+    start: Instant,
+    last: Option<(Instant, f64)>,
+    /// Smoothed percent per second during the timestep loop.
+    rate: Option<f64>,
+    outputs_start: Option<Instant>,
+    /// False for attempts resumed from a checkpoint, whose timings would skew the history.
+    from_start: bool,
+}
+
+impl Estimator {
+    pub fn new(app: &AppHandle, cfg: &Value) -> Self {
+        let work = work(cfg);
+        Estimator { work, prior: prior(&load_history(app), work), start: Instant::now(), last: None, rate: None, outputs_start: None, from_start: true }
+    }
+
+    /// Feeds the engine's progress and returns the remaining seconds, if anything is known yet.
+    pub fn update(&mut self, pct: f64) -> Option<f64> {
+        let now = Instant::now();
+        match self.last {
+            // The first sample is only a baseline: a resumed run jumps straight to its checkpoint.
+            None => {
+                self.from_start = pct < 1.0;
+                self.last = Some((now, pct));
+            }
+            Some((t, p)) if pct > p && pct <= COMPUTE_END_PCT => {
+                let sample = (pct - p) / now.duration_since(t).as_secs_f64().max(1e-3);
+                self.rate = Some(self.rate.map_or(sample, |r| r + SMOOTHING * (sample - r)));
+                self.last = Some((now, pct));
+            }
+            _ => {}
+        }
+        if pct >= COMPUTE_END_PCT && self.outputs_start.is_none() {
+            self.outputs_start = Some(now);
+        }
+        let elapsed = now.duration_since(self.start).as_secs_f64();
+        let prior_outputs = self.prior.map(|p| p.1);
+        if let Some(outputs_start) = self.outputs_start {
+            return prior_outputs.map(|o| (o - now.duration_since(outputs_start).as_secs_f64()).max(0.0));
+        }
+        let measured = self.rate.filter(|r| *r > 0.0).map(|r| (COMPUTE_END_PCT - pct) / r);
+        let expected = self.prior.filter(|_| self.from_start).map(|p| (p.0 - elapsed).max(0.0));
+        let compute = match (measured, expected) {
+            (Some(m), Some(e)) => {
+                let w = (pct / CALIBRATED_PCT).min(1.0);
+                Some(w * m + (1.0 - w) * e)
+            }
+            (m, e) => m.or(e),
+        }?;
+        Some(compute + prior_outputs.unwrap_or(0.0))
+    }
+
+    /// Stores the stage durations of a complete, uninterrupted run for later estimates, with the
+    /// engine's profile when it was asked for one.
+    pub fn record(self, app: &AppHandle, profile: Option<Vec<StageTiming>>) {
+        let (Some(outputs_start), true) = (self.outputs_start, self.from_start && self.work > 0.0) else { return };
+        let mut history = load_history(app);
+        history.push(HistoryEntry {
+            finished_at: chrono::Local::now(),
+            work: self.work,
+            compute_s: outputs_start.duration_since(self.start).as_secs_f64(),
+            outputs_s: outputs_start.elapsed().as_secs_f64(),
+            profile,
+        });
+        let excess = history.len().saturating_sub(HISTORY_LEN);
+        history.drain(..excess);
+        if let Ok(raw) = serde_json::to_string(&history) {
+            let _ = fs::write(history_path(app), raw);
+        }
+    }
+}
+
+/// The most recent profiled run in the history: when it finished, its size and its stages.
+pub fn latest_profile(app: &AppHandle) -> Option<(chrono::DateTime<chrono::Local>, f64, Vec<StageTiming>)> {
+    load_history(app).into_iter().rev().find_map(|h| Some((h.finished_at, h.work, h.profile?)))
+}
+
+fn format_eta(s: f64) -> String {
+    let m = (s / 60.0).round() as u64;
+    if m >= 60 {
+        format!("{}h{:02}", m / 60, m % 60)
+    } else if m > 0 {
+        format!("{m} min")
+    } else {
+        "< 1 min".to_string()
+    }
+}
+
+/// Tray tooltip with the running jobs' progress and the latest finishing time.
+pub fn show(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else { return };
+    let running: Vec<_> = app.state::<JobQueue>().snapshot().into_iter().filter(|j| j.status == JobStatus::Running).collect();
+    let eta = running.iter().filter_map(|j| j.eta_s).reduce(f64::max);
+    let text = match running.as_slice() {
+        [] => APP_NAME.to_string(),
+        [job] => {
+            let pct = job.engine.as_ref().and_then(|e| e["progress_pct"].as_f64()).unwrap_or(0.0);
+            format!("{APP_NAME}: {} {pct:.0}%{}", job.label, eta.map(|s| format!(", ETA {}", format_eta(s))).unwrap_or_default())
+        }
+        jobs => format!("{APP_NAME}: {} runs{}", jobs.len(), eta.map(|s| format!(", all done in {}", format_eta(s))).unwrap_or_default()),
+    };
+    let _ = tray.set_tooltip(Some(text));
+}
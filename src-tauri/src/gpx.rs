@@ -0,0 +1,211 @@
+//! GPX import for positions captured with handheld GPS on site surveys: waypoints (and route
+//! points) become turbines or receptors by their name, symbol and description, reprojected from
+//! WGS84 to the project CRS. Waypoints naming an existing object move it; others are added.
+
+use crate::{crs, viewer::ViewerMode, ProjectConfig, Receptor, Turbine};
+use quick_xml::{events::Event, Reader};
+use serde::Serialize;
+use std::{fs, path::Path};
+use tauri::State;
+
+/// Engine limit on project turbines, see `MAX_TURBINES` there.
+const MAX_TURBINES: usize = 20;
+/// Window height for new receptors, as for receptors entered by hand.
+const RECEPTOR_HEIGHT_M: f64 = 2.0;
+
+/// Name prefixes (`WTG03`, `R 12`, `Casa Rossi`) and words in descriptions and types.
+const TURBINE_WORDS: &[&str] = &["WTG", "WEA", "AG", "TURB", "TURBINE", "TURBINA", "AEROGENERATORE", "PALA"];
+const RECEPTOR_WORDS: &[&str] = &["REC", "RIC", "SR", "RECEPTOR", "RICETTORE", "CASA", "HOUSE", "ABITAZIONE", "EDIFICIO", "BUILDING", "CASCINA", "RESIDENCE"];
+/// Single letters count only when followed by a number (`T1`, `R7`).
+const TURBINE_LETTER: &str = "T";
+const RECEPTOR_LETTERS: &[&str] = &["R", "H"];
+/// Garmin symbols.
+const TURBINE_SYMBOLS: &[&str] = &["tall tower", "short tower"];
+const RECEPTOR_SYMBOLS: &[&str] = &["residence", "building", "school", "church", "lodging"];
+
+#[derive(Default)]
+struct Waypoint {
+    name: String,
+    /// `desc`, `cmt` and `type` together.
+    notes: String,
+    sym: String,
+    lon: f64,
+    lat: f64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Turbine,
+    Receptor,
+}
+
+#[derive(Serialize)]
+pub struct GpxImport {
+    cfg: ProjectConfig,
+    turbines: Vec<String>,
+    receptors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+fn waypoint(tag: &quick_xml::events::BytesStart) -> Result<Waypoint, String> {
+    let coord = |key: &str| -> Result<f64, String> {
+        let attr = tag.try_get_attribute(key).map_err(|e| e.to_string())?.ok_or(format!("waypoint without {key}"))?;
+        attr.value.trim().parse().map_err(|_| format!("invalid {key}: {}", attr.value))
+    };
+    Ok(Waypoint { lon: coord("lon")?, lat: coord("lat")?, ..Default::default() })
+}
+
+fn parse(text: &str) -> Result<Vec<Waypoint>, String> {
+    let mut reader = Reader::from_str(text);
+    let (mut points, mut current, mut field) = (vec![], None::<Waypoint>, None::<String>);
+    loop {
+        let event = reader.read_event().map_err(|e| format!("at byte {}: {e}", reader.buffer_position()))?;
+        let mut append = |s: &str| {
+            if let (Some(w), Some(f)) = (current.as_mut(), field.as_deref()) {
+                let target = match f {
+                    "name" => &mut w.name,
+                    "sym" => &mut w.sym,
+                    _ => &mut w.notes,
+                };
+                target.push_str(s);
+            }
+        };
+        match event {
+            Event::Start(e) => match e.local_name().as_ref() {
+                "wpt" | "rtept" => current = Some(waypoint(&e)?),
+                tag @ ("name" | "desc" | "cmt" | "type" | "sym") => {
+                    field = Some(tag.to_string());
+                    if let Some(w) = current.as_mut() {
+                        w.notes.push(' ');
+                    }
+                }
+                _ => {}
+            },
+            Event::Empty(e) if matches!(e.local_name().as_ref(), "wpt" | "rtept") => points.push(waypoint(&e)?),
+            Event::Text(t) => append(&t.xml10_content()),
+            Event::CData(t) => append(&t.xml10_content()),
+            Event::GeneralRef(r) => append(&quick_xml::escape::unescape(&format!("&{};", &*r)).map_err(|e| e.to_string())?),
+            Event::End(e) => match e.local_name().as_ref() {
+                "wpt" | "rtept" => points.extend(current.take()),
+                _ => field = None,
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    for w in &mut points {
+        w.name = w.name.trim().to_string();
+    }
+    Ok(points)
+}
+
+/// Leading letters and the number right after them: `WTG-03` is (`WTG`, 3), `Casa Rossi` (`CASA`, none).
+fn prefix(name: &str) -> (String, Option<u32>) {
+    let upper = name.trim().to_uppercase();
+    let letters: String = upper.chars().take_while(|c| c.is_alphabetic()).collect();
+    let rest = upper[letters.len()..].trim_start_matches([' ', '-', '_', '.', '#']);
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    (letters, digits.parse().ok())
+}
+
+fn classify(w: &Waypoint) -> Option<Kind> {
+    let sym = w.sym.trim().to_lowercase();
+    if TURBINE_SYMBOLS.contains(&sym.as_str()) {
+        return Some(Kind::Turbine);
+    }
+    if RECEPTOR_SYMBOLS.contains(&sym.as_str()) {
+        return Some(Kind::Receptor);
+    }
+    let (letters, number) = prefix(&w.name);
+    if TURBINE_WORDS.contains(&letters.as_str()) || (letters == TURBINE_LETTER && number.is_some()) {
+        return Some(Kind::Turbine);
+    }
+    if RECEPTOR_WORDS.contains(&letters.as_str()) || (RECEPTOR_LETTERS.contains(&letters.as_str()) && number.is_some()) {
+        return Some(Kind::Receptor);
+    }
+    let notes = w.notes.to_uppercase();
+    let words: Vec<&str> = notes.split(|c: char| !c.is_alphanumeric()).collect();
+    if TURBINE_WORDS.iter().any(|t| words.contains(t)) {
+        Some(Kind::Turbine)
+    } else if RECEPTOR_WORDS.iter().any(|r| words.contains(r)) {
+        Some(Kind::Receptor)
+    } else {
+        None
+    }
+}
+
+/// `T01`, `t-1` and `T1` share a key; names without a number keep their letters and digits.
+fn key(name: &str) -> String {
+    match prefix(name) {
+        (letters, Some(n)) => format!("{letters}{n}"),
+        _ => name.to_uppercase().chars().filter(|c| c.is_alphanumeric()).collect(),
+    }
+}
+
+/// Index of the object `name` refers to: the same key, or else the only object with the same
+/// number after any prefix (`WTG03` for `T3`).
+fn find(ids: &[&String], name: &str) -> Option<usize> {
+    let k = key(name);
+    ids.iter().position(|id| key(id) == k).or_else(|| {
+        let n = prefix(name).1?;
+        let same: Vec<usize> = (0..ids.len()).filter(|&i| prefix(ids[i]).1 == Some(n)).collect();
+        (same.len() == 1).then(|| same[0])
+    })
+}
+
+pub fn import(path: &Path, mut cfg: ProjectConfig) -> Result<GpxImport, String> {
+    let text = String::from_utf8_lossy(&fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?).to_string();
+    let points = parse(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+    if points.is_empty() {
+        return Err(format!("{}: no waypoints", path.display()));
+    }
+    let project = crs::parse_epsg(&cfg.epsg)?;
+    let template = cfg.turbines.first().cloned();
+    let (mut turbines, mut receptors, mut warnings) = (vec![], vec![], vec![]);
+    for (i, w) in points.iter().enumerate() {
+        let (x, y) = project.project(w.lon, w.lat);
+        let label = if w.name.is_empty() { format!("waypoint {}", i + 1) } else { w.name.clone() };
+        match classify(w) {
+            Some(Kind::Turbine) => {
+                if let Some(at) = find(&cfg.turbines.iter().map(|t| &t.id).collect::<Vec<_>>(), &w.name) {
+                    let t = &mut cfg.turbines[at];
+                    (t.x, t.y) = (x, y);
+                    turbines.push(t.id.clone());
+                } else if cfg.turbines.len() >= MAX_TURBINES {
+                    warnings.push(format!("{label}: already {MAX_TURBINES} turbines, skipped"));
+                } else {
+                    let Some(tpl) = template.as_ref() else {
+                        warnings.push(format!("{label}: no turbine in the project to copy hub height and rotor diameter from, skipped"));
+                        continue;
+                    };
+                    let id = if w.name.is_empty() { format!("T{}", cfg.turbines.len() + 1) } else { w.name.clone() };
+                    cfg.turbines.push(Turbine { id: id.clone(), x, y, hub_height_m: tpl.hub_height_m, rotor_diameter_m: tpl.rotor_diameter_m });
+                    warnings.push(format!("{id}: new turbine, hub height and rotor diameter copied from {}", tpl.id));
+                    turbines.push(id);
+                }
+            }
+            Some(Kind::Receptor) => {
+                if let Some(at) = find(&cfg.receptors.iter().map(|r| &r.id).collect::<Vec<_>>(), &w.name) {
+                    let r = &mut cfg.receptors[at];
+                    (r.x, r.y) = (x, y);
+                    receptors.push(r.id.clone());
+                } else {
+                    let id = if w.name.is_empty() { format!("R{}", cfg.receptors.len() + 1) } else { w.name.clone() };
+                    cfg.receptors.push(Receptor { id: id.clone(), x, y, height_m: RECEPTOR_HEIGHT_M });
+                    receptors.push(id);
+                }
+            }
+            None => warnings.push(format!("{label}: neither a turbine nor a receptor by name, symbol or description, skipped")),
+        }
+    }
+    Ok(GpxImport { cfg, turbines, receptors, warnings })
+}
+
+#[tauri::command]
+pub fn import_gpx(cfg: ProjectConfig, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Option<GpxImport>, String> {
+    viewer.check(window.label())?;
+    let Some(path) = rfd::FileDialog::new().add_filter("GPX", &["gpx"]).pick_file() else {
+        return Ok(None);
+    };
+    import(&path, cfg).map(Some)
+}
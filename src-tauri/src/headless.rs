@@ -0,0 +1,207 @@
+//! Headless render mode for build servers: the window stays hidden and projects are run through
+//! the job queue, their engine outputs (map, report, grids) copied to a deliverables folder
+//! together with the DXF layout.
+//!
+//! `--headless --project <dir> [--out <dir>]` renders once and exits with 0 on success, 1 on
+//! failure; `--headless --serve <port>` keeps answering `POST /render` on 127.0.0.1 with a JSON
+//! body `{ "project": "<dir>", "out": "<dir>" }`. The default output folder is
+//! `<project>/deliverables`; a `render.json` summary is written there in both cases.
+//!
+//! Release builds on Windows are GUI-subsystem executables without a console of their own, so
+//! headless mode attaches to the console of the process that launched it for its progress and
+//! result lines. `cmd` and PowerShell do not wait for such programs: use `start /wait wss.exe
+//! --headless ...` or `Start-Process -Wait -PassThru` to get the exit code.
+
+use crate::{
+    audit, dxf,
+    jobs::{self, JobQueue, JobStatus, Priority},
+    project, settings,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+use tauri::{AppHandle, Manager};
+
+const DELIVERABLES_DIR: &str = "deliverables";
+const SUMMARY_FILE: &str = "render.json";
+/// Request bodies are a couple of paths.
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+pub enum Mode {
+    Render { project: PathBuf, out: Option<PathBuf> },
+    Serve { port: u16 },
+}
+
+#[derive(Serialize)]
+pub struct Rendered {
+    project: String,
+    out: String,
+    job: u64,
+    /// Copied engine outputs by name (`map_png`, `pdf`, ...), then `dxf`.
+    files: Vec<(String, String)>,
+    dxf: dxf::DxfExport,
+}
+
+#[derive(Deserialize)]
+struct RenderRequest {
+    project: PathBuf,
+    out: Option<PathBuf>,
+}
+
+fn arg_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).cloned()
+}
+
+/// `None` without `--headless`; a headless launch missing its project or port is an error.
+pub fn from_launch() -> Option<Result<Mode, String>> {
+    let args: Vec<String> = std::env::args().collect();
+    if !args.iter().any(|a| a == "--headless") {
+        return None;
+    }
+    if let Some(port) = arg_value(&args, "--serve") {
+        return Some(port.parse().map(|port| Mode::Serve { port }).map_err(|_| format!("invalid --serve port: {port}")));
+    }
+    Some(match arg_value(&args, "--project") {
+        Some(project) => Ok(Mode::Render { project: project.into(), out: arg_value(&args, "--out").map(PathBuf::from) }),
+        None => Err("--headless needs --project <dir> or --serve <port>".to_string()),
+    })
+}
+
+/// Runs the project and collects its deliverables; progress goes to stdout.
+pub fn render(app: &AppHandle, folder: &Path, out: Option<&Path>) -> Result<Rendered, String> {
+    let cfg = project::read(folder)?;
+    let out = out.map_or_else(|| folder.join(DELIVERABLES_DIR), Path::to_path_buf);
+    fs::create_dir_all(&out).map_err(|e| format!("{}: {e}", out.display()))?;
+    let queue = app.state::<JobQueue>();
+    let id = queue.submit(format!("Headless render {}", folder.display()), None, Priority::Export, None, &cfg.project_path, &cfg)?;
+    jobs::preempt_when_due(app, None);
+    let mut last = None;
+    let job = loop {
+        let job = queue.get(id).ok_or(format!("job {id} disappeared"))?;
+        let pct = job.engine.as_ref().and_then(|e| e["progress_pct"].as_f64()).unwrap_or(0.0).round();
+        if last != Some((job.status, pct)) {
+            println!("[{}] job {id} {} {pct}%", folder.display(), json!(job.status).as_str().unwrap_or_default());
+            last = Some((job.status, pct));
+        }
+        if job.status.is_finished() {
+            break job;
+        }
+        thread::sleep(Duration::from_secs(1));
+    };
+    if job.status != JobStatus::Done {
+        return Err(job.error.unwrap_or(format!("job {id} {}", json!(job.status).as_str().unwrap_or_default())));
+    }
+    let mut files = vec![];
+    let outputs = job.engine.as_ref().map(|e| e["outputs"].clone()).unwrap_or(Value::Null);
+    for (name, src) in outputs.as_object().into_iter().flatten() {
+        let Some(src) = src.as_str().map(PathBuf::from) else { continue };
+        let Some(file) = src.file_name() else { continue };
+        let dst = out.join(file);
+        fs::copy(&src, &dst).map_err(|e| format!("{}: {e}", src.display()))?;
+        files.push((name.clone(), dst.display().to_string()));
+    }
+    let levels = dxf::contour_levels(None, settings::load(app).compliance_limit_h);
+    let dxf = dxf::write_layout(&cfg, &levels, &out.join("layout.dxf"))?;
+    let rendered = Rendered { project: cfg.project_path.clone(), out: out.display().to_string(), job: id, files, dxf };
+    let summary = serde_json::to_string_pretty(&rendered).map_err(|e| e.to_string())?;
+    fs::write(out.join(SUMMARY_FILE), summary).map_err(|e| format!("{}: {e}", out.display()))?;
+    audit::note(&cfg.project_path, "headless_render", json!({ "job": id, "out": rendered.out }));
+    Ok(rendered)
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &Value) {
+    let body = body.to_string();
+    let _ = write!(stream, "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+}
+
+fn handle(app: &AppHandle, mut stream: TcpStream) -> Result<(), String> {
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| e.to_string())?;
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).map_err(|e| e.to_string())? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let target = line.split_whitespace().take(2).collect::<Vec<_>>();
+    match target.as_slice() {
+        ["GET", "/health"] => respond(&mut stream, "200 OK", &json!({ "ok": true })),
+        ["POST", "/render"] if length <= MAX_BODY_BYTES => {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+            match serde_json::from_slice::<RenderRequest>(&body) {
+                Ok(req) => match render(app, &req.project, req.out.as_deref()) {
+                    Ok(r) => respond(&mut stream, "200 OK", &json!(r)),
+                    Err(e) => respond(&mut stream, "500 Internal Server Error", &json!({ "error": e })),
+                },
+                Err(e) => respond(&mut stream, "400 Bad Request", &json!({ "error": e.to_string() })),
+            }
+        }
+        ["POST", "/render"] => respond(&mut stream, "413 Payload Too Large", &json!({ "error": "request body too large" })),
+        _ => respond(&mut stream, "404 Not Found", &json!({ "error": "use POST /render or GET /health" })),
+    }
+    Ok(())
+}
+
+/// Renders one request at a time so runs do not compete for the workers.
+fn serve(app: &AppHandle, port: u16) -> Result<(), String> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| format!("127.0.0.1:{port}: {e}"))?;
+    println!("headless render server on http://127.0.0.1:{port}");
+    for stream in listener.incoming() {
+        match stream {
+            Ok(s) => {
+                if let Err(e) = handle(app, s) {
+                    eprintln!("render request: {e}");
+                }
+            }
+            Err(e) => eprintln!("render connection: {e}"),
+        }
+    }
+    Ok(())
+}
+
+/// Output of a Windows GUI build goes to the launching console, if there is one.
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // SAFETY: no arguments beyond a constant; failure (no parent console) leaves stdout detached.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Hides the window and runs `mode` in the background; the app exits when it is done.
+pub fn start(app: AppHandle, mode: Result<Mode, String>) {
+    #[cfg(windows)]
+    attach_console();
+    for window in app.webview_windows().values() {
+        let _ = window.hide();
+    }
+    thread::spawn(move || {
+        let result = mode.and_then(|m| match m {
+            Mode::Render { project, out } => render(&app, &project, out.as_deref()).map(|r| println!("rendered to {}", r.out)),
+            Mode::Serve { port } => serve(&app, port),
+        });
+        if let Err(e) = &result {
+            eprintln!("headless render failed: {e}");
+        }
+        app.exit(i32::from(result.is_err()));
+    });
+}
@@ -0,0 +1,581 @@
+//! Shell-side job queue: runs are queued here and relayed to the engine by a pool of workers, with
+//! progress published as Tauri events (`job://progress`, `job://done`, `job://failed`).
+//! Jobs may carry a start time; the workers live in the shell, so a minimised window does not
+//! hold them back. Running jobs can be paused: the engine checkpoints them in the runtime dir and
+//! paused jobs are kept in `paused.json` there, so they can be resumed after a restart.
+//! Queued jobs start by priority class, then in order of submission; an interactive run finding
+//! every worker busy with lower classes preempts one of them, which is paused and queued again.
+
+use crate::{audit, disk, engine, eta, notify, profile, settings, viewer::ViewerMode, workspace, ProjectConfig, Receptor};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    cmp::Reverse,
+    fs,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Condvar, Mutex, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+use tauri::{AppHandle, Manager, State};
+
+/// Upper bound for one wait on a scheduled job, so clock changes and sleep/resume are noticed.
+const SCHEDULE_RECHECK: Duration = Duration::from_secs(60);
+/// Resubmissions of one job after the engine forgot it.
+const ENGINE_RESTARTS: u32 = 2;
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Error,
+    Cancelled,
+    Paused,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Error | JobStatus::Cancelled)
+    }
+}
+
+/// Priority classes, lowest first.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Cache priming on project open (see `prime_cache`).
+    Background,
+    /// Sweeps, scenario batches, overnight cumulative runs.
+    #[default]
+    Batch,
+    /// Runs producing deliverables.
+    Export,
+    /// Quick checks the user is waiting for.
+    Interactive,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub label: String,
+    /// Jobs submitted together (a sensitivity sweep, a scenario batch) share a batch id.
+    pub batch: Option<String>,
+    pub status: JobStatus,
+    pub priority: Priority,
+    /// Paused to free a worker for a higher class, queued again once it has stopped.
+    pub preempted: bool,
+    /// Queued jobs with a start time are held back until then.
+    pub start_at: Option<DateTime<Local>>,
+    pub started_at: Option<DateTime<Local>>,
+    pub finished_at: Option<DateTime<Local>>,
+    /// Output folder of the run: the project itself, or a variant's subfolder in a batch.
+    pub project_path: String,
+    /// Project the job was submitted from; its engine runs the job and its window gets the events.
+    pub project: String,
+    pub error: Option<String>,
+    /// The same failure with its code, stage and details; also sent as `engine://error`.
+    pub error_detail: Option<engine::EngineError>,
+    /// Last snapshot reported by the engine: progress, logs, outputs, overlay bounds, stats.
+    pub engine: Option<Value>,
+    /// Estimated seconds left, from `eta::Estimator`.
+    pub eta_s: Option<f64>,
+    /// Engine checkpoint key, stable across pause and resume.
+    #[serde(skip)]
+    checkpoint: String,
+    /// Engine job id of the current attempt, needed to pause it.
+    #[serde(skip)]
+    engine_id: Option<String>,
+    #[serde(skip)]
+    cfg: Value,
+}
+
+/// What `paused.json` keeps of a paused job.
+#[derive(Serialize, Deserialize)]
+struct PausedJob {
+    label: String,
+    batch: Option<String>,
+    checkpoint: String,
+    engine: Option<Value>,
+    cfg: Value,
+    #[serde(default)]
+    priority: Priority,
+    /// Missing in files from older versions, where it was the run's own folder.
+    #[serde(default)]
+    project: Option<String>,
+}
+
+impl Job {
+    /// Engine cache priming rather than a calculation: no results, notifications or ETA samples.
+    pub fn is_priming(&self) -> bool {
+        self.cfg["mode"] == "prime"
+    }
+
+    /// Receptors of the submitted project, for result tables.
+    pub fn receptors(&self) -> Vec<Receptor> {
+        serde_json::from_value(self.cfg["receptors"].clone()).unwrap_or_default()
+    }
+}
+
+#[derive(Default)]
+pub struct JobQueue {
+    jobs: Mutex<Vec<Job>>,
+    wake: Condvar,
+    /// Size of the worker pool started by `start_workers`; preemption compares against it rather
+    /// than the setting, which only takes effect at the next launch.
+    workers: AtomicUsize,
+}
+
+impl JobQueue {
+    /// `project` owns the job (see `Job::project`); `cfg` may be a variant writing elsewhere.
+    pub fn submit(&self, label: String, batch: Option<String>, priority: Priority, start_at: Option<DateTime<Local>>, project: &str, cfg: &ProjectConfig) -> Result<u64, String> {
+        self.submit_value(label, batch, priority, start_at, project, serde_json::to_value(cfg).map_err(|e| e.to_string())?)
+    }
+
+    fn submit_value(&self, label: String, batch: Option<String>, priority: Priority, start_at: Option<DateTime<Local>>, project: &str, cfg: Value) -> Result<u64, String> {
+        let mut jobs = self.jobs.lock().map_err(|e| e.to_string())?;
+        let id = jobs.last().map_or(1, |j| j.id + 1);
+        let project_path = cfg["project_path"].as_str().unwrap_or_default().to_string();
+        let checkpoint = format!("{}-{id}", Local::now().format("%Y%m%d%H%M%S"));
+        jobs.push(Job { id, label, batch, status: JobStatus::Queued, priority, preempted: false, start_at, started_at: None, finished_at: None, project_path, project: project.to_string(), error: None, error_detail: None, engine: None, eta_s: None, checkpoint, engine_id: None, cfg });
+        self.wake.notify_all();
+        Ok(id)
+    }
+
+    fn restore(&self, saved: PausedJob) {
+        if let Ok(mut jobs) = self.jobs.lock() {
+            let id = jobs.last().map_or(1, |j| j.id + 1);
+            let project_path = saved.cfg["project_path"].as_str().unwrap_or_default().to_string();
+            let project = saved.project.unwrap_or_else(|| project_path.clone());
+            jobs.push(Job { id, label: saved.label, batch: saved.batch, status: JobStatus::Paused, priority: saved.priority, preempted: false, start_at: None, started_at: None, finished_at: None, project_path, project, error: None, error_detail: None, engine: saved.engine, eta_s: None, checkpoint: saved.checkpoint, engine_id: None, cfg: saved.cfg });
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.jobs.lock().ok()?.iter().find(|j| j.id == id).cloned()
+    }
+
+    pub fn snapshot(&self) -> Vec<Job> {
+        self.jobs.lock().map(|j| j.clone()).unwrap_or_default()
+    }
+
+    /// Blocks until all `ids` are finished and returns them in the same order.
+    pub fn wait_all(&self, ids: &[u64]) -> Vec<Job> {
+        loop {
+            let jobs: Vec<Job> = ids.iter().filter_map(|id| self.get(*id)).collect();
+            if jobs.iter().all(|j| j.status.is_finished()) {
+                return jobs;
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut Job)) -> Option<Job> {
+        let mut jobs = self.jobs.lock().ok()?;
+        let job = jobs.iter_mut().find(|j| j.id == id)?;
+        f(job);
+        Some(job.clone())
+    }
+
+    /// Blocks until a queued job is due and marks the first of the highest class running. A job
+    /// that panicked while holding the lock does not stop the workers: the queue is used as left.
+    fn next(&self) -> Job {
+        let mut jobs = self.jobs.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            let now = Local::now();
+            let due = jobs.iter_mut().filter(|j| j.status == JobStatus::Queued && j.start_at.is_none_or(|t| t <= now));
+            if let Some(job) = due.max_by_key(|j| (j.priority, Reverse(j.id))) {
+                job.status = JobStatus::Running;
+                job.started_at = Some(now);
+                return job.clone();
+            }
+            let due = jobs.iter().filter(|j| j.status == JobStatus::Queued).filter_map(|j| j.start_at).min();
+            jobs = match due {
+                Some(t) => self.wake.wait_timeout(jobs, (t - now).to_std().unwrap_or_default().min(SCHEDULE_RECHECK)).unwrap_or_else(PoisonError::into_inner).0,
+                None => self.wake.wait(jobs).unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+
+    /// With all `workers` busy and more due jobs waiting above the lowest running class than the
+    /// slots already being freed, marks the most recently started job of that class preempted and
+    /// returns it. Priming jobs have no checkpoint and are left to finish.
+    fn preemption_victim(&self, workers: usize) -> Option<Job> {
+        let mut jobs = self.jobs.lock().ok()?;
+        let now = Local::now();
+        let due: Vec<Priority> = jobs.iter().filter(|j| j.status == JobStatus::Queued && j.start_at.is_none_or(|t| t <= now)).map(|j| j.priority).collect();
+        let waiting = due.iter().copied().max()?;
+        let running = jobs.iter().filter(|j| j.status == JobStatus::Running).count();
+        let freeing = jobs.iter().filter(|j| j.status == JobStatus::Running && j.preempted).count();
+        if running < workers {
+            return None;
+        }
+        let victim = jobs.iter_mut().filter(|j| j.status == JobStatus::Running && !j.preempted && !j.is_priming() && j.engine_id.is_some() && j.priority < waiting).min_by_key(|j| (j.priority, Reverse(j.started_at)))?;
+        // Only jobs that would displace the victim's class count; a queued sweep of its own class does not.
+        if due.iter().filter(|p| **p > victim.priority).count() <= freeing {
+            return None;
+        }
+        victim.preempted = true;
+        Some(victim.clone())
+    }
+
+    /// All jobs of `batch`, once every one of them has finished.
+    pub fn finished_batch(&self, batch: &str) -> Option<Vec<Job>> {
+        let jobs: Vec<Job> = self.jobs.lock().ok()?.iter().filter(|j| j.batch.as_deref() == Some(batch)).cloned().collect();
+        jobs.iter().all(|j| j.status.is_finished()).then_some(jobs)
+    }
+}
+
+/// Accepts `HH:MM` (next occurrence, e.g. tonight at 22:00), `YYYY-MM-DDTHH:MM` local time or RFC 3339.
+pub fn parse_start_at(text: &str) -> Result<DateTime<Local>, String> {
+    let text = text.trim();
+    let now = Local::now();
+    if let Ok(t) = NaiveTime::parse_from_str(text, "%H:%M") {
+        let today = now.date_naive().and_time(t).and_local_timezone(Local).earliest();
+        let at = today.filter(|at| *at > now).or_else(|| (now.date_naive() + chrono::Days::new(1)).and_time(t).and_local_timezone(Local).earliest());
+        return at.ok_or_else(|| format!("invalid local time: {text}"));
+    }
+    if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+        return Ok(at.with_timezone(&Local));
+    }
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M")
+        .ok()
+        .and_then(|at| at.and_local_timezone(Local).earliest())
+        .ok_or_else(|| format!("unrecognised start time: {text} (use HH:MM or YYYY-MM-DDTHH:MM)"))
+}
+
+pub fn parse_optional_start_at(text: Option<String>) -> Result<Option<DateTime<Local>>, String> {
+    text.filter(|t| !t.trim().is_empty()).map(|t| parse_start_at(&t)).transpose()
+}
+
+fn run(app: &AppHandle, job: &Job) -> Result<Value, engine::EngineError> {
+    let queue = app.state::<JobQueue>();
+    let mut cfg = job.cfg.clone();
+    cfg["checkpoint"] = job.checkpoint.clone().into();
+    cfg["profile"] = settings::load(app).profile_runs.into();
+    // One key per attempt: resuming a paused job must start a new engine job, retries must not.
+    let key = format!("{}-{}", job.checkpoint, Local::now().timestamp_millis());
+    let mut estimator = eta::Estimator::new(app, &cfg);
+    let mut restarts = 0;
+    let state = loop {
+        let client = engine::client(app, &job.project)?;
+        let engine_id = client.submit_run(&cfg, &key)?;
+        queue.update(job.id, |j| j.engine_id = Some(engine_id.clone()));
+        let state = client.wait(&engine_id, |snapshot| {
+            let eta_s = estimator.update(snapshot["progress_pct"].as_f64().unwrap_or(0.0));
+            if let Some(j) = queue.update(job.id, |j| {
+                j.engine = Some(snapshot.clone());
+                j.eta_s = eta_s;
+            }) {
+                workspace::emit(app, &j.project, "job://progress", &j);
+            }
+            eta::show(app);
+        });
+        // An engine stopped under the job (`restart_engine`, its window closed) fails the wait;
+        // the job goes on from its checkpoint if another engine serves the project by then.
+        let state = match state {
+            Err(_) if restarts < ENGINE_RESTARTS && engine::replaced(app, &job.project, &client) => None,
+            state => state?,
+        };
+        // A restarted engine forgot the job; submitting again continues from its last checkpoint.
+        match state {
+            Some(state) => break state,
+            None if restarts < ENGINE_RESTARTS => restarts += 1,
+            None => return Err("the engine lost the job after restarting".to_string().into()),
+        }
+    };
+    match state["status"].as_str() {
+        Some("done") => {
+            if !job.is_priming() {
+                estimator.record(app, profile::from_snapshot(&state));
+            }
+            Ok(state)
+        }
+        Some("paused") => Ok(state),
+        _ => Err(engine::EngineError::from_snapshot(&state)),
+    }
+}
+
+fn paused_path(app: &AppHandle) -> std::path::PathBuf {
+    engine::runtime_dir(app).join("paused.json")
+}
+
+/// Rewrites `paused.json` from the queue; called whenever a job enters or leaves `Paused`.
+fn save_paused(app: &AppHandle) {
+    let paused: Vec<PausedJob> = app
+        .state::<JobQueue>()
+        .snapshot()
+        .into_iter()
+        .filter(|j| j.status == JobStatus::Paused)
+        .map(|j| PausedJob { label: j.label, batch: j.batch, checkpoint: j.checkpoint, engine: j.engine, cfg: j.cfg, priority: j.priority, project: Some(j.project) })
+        .collect();
+    let written = serde_json::to_string_pretty(&paused).map_err(|e| e.to_string()).and_then(|s| fs::write(paused_path(app), s).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        eprintln!("paused jobs: {e}");
+    }
+}
+
+/// Puts jobs paused in a previous session back in the queue, still paused.
+pub fn restore_paused(app: &AppHandle) {
+    let Ok(raw) = fs::read_to_string(paused_path(app)) else { return };
+    let queue = app.state::<JobQueue>();
+    for saved in serde_json::from_str::<Vec<PausedJob>>(&raw).unwrap_or_default() {
+        queue.restore(saved);
+    }
+}
+
+/// Worker threads draining the queue, one engine run each; the engine computes every run in its own thread.
+pub fn start_workers(app: AppHandle) {
+    let workers = settings::load(&app).engine_workers.max(1);
+    app.state::<JobQueue>().workers.store(workers, Ordering::Relaxed);
+    for _ in 0..workers {
+        let app = app.clone();
+        thread::spawn(move || work(app));
+    }
+}
+
+fn work(app: AppHandle) {
+    loop {
+        let queue = app.state::<JobQueue>();
+        let job = queue.next();
+        if !job.is_priming() {
+            notify::job_event(&app, "started", &job);
+        }
+        workspace::emit(&app, &job.project, "job://progress", &job);
+        let result = run(&app, &job);
+        let finished = queue.update(job.id, |j| {
+            j.engine_id = None;
+            j.eta_s = None;
+            let preempted = std::mem::take(&mut j.preempted);
+            match result {
+                // Back in the queue, ahead of later jobs of its class; it resumes from the checkpoint.
+                Ok(state) if state["status"] == "paused" && preempted => {
+                    j.status = JobStatus::Queued;
+                    j.engine = Some(state);
+                    return;
+                }
+                Ok(state) if state["status"] == "paused" => {
+                    j.status = JobStatus::Paused;
+                    j.engine = Some(state);
+                    return;
+                }
+                Ok(state) => {
+                    j.status = JobStatus::Done;
+                    j.engine = Some(state);
+                }
+                Err(e) => {
+                    j.status = JobStatus::Error;
+                    j.error = Some(e.message.clone());
+                    j.error_detail = Some(e);
+                }
+            }
+            j.finished_at = Some(Local::now());
+        });
+        eta::show(&app);
+        if let Some(j) = finished.as_ref().filter(|j| j.status == JobStatus::Queued) {
+            queue.wake.notify_all();
+            workspace::emit(&app, &j.project, "job://preempted", j);
+        } else if let Some(j) = finished.as_ref().filter(|j| j.status == JobStatus::Paused) {
+            save_paused(&app);
+            workspace::emit(&app, &j.project, "job://paused", j);
+        } else if let Some(j) = finished.as_ref().filter(|j| j.is_priming()) {
+            // A failed priming only costs the first run its head start.
+            if let Some(engine) = j.engine.as_ref().filter(|_| j.status == JobStatus::Done) {
+                workspace::emit(&app, &j.project, "cache://primed", json!({ "project_path": j.project_path, "engine_job": engine["id"], "outputs": engine["outputs"], "overlay_bounds": engine["overlay_bounds"] }));
+            }
+        } else if let Some(j) = finished {
+            if let Some(e) = &j.error_detail {
+                workspace::emit(&app, &j.project, "engine://error", json!({ "job": j.id, "error": e }));
+            }
+            notify::job_finished(&app, &j);
+            workspace::emit(&app, &j.project, if j.status == JobStatus::Done { "job://done" } else { "job://failed" }, &j);
+        }
+    }
+}
+
+/// Engine memory estimate: `full` fits, `tiled` fits with screening rasters read around the site
+/// only, `refuse` is what the run will fail with (`insufficient_memory`).
+#[derive(Serialize, Deserialize)]
+pub struct MemoryPlan {
+    mode: String,
+    required_mb: f64,
+    tiled_required_mb: f64,
+    available_mb: f64,
+    /// Grids, DEM, screening rasters, timesteps, receptor table and print map.
+    parts_mb: std::collections::BTreeMap<String, f64>,
+}
+
+/// Checks a run against the engine's free memory before it is submitted.
+#[tauri::command(async)]
+pub fn estimate_run_memory(cfg: ProjectConfig, app: AppHandle) -> Result<MemoryPlan, String> {
+    let plan = engine::client(&app, &cfg.project_path)?.memory_plan(&serde_json::to_value(&cfg).map_err(|e| e.to_string())?)?;
+    serde_json::from_value(plan).map_err(|e| e.to_string())
+}
+
+/// Queues a background job filling the engine cache for `cfg` (DEM clip, sun calendar, hillshade),
+/// unless the project has nothing to prime or a priming job for it is still pending.
+pub fn prime_cache(app: &AppHandle, cfg: &ProjectConfig) {
+    if cfg.dem_path.is_empty() || cfg.turbines.is_empty() {
+        return;
+    }
+    let queue = app.state::<JobQueue>();
+    if queue.snapshot().iter().any(|j| j.is_priming() && j.project == cfg.project_path && !j.status.is_finished()) {
+        return;
+    }
+    let submitted = serde_json::to_value(cfg).map_err(|e| e.to_string()).and_then(|mut value| {
+        value["mode"] = "prime".into();
+        queue.submit_value("cache priming".to_string(), None, Priority::Background, None, &cfg.project_path, value)
+    });
+    if let Err(e) = submitted {
+        eprintln!("cache priming: {e}");
+    }
+}
+
+/// Pauses lower-class running jobs while every worker is busy and a higher class is waiting.
+fn preempt(app: &AppHandle) {
+    let queue = app.state::<JobQueue>();
+    let workers = queue.workers.load(Ordering::Relaxed).max(1);
+    while let Some(victim) = queue.preemption_victim(workers) {
+        let paused = engine::client(app, &victim.project).and_then(|c| Ok(c.pause(victim.engine_id.as_deref().unwrap_or_default())?));
+        match paused {
+            Ok(()) => audit::note(&victim.project, "run_preempted", json!({ "job": victim.id })),
+            // Not checkpointable (or already finishing): it runs to the end instead.
+            Err(e) => {
+                queue.update(victim.id, |j| j.preempted = false);
+                eprintln!("preempting job {}: {e}", victim.id);
+                return;
+            }
+        }
+    }
+}
+
+/// Preempts for jobs just queued, now or once `start_at` is due; every submission calls it.
+pub fn preempt_when_due(app: &AppHandle, start_at: Option<DateTime<Local>>) {
+    let Some(at) = start_at else { return preempt(app) };
+    let app = app.clone();
+    thread::spawn(move || {
+        while let Ok(left) = (at - Local::now()).to_std() {
+            thread::sleep(left.min(SCHEDULE_RECHECK));
+        }
+        preempt(&app);
+    });
+}
+
+/// `start_at` schedules the run (see `parse_start_at`); without it the run starts when the queue is free.
+/// `priority` defaults to interactive for runs started now and batch for scheduled ones.
+#[tauri::command]
+pub fn submit_run(cfg: ProjectConfig, start_at: Option<String>, priority: Option<Priority>, app: AppHandle, queue: State<JobQueue>, window: tauri::Window, viewer: State<ViewerMode>) -> Result<u64, String> {
+    viewer.check(window.label())?;
+    let start_at = parse_optional_start_at(start_at)?;
+    disk::ensure_run_space(&app, &cfg, 1)?;
+    let priority = priority.unwrap_or(if start_at.is_some() { Priority::Batch } else { Priority::Interactive });
+    let id = queue.submit(if start_at.is_some() { "scheduled run" } else { "run" }.to_string(), None, priority, start_at, &cfg.project_path, &cfg)?;
+    audit::record(&app, &cfg, "run_submitted", json!({ "job": id, "start_at": start_at, "priority": priority }));
+    preempt_when_due(&app, start_at);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn get_job(id: u64, queue: State<JobQueue>) -> Result<Job, String> {
+    queue.get(id).ok_or_else(|| format!("job {id} not found"))
+}
+
+#[tauri::command]
+pub fn list_jobs(queue: State<JobQueue>) -> Vec<Job> {
+    queue.snapshot()
+}
+
+/// Only queued and paused jobs can be cancelled; a running calculation has to be paused first.
+#[tauri::command]
+pub fn cancel_job(id: u64, app: AppHandle, queue: State<JobQueue>, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Job, String> {
+    viewer.check(window.label())?;
+    let mut was_paused = false;
+    let job = queue
+        .update(id, |j| {
+            was_paused = j.status == JobStatus::Paused;
+            if matches!(j.status, JobStatus::Queued | JobStatus::Paused) {
+                j.status = JobStatus::Cancelled;
+            }
+        })
+        .ok_or_else(|| format!("job {id} not found"))?;
+    if job.status != JobStatus::Cancelled {
+        return Err(format!("job {id} is not queued or paused"));
+    }
+    if was_paused {
+        save_paused(&app);
+        let _ = fs::remove_file(engine::runtime_dir(&app).join("checkpoints").join(format!("{}.npz", job.checkpoint)));
+    }
+    notify::job_cancelled(&app, &job);
+    audit::note(&job.project, "run_cancelled", json!({ "job": id }));
+    Ok(job)
+}
+
+/// Asks the engine to checkpoint the running job; `job://paused` follows once it has stopped.
+/// Cache priming never checkpoints, so it cannot be paused (cancel it instead).
+#[tauri::command]
+pub fn pause_job(id: u64, app: AppHandle, queue: State<JobQueue>, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Job, String> {
+    viewer.check(window.label())?;
+    let job = queue.get(id).ok_or_else(|| format!("job {id} not found"))?;
+    if job.is_priming() {
+        return Err(format!("job {id} is cache priming, which cannot be paused; cancel it instead"));
+    }
+    let engine_id = job.engine_id.as_deref().filter(|_| job.status == JobStatus::Running).ok_or_else(|| format!("job {id} is not running"))?;
+    engine::client(&app, &job.project)?.pause(engine_id)?;
+    audit::note(&job.project, "run_paused", json!({ "job": id }));
+    Ok(job)
+}
+
+/// Queues a paused job again; the engine continues from its checkpoint.
+#[tauri::command]
+pub fn resume_job(id: u64, app: AppHandle, queue: State<JobQueue>, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Job, String> {
+    viewer.check(window.label())?;
+    let job = queue
+        .update(id, |j| {
+            if j.status == JobStatus::Paused {
+                j.status = JobStatus::Queued;
+                j.start_at = None;
+            }
+        })
+        .ok_or_else(|| format!("job {id} not found"))?;
+    if job.status != JobStatus::Queued {
+        return Err(format!("job {id} is not paused"));
+    }
+    queue.wake.notify_all();
+    save_paused(&app);
+    audit::note(&job.project, "run_resumed", json!({ "job": id }));
+    Ok(job)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn running(queue: &JobQueue, priority: Priority) -> u64 {
+        let id = queue.submit_value("run".to_string(), None, priority, None, "p", json!({ "project_path": "p" })).unwrap();
+        let job = queue.next();
+        assert_eq!(job.id, id);
+        queue.update(id, |j| j.engine_id = Some(format!("e{id}")));
+        id
+    }
+
+    #[test]
+    fn one_interactive_run_preempts_one_batch_job() {
+        let queue = JobQueue::default();
+        let workers = 2;
+        for _ in 0..workers {
+            running(&queue, Priority::Batch);
+        }
+        for _ in 0..3 {
+            queue.submit_value("sweep".to_string(), Some("b".to_string()), Priority::Batch, None, "p", json!({ "project_path": "p" })).unwrap();
+        }
+        queue.submit_value("check".to_string(), None, Priority::Interactive, None, "p", json!({ "project_path": "p" })).unwrap();
+
+        assert!(queue.preemption_victim(workers).is_some());
+        assert!(queue.preemption_victim(workers).is_none());
+        assert_eq!(queue.snapshot().iter().filter(|j| j.preempted).count(), 1);
+    }
+}
@@ -1,13 +1,77 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf, process::Command, sync::Mutex, thread, time::Duration};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    process::{Child, Command},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
 use tauri::{Manager, State};
 
+mod actions;
+mod archive;
+mod audit;
+mod backup;
+mod benchmark;
+mod buildings;
+mod coordinates;
+mod contours;
+mod crs;
+mod disk;
+mod dxf;
+mod engine;
+#[cfg(feature = "grpc")]
+mod engine_grpc;
+mod eta;
+mod gpx;
+mod headless;
+mod jobs;
+mod measure;
+mod meteo;
+mod montecarlo;
+mod notify;
+mod portable;
+mod print;
+mod profile;
+mod project;
+mod publish;
+mod raster;
+mod raster_calc;
+mod receptors;
+mod recent;
+mod results;
+mod scenarios;
+mod screenshot;
+mod sensitivity;
+mod setbacks;
+mod settings;
+mod tls;
+mod viewer;
+mod windpro;
+mod workspace;
+
+/// How long a stopping engine gets to exit on its own before it is killed.
+const ENGINE_STOP_GRACE: Duration = Duration::from_secs(3);
+
+/// A spawned engine; `pid` is the serving process named in its port file, in the one-file
+/// build a child of `child`.
+struct EngineProcess {
+    child: Child,
+    pid: Option<u32>,
+}
+
+/// Engine port and process by project handle, see `workspace`.
 #[derive(Default)]
-struct EngineState(Mutex<Option<u16>>);
+struct EngineState {
+    ports: Mutex<BTreeMap<String, u16>>,
+    processes: Mutex<BTreeMap<String, EngineProcess>>,
+}
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 struct Turbine {
     id: String,
     x: f64,
@@ -16,7 +80,24 @@ struct Turbine {
     rotor_diameter_m: f64,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Turbines of a neighbouring existing or consented farm: they contribute to the cumulative
+/// calculation but are reported apart from the project's own layout.
+#[derive(Clone, Serialize, Deserialize)]
+struct TurbineGroup {
+    name: String,
+    status: String,
+    turbines: Vec<Turbine>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Receptor {
+    id: String,
+    x: f64,
+    y: f64,
+    height_m: f64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 struct ProjectConfig {
     project_path: String,
     epsg: String,
@@ -24,13 +105,51 @@ struct ProjectConfig {
     buffer_m: f64,
     terrain_aware: bool,
     dem_path: String,
+    /// Surface model (vegetation, buildings) used for screening; `dem_path` stays the bare-earth DTM.
+    #[serde(default)]
+    dsm_path: String,
+    /// Classified raster whose classes get a screening height where no DSM is available.
+    #[serde(default)]
+    landcover_path: String,
+    #[serde(default = "default_landcover_heights")]
+    landcover_heights: BTreeMap<String, f64>,
+    /// Building height raster rasterized from footprints, merged into the screening surface.
+    #[serde(default)]
+    buildings_path: String,
     turbines: Vec<Turbine>,
+    #[serde(default)]
+    external_groups: Vec<TurbineGroup>,
+    #[serde(default)]
+    receptors: Vec<Receptor>,
+    #[serde(default)]
+    external_results: Vec<results::ExternalResult>,
+    /// Realistic-case weights; `None` computes astronomical worst-case hours.
+    #[serde(default)]
+    realistic: Option<montecarlo::RealisticCase>,
+    /// Opens the app in read-only viewer mode (see `viewer`).
+    #[serde(default)]
+    read_only: bool,
+    /// Alternatives run side by side by `scenarios::run_scenarios`.
+    #[serde(default)]
+    scenarios: Vec<scenarios::Scenario>,
+    /// GeoJSON Polygon or MultiPolygon in the project CRS; masks the print map and is reported.
+    #[serde(default)]
+    site_boundary: Option<serde_json::Value>,
     output: serde_json::Value,
 }
 
+/// Screening heights in metres for CORINE Land Cover level-3 codes.
+fn default_landcover_heights() -> BTreeMap<String, f64> {
+    [("111", 12.0), ("112", 8.0), ("121", 10.0), ("141", 8.0), ("222", 4.0), ("311", 20.0), ("312", 20.0), ("313", 20.0), ("324", 6.0)]
+        .into_iter()
+        .map(|(class, h)| (class.to_string(), h))
+        .collect()
+}
+
 #[tauri::command]
-fn get_engine_port(state: State<EngineState>) -> Result<u16, String> {
-    state.0.lock().map_err(|e| e.to_string())?.ok_or_else(|| "engine port unavailable".to_string())
+fn get_engine_port(window: tauri::WebviewWindow, state: State<EngineState>) -> Result<u16, String> {
+    let ports = state.ports.lock().map_err(|e| e.to_string())?;
+    ports.get(window.label()).or(ports.get(workspace::MAIN)).copied().ok_or_else(|| "engine port unavailable".to_string())
 }
 
 #[tauri::command]
@@ -38,11 +157,7 @@ fn pick_dem() -> Option<String> {
     rfd::FileDialog::new().add_filter("DEM", &["tif", "tiff", "asc"]).pick_file().map(|p| p.display().to_string())
 }
 
-#[tauri::command]
-fn import_csv_turbines() -> Result<Vec<Turbine>, String> {
-    let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).pick_file() else {
-        return Ok(vec![]);
-    };
+fn read_turbines_csv(path: &std::path::Path) -> Result<Vec<Turbine>, String> {
     let mut rdr = csv::ReaderBuilder::new().delimiter(b';').from_path(path).map_err(|e| e.to_string())?;
     let mut out = vec![];
     for rec in rdr.deserialize() {
@@ -53,7 +168,52 @@ fn import_csv_turbines() -> Result<Vec<Turbine>, String> {
 }
 
 #[tauri::command]
-fn choose_project(mode: &str) -> Result<ProjectConfig, String> {
+fn import_csv_turbines(window: tauri::Window, viewer: State<viewer::ViewerMode>) -> Result<Vec<Turbine>, String> {
+    viewer.check(window.label())?;
+    let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).pick_file() else {
+        return Ok(vec![]);
+    };
+    read_turbines_csv(&path)
+}
+
+#[tauri::command]
+fn import_external_group(name: String, status: String, window: tauri::Window, viewer: State<viewer::ViewerMode>) -> Result<Option<TurbineGroup>, String> {
+    viewer.check(window.label())?;
+    if !matches!(status.as_str(), "existing" | "consented") {
+        return Err(format!("invalid farm status: {status}"));
+    }
+    let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).pick_file() else {
+        return Ok(None);
+    };
+    Ok(Some(TurbineGroup { name, status, turbines: read_turbines_csv(&path)? }))
+}
+
+#[tauri::command]
+fn choose_project(mode: &str, window: tauri::Window, app: tauri::AppHandle, viewer: State<viewer::ViewerMode>) -> Result<ProjectConfig, String> {
+    if mode == "new" {
+        viewer.check(window.label())?;
+    }
+    let cfg = open_project(mode)?;
+    workspace::claim(&app, window.label(), &cfg.project_path)?;
+    project_opened(&app, &viewer, &cfg, mode, window.label());
+    Ok(cfg)
+}
+
+/// Session bookkeeping for a project just opened or created (`mode` as in `choose_project`) in
+/// the window `handle`.
+fn project_opened(app: &tauri::AppHandle, viewer: &viewer::ViewerMode, cfg: &ProjectConfig, mode: &str, handle: &str) {
+    app.state::<workspace::Workspace>().bind(handle, &cfg.project_path);
+    viewer.set_read_only(handle, cfg.read_only);
+    backup::track(app, &cfg.project_path);
+    project::loaded(app, &cfg.project_path, mode != "new");
+    jobs::prime_cache(app, cfg);
+    if mode != "demo" {
+        recent::remember(app, &cfg.project_path);
+    }
+    audit::record(app, cfg, if mode == "new" { "project_created" } else { "project_opened" }, serde_json::json!({}));
+}
+
+fn open_project(mode: &str) -> Result<ProjectConfig, String> {
     if mode == "demo" {
         let base = dirs::document_dir().unwrap_or(PathBuf::from(".")).join("WindShadowStudio").join("Demo");
         fs::create_dir_all(&base).map_err(|e| e.to_string())?;
@@ -71,11 +231,8 @@ fn choose_project(mode: &str) -> Result<ProjectConfig, String> {
     let Some(folder) = rfd::FileDialog::new().pick_folder() else {
         return Err("no folder selected".to_string());
     };
-    let proj_file = folder.join("project.wssproj.json");
-    if mode == "open" && proj_file.exists() {
-        let mut cfg: ProjectConfig = serde_json::from_str(&fs::read_to_string(proj_file).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
-        cfg.project_path = folder.display().to_string();
-        return Ok(cfg);
+    if mode == "open" && folder.join(project::PROJECT_FILE).exists() {
+        return project::read(&folder);
     }
     Ok(ProjectConfig {
         project_path: folder.display().to_string(),
@@ -84,46 +241,235 @@ fn choose_project(mode: &str) -> Result<ProjectConfig, String> {
         buffer_m: 2000.0,
         terrain_aware: false,
         dem_path: String::new(),
+        dsm_path: String::new(),
+        landcover_path: String::new(),
+        landcover_heights: default_landcover_heights(),
+        buildings_path: String::new(),
         turbines: vec![Turbine { id: "T1".into(), x: 500100.0, y: 5000100.0, hub_height_m: 120.0, rotor_diameter_m: 140.0 }],
+        external_groups: vec![],
+        receptors: vec![],
+        external_results: vec![],
+        realistic: None,
+        read_only: false,
+        scenarios: vec![],
+        site_boundary: None,
         output: serde_json::json!({"format":"both"}),
     })
 }
 
-fn start_engine(app: &tauri::AppHandle, state: &EngineState) {
-    let runtime = app.path().app_data_dir().unwrap_or(PathBuf::from(".")).join("runtime");
+/// Spawns the engine of `handle` and waits for its port file. The file has to carry the token
+/// given to this spawn: one left behind by a crashed session names a port nobody listens on.
+fn start_engine(app: &tauri::AppHandle, state: &EngineState, handle: &str) {
+    let runtime = engine::runtime_dir(app);
     fs::create_dir_all(&runtime).ok();
-    let port_file = runtime.join("port.json");
+    let port_file = engine::port_file(app, handle);
+    let token = format!("{}-{handle}-{}", std::process::id(), chrono::Local::now().timestamp_millis());
     let dev_sidecar = PathBuf::from("../engine/dist/engine.exe");
-    if dev_sidecar.exists() {
-        let _ = Command::new(dev_sidecar).env("WSS_RUNTIME_DIR", runtime.display().to_string()).spawn();
+    let mut cmd = if dev_sidecar.exists() {
+        Command::new(dev_sidecar)
     } else {
-        let _ = Command::new("python").arg("../engine/run_engine.py").env("WSS_RUNTIME_DIR", runtime.display().to_string()).spawn();
-    }
+        let mut python = Command::new("python");
+        python.arg("../engine/run_engine.py");
+        python
+    };
+    cmd.env("WSS_RUNTIME_DIR", runtime.display().to_string());
+    cmd.env("WSS_PORT_FILE", port_file.file_name().unwrap_or_default());
+    cmd.env("WSS_ENGINE_TOKEN", &token);
+    // The engine exits by itself should this process die without stopping it.
+    cmd.env("WSS_PARENT_PID", std::process::id().to_string());
+    #[cfg(feature = "grpc")]
+    cmd.env("WSS_GRPC", "1");
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("engine {handle}: {e}");
+            return;
+        }
+    };
+    let Ok(mut processes) = state.processes.lock() else {
+        // Nobody could stop an engine we cannot record.
+        let _ = child.kill();
+        return;
+    };
+    processes.insert(handle.to_string(), EngineProcess { child, pid: None });
+    drop(processes);
     for _ in 0..50 {
-        if let Ok(raw) = fs::read_to_string(&port_file) {
-            if let Ok(v) = serde_json::from_str::<serde_json::Value>(&raw) {
-                if let Some(p) = v["port"].as_u64() {
-                    let mut lock = state.0.lock().unwrap();
-                    *lock = Some(p as u16);
-                    break;
+        let ports = fs::read_to_string(&port_file).ok().and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok());
+        if let Some(v) = ports.filter(|v| v["token"] == token.as_str()) {
+            if let Some(p) = v["port"].as_u64() {
+                if let Ok(mut ports) = state.ports.lock() {
+                    ports.insert(handle.to_string(), p as u16);
+                }
+                if let Ok(mut processes) = state.processes.lock() {
+                    if let Some(process) = processes.get_mut(handle) {
+                        process.pid = v["pid"].as_u64().map(|pid| pid as u32);
+                    }
                 }
+                return;
+            }
+        }
+        if let Ok(mut processes) = state.processes.lock() {
+            if let Some(status) = processes.get_mut(handle).and_then(|p| p.child.try_wait().ok().flatten()) {
+                processes.remove(handle);
+                eprintln!("engine {handle} exited before listening: {status}");
+                return;
             }
         }
         thread::sleep(Duration::from_millis(200));
     }
 }
 
+/// Asks the engine of `handle` to stop, kills it after `ENGINE_STOP_GRACE` and forgets its port.
+fn stop_engine(app: &tauri::AppHandle, handle: &str) {
+    let state = app.state::<EngineState>();
+    let port = state.ports.lock().ok().and_then(|mut ports| ports.remove(handle));
+    let Some(mut process) = state.processes.lock().ok().and_then(|mut processes| processes.remove(handle)) else { return };
+    if let Some(port) = port {
+        let _ = engine::EngineClient::new(port).shutdown();
+    }
+    let deadline = Instant::now() + ENGINE_STOP_GRACE;
+    while process.child.try_wait().ok().flatten().is_none() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(100));
+    }
+    if process.child.try_wait().ok().flatten().is_none() {
+        let _ = process.child.kill();
+        // Killing the one-file launcher does not stop the Python process it started.
+        if let Some(pid) = process.pid.filter(|pid| *pid != process.child.id()) {
+            let _ = if cfg!(windows) { Command::new("taskkill").args(["/F", "/PID", &pid.to_string()]).status() } else { Command::new("kill").args(["-9", &pid.to_string()]).status() };
+        }
+    }
+    let _ = process.child.wait();
+    let _ = fs::remove_file(engine::port_file(app, handle));
+}
+
+/// Stops every engine at once, as the app exits.
+fn stop_engines(app: &tauri::AppHandle) {
+    let handles: Vec<String> = app.state::<EngineState>().processes.lock().map(|p| p.keys().cloned().collect()).unwrap_or_default();
+    let stopping: Vec<_> = handles
+        .into_iter()
+        .map(|handle| {
+            let app = app.clone();
+            thread::spawn(move || stop_engine(&app, &handle))
+        })
+        .collect();
+    for t in stopping {
+        let _ = t.join();
+    }
+}
+
+/// Stops and respawns the engine of the calling window (the main one for a window without its
+/// own) and returns the new port; runs in progress continue from their last checkpoint.
+#[tauri::command(async)]
+fn restart_engine(window: tauri::WebviewWindow, app: tauri::AppHandle) -> Result<u16, String> {
+    let state = app.state::<EngineState>();
+    let own = state.processes.lock().map_err(|e| e.to_string())?.contains_key(window.label());
+    let handle = if own { window.label() } else { workspace::MAIN }.to_string();
+    stop_engine(&app, &handle);
+    start_engine(&app, &state, &handle);
+    let port = state.ports.lock().map_err(|e| e.to_string())?.get(&handle).copied();
+    port.ok_or_else(|| "the engine did not start again".to_string())
+}
+
 fn main() {
+    portable::redirect_webview();
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(EngineState::default())
+        .manage(jobs::JobQueue::default())
+        .manage(backup::BackupState::default())
+        .manage(viewer::ViewerMode::from_launch())
+        .manage(audit::AuditState::default())
+        .manage(project::ProjectState::default())
+        .manage(workspace::Workspace::default())
         .setup(|app| {
             let handle = app.handle().clone();
+            let headless = headless::from_launch();
+            if let (None, Some(icon)) = (&headless, app.default_window_icon()) {
+                tauri::tray::TrayIconBuilder::with_id(eta::TRAY_ID).icon(icon.clone()).tooltip("Wind Shadow Studio").build(app)?;
+            }
             let state = app.state::<EngineState>();
-            start_engine(&handle, &state);
+            start_engine(&handle, &state, workspace::MAIN);
+            jobs::restore_paused(&handle);
+            jobs::start_workers(handle.clone());
+            match headless {
+                Some(mode) => headless::start(handle, mode),
+                None => backup::start_scheduler(handle),
+            }
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![get_engine_port, choose_project, pick_dem, import_csv_turbines])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                workspace::closed(window.app_handle(), window.label());
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            get_engine_port,
+            restart_engine,
+            choose_project,
+            pick_dem,
+            import_csv_turbines,
+            import_external_group,
+            windpro::export_windpro,
+            windpro::import_windpro,
+            results::register_external_result,
+            results::compare_external_result,
+            raster_calc::raster_calc,
+            buildings::rasterize_buildings,
+            receptors::derive_receptor_heights,
+            jobs::submit_run,
+            jobs::estimate_run_memory,
+            jobs::get_job,
+            jobs::list_jobs,
+            jobs::cancel_job,
+            jobs::pause_job,
+            jobs::resume_job,
+            sensitivity::run_sensitivity,
+            montecarlo::run_monte_carlo,
+            scenarios::run_scenarios,
+            settings::get_settings,
+            settings::set_settings,
+            notify::send_test_email,
+            publish::publish_results,
+            backup::backup_now,
+            viewer::get_viewer_mode,
+            portable::get_portable_mode,
+            print::list_printers,
+            print::print_report,
+            screenshot::capture_map,
+            coordinates::parse_coordinate,
+            gpx::import_gpx,
+            meteo::import_meteo_years,
+            dxf::export_dxf,
+            dxf::list_dxf_layers,
+            dxf::import_dxf_zones,
+            setbacks::generate_setbacks,
+            setbacks::export_setbacks,
+            measure::measure_distance,
+            measure::nearest_features,
+            measure::nearest_dwellings,
+            measure::turbine_receptor_report,
+            profile::profile_breakdown,
+            benchmark::run_benchmark,
+            archive::export_archive,
+            archive::import_archive,
+            audit::get_audit_log,
+            audit::export_audit_log,
+            project::save_project,
+            recent::list_recent_projects,
+            recent::set_active_scenario,
+            recent::reopen_last_project,
+            workspace::open_project_window,
+            workspace::window_project,
+            workspace::list_open_projects,
+            actions::list_actions,
+            actions::invoke_action
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                stop_engines(app);
+            }
+        });
 }
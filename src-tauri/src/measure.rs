@@ -0,0 +1,216 @@
+//! Distances and bearings for the map's measuring tools and the report tables: geodesic on the
+//! WGS84 ellipsoid between points in the project CRS, next to the plain grid figures that CAD
+//! drawings show. Over a wind farm the two differ by the grid scale factor (up to a few 1e-4).
+
+use crate::{crs, ProjectConfig};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, io::Read, path::PathBuf};
+
+const DEFAULT_NEAREST: usize = 5;
+/// Written by the engine next to the grids: receptor;turbine;external;hours.
+const CONTRIBUTIONS_FILE: &str = "receptor_contributions.csv";
+const COMPASS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+
+#[derive(Clone, Copy, Deserialize)]
+pub struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct Measurement {
+    /// Along the ellipsoid, the distance a survey reports.
+    pub distance_m: f64,
+    /// True north azimuth, degrees clockwise, at the start and seen back from the end.
+    pub bearing_deg: f64,
+    pub back_bearing_deg: f64,
+    /// Straight line and direction from grid north in map coordinates; `None` in lon/lat projects.
+    pub grid_distance_m: Option<f64>,
+    pub grid_bearing_deg: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct Nearby {
+    kind: &'static str,
+    id: String,
+    x: f64,
+    y: f64,
+    #[serde(flatten)]
+    measurement: Measurement,
+}
+
+#[derive(Serialize)]
+pub struct NearestDwelling {
+    turbine: String,
+    receptor: String,
+    #[serde(flatten)]
+    measurement: Measurement,
+}
+
+pub fn measure(crs: &crs::Crs, from: (f64, f64), to: (f64, f64)) -> Measurement {
+    let (lon1, lat1) = crs.unproject(from.0, from.1);
+    let (lon2, lat2) = crs.unproject(to.0, to.1);
+    let geographic = matches!(crs, crs::Crs::Geographic);
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let grid_distance_m = (!geographic).then(|| dx.hypot(dy));
+    let grid_bearing_deg = (!geographic).then(|| dx.atan2(dy).to_degrees().rem_euclid(360.0));
+    // Vincenty fails only near antipodes, far beyond any project; the grid figures stand in.
+    let (distance_m, bearing_deg, arrival) = crs::geodesic(lon1, lat1, lon2, lat2).unwrap_or((grid_distance_m.unwrap_or(f64::NAN), grid_bearing_deg.unwrap_or(f64::NAN), grid_bearing_deg.unwrap_or(f64::NAN)));
+    Measurement { distance_m, bearing_deg, back_bearing_deg: (arrival + 180.0).rem_euclid(360.0), grid_distance_m, grid_bearing_deg }
+}
+
+/// Project and external turbines and receptors as (kind, id, position).
+fn features(cfg: &ProjectConfig) -> Vec<(&'static str, &str, (f64, f64))> {
+    let turbines = cfg.turbines.iter().map(|t| ("turbine", t.id.as_str(), (t.x, t.y)));
+    let external = cfg.external_groups.iter().flat_map(|g| &g.turbines).map(|t| ("external_turbine", t.id.as_str(), (t.x, t.y)));
+    let receptors = cfg.receptors.iter().map(|r| ("receptor", r.id.as_str(), (r.x, r.y)));
+    turbines.chain(external).chain(receptors).collect()
+}
+
+#[tauri::command]
+pub fn measure_distance(cfg: ProjectConfig, from: Point, to: Point) -> Result<Measurement, String> {
+    Ok(measure(&crs::parse_epsg(&cfg.epsg)?, (from.x, from.y), (to.x, to.y)))
+}
+
+/// The `limit` (default 5) features closest to `at`, of `kind` (`turbine`, `external_turbine`,
+/// `receptor`) or of every kind.
+#[tauri::command]
+pub fn nearest_features(cfg: ProjectConfig, at: Point, kind: Option<String>, limit: Option<usize>) -> Result<Vec<Nearby>, String> {
+    let crs = crs::parse_epsg(&cfg.epsg)?;
+    let mut out: Vec<Nearby> = features(&cfg)
+        .into_iter()
+        .filter(|(k, _, _)| kind.as_deref().is_none_or(|want| want == *k))
+        .map(|(kind, id, p)| Nearby { kind, id: id.to_string(), x: p.0, y: p.1, measurement: measure(&crs, (at.x, at.y), p) })
+        .collect();
+    out.sort_by(|a, b| a.measurement.distance_m.total_cmp(&b.measurement.distance_m));
+    out.truncate(limit.unwrap_or(DEFAULT_NEAREST));
+    Ok(out)
+}
+
+/// Distance from each project turbine to its nearest receptor, for the report table.
+#[tauri::command]
+pub fn nearest_dwellings(cfg: ProjectConfig) -> Result<Vec<NearestDwelling>, String> {
+    let crs = crs::parse_epsg(&cfg.epsg)?;
+    Ok(cfg
+        .turbines
+        .iter()
+        .filter_map(|t| {
+            cfg.receptors
+                .iter()
+                .map(|r| (r, measure(&crs, (t.x, t.y), (r.x, r.y))))
+                .min_by(|a, b| a.1.distance_m.total_cmp(&b.1.distance_m))
+                .map(|(r, measurement)| NearestDwelling { turbine: t.id.clone(), receptor: r.id.clone(), measurement })
+        })
+        .collect())
+}
+
+#[derive(Serialize)]
+pub struct ReceptorImpact {
+    receptor: String,
+    /// Eight-point direction of the receptor as seen from the turbine.
+    direction: &'static str,
+    #[serde(flatten)]
+    measurement: Measurement,
+    /// Hours this turbine adds at the receptor and the receptor's total from all turbines,
+    /// `None` before a run with receptors or for receptors outside the grid.
+    contribution_h: Option<f64>,
+    total_h: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct TurbineImpacts {
+    turbine: String,
+    receptors: Vec<ReceptorImpact>,
+}
+
+#[derive(Serialize)]
+pub struct ImpactReport {
+    turbines: Vec<TurbineImpacts>,
+    warnings: Vec<String>,
+}
+
+fn compass(bearing_deg: f64) -> &'static str {
+    COMPASS[((bearing_deg / 45.0).round() as usize) % 8]
+}
+
+#[derive(Deserialize)]
+struct Contribution {
+    receptor: String,
+    turbine: String,
+    /// 1 for a turbine of an external group, whose ids may repeat the project's.
+    external: u8,
+    hours: f64,
+}
+
+type Contributions = HashMap<(String, String, u8), f64>;
+
+fn read_contributions(data: impl Read) -> Result<Contributions, String> {
+    let mut rdr = csv::ReaderBuilder::new().delimiter(b';').from_reader(data);
+    let mut out = HashMap::new();
+    for rec in rdr.deserialize() {
+        let c: Contribution = rec.map_err(|e| e.to_string())?;
+        *out.entry((c.receptor, c.turbine, c.external)).or_insert(0.0) += c.hours;
+    }
+    Ok(out)
+}
+
+/// (receptor, turbine, external) hours of the last run, `None` without a contributions file. The
+/// file has every pair for receptors inside the grid and none for the others.
+fn contributions(cfg: &ProjectConfig) -> Result<Option<Contributions>, String> {
+    let path = PathBuf::from(&cfg.project_path).join("outputs").join(CONTRIBUTIONS_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let file = fs::File::open(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    read_contributions(file).map(Some).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// For each project turbine, its `limit` (default 5) closest receptors with distance, direction
+/// and the turbine's share of their hours in the last run, to see which machines drive exceedances.
+#[tauri::command]
+pub fn turbine_receptor_report(cfg: ProjectConfig, limit: Option<usize>) -> Result<ImpactReport, String> {
+    let crs = crs::parse_epsg(&cfg.epsg)?;
+    let mut warnings = vec![];
+    let hours = contributions(&cfg)?;
+    if hours.is_none() {
+        warnings.push(format!("no {CONTRIBUTIONS_FILE} in outputs/: run the calculation with receptors for turbine contributions"));
+    }
+    let totals: HashMap<&str, f64> = hours.iter().flatten().fold(HashMap::new(), |mut acc, ((r, _, _), h)| {
+        *acc.entry(r.as_str()).or_insert(0.0) += h;
+        acc
+    });
+    let turbines = cfg
+        .turbines
+        .iter()
+        .map(|t| {
+            let mut receptors: Vec<ReceptorImpact> = cfg
+                .receptors
+                .iter()
+                .map(|r| {
+                    let measurement = measure(&crs, (t.x, t.y), (r.x, r.y));
+                    let total_h = totals.get(r.id.as_str()).copied();
+                    let contribution_h = hours.as_ref().and_then(|h| h.get(&(r.id.clone(), t.id.clone(), 0)).copied());
+                    ReceptorImpact { receptor: r.id.clone(), direction: compass(measurement.bearing_deg), measurement, contribution_h, total_h }
+                })
+                .collect();
+            receptors.sort_by(|a, b| a.measurement.distance_m.total_cmp(&b.measurement.distance_m));
+            receptors.truncate(limit.unwrap_or(DEFAULT_NEAREST));
+            TurbineImpacts { turbine: t.id.clone(), receptors }
+        })
+        .collect();
+    Ok(ImpactReport { turbines, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn external_turbine_with_a_project_id_is_kept_apart() {
+        let csv = "receptor;turbine;external;hours\nR1;T1;0;12.50\nR1;T1;1;4.00\nR1;T2;0;1.00\n";
+        let hours = read_contributions(csv.as_bytes()).unwrap();
+        assert_eq!(hours[&("R1".to_string(), "T1".to_string(), 0)], 12.5);
+        assert_eq!(hours[&("R1".to_string(), "T1".to_string(), 1)], 4.0);
+        assert_eq!(hours.len(), 3);
+    }
+}
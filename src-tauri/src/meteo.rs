@@ -0,0 +1,72 @@
+//! Meteorological years for multi-year runs: sunshine and operational data per year, imported
+//! from a `;` CSV with one row per year and the columns `year`, `sun_01` to `sun_12` (monthly
+//! probability of direct sunshine), `operating_fraction` and optionally `wind_00`, `wind_01`, ...
+//! (relative frequency per wind sector, sector 0 centred on north). The engine computes each
+//! year and reports the long-term average with the spread per receptor.
+
+use crate::viewer::ViewerMode;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::State;
+
+/// A multi-year run needs at least this many years; one year is an ordinary realistic case.
+const MIN_YEARS: usize = 2;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MeteoYear {
+    pub year: i32,
+    pub sunshine_probability: Vec<f64>,
+    pub operating_fraction: f64,
+    #[serde(default)]
+    pub wind_direction_frequency: Vec<f64>,
+}
+
+fn fraction(value: &str, column: &str, row: usize) -> Result<f64, String> {
+    let v: f64 = value.trim().replace(',', ".").parse().map_err(|_| format!("row {row}: invalid {column}: {value}"))?;
+    if !(0.0..=1.0).contains(&v) {
+        return Err(format!("row {row}: {column} must be between 0 and 1, got {v}"));
+    }
+    Ok(v)
+}
+
+fn read_csv(path: &Path) -> Result<Vec<MeteoYear>, String> {
+    let mut rdr = csv::ReaderBuilder::new().delimiter(b';').from_path(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let headers: Vec<String> = rdr.headers().map_err(|e| e.to_string())?.iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |name: &str| headers.iter().position(|h| h == name).ok_or(format!("{}: missing column {name}", path.display()));
+    let year = column("year")?;
+    let months = (1..=12).map(|m| column(&format!("sun_{m:02}"))).collect::<Result<Vec<_>, _>>()?;
+    let operating = column("operating_fraction")?;
+    let mut sectors: Vec<usize> = (0..).map_while(|k| column(&format!("wind_{k:02}")).ok()).collect();
+    if sectors.len() < 2 {
+        sectors.clear();
+    }
+    let mut years: Vec<MeteoYear> = vec![];
+    for (i, rec) in rdr.records().enumerate() {
+        let rec = rec.map_err(|e| e.to_string())?;
+        let (row, get) = (i + 2, |c: usize| rec.get(c).unwrap_or_default());
+        let y = MeteoYear {
+            year: get(year).trim().parse().map_err(|_| format!("row {row}: invalid year: {}", get(year)))?,
+            sunshine_probability: months.iter().enumerate().map(|(m, &c)| fraction(get(c), &format!("sun_{:02}", m + 1), row)).collect::<Result<_, _>>()?,
+            operating_fraction: fraction(get(operating), "operating_fraction", row)?,
+            wind_direction_frequency: sectors.iter().map(|&c| get(c).trim().replace(',', ".").parse().map_err(|_| format!("row {row}: invalid wind frequency: {}", get(c)))).collect::<Result<_, _>>()?,
+        };
+        if years.iter().any(|o| o.year == y.year) {
+            return Err(format!("row {row}: year {} listed twice", y.year));
+        }
+        years.push(y);
+    }
+    if years.len() < MIN_YEARS {
+        return Err(format!("{}: {} year(s), a multi-year run needs at least {MIN_YEARS}", path.display(), years.len()));
+    }
+    years.sort_by_key(|y| y.year);
+    Ok(years)
+}
+
+#[tauri::command]
+pub fn import_meteo_years(window: tauri::Window, viewer: State<ViewerMode>) -> Result<Option<Vec<MeteoYear>>, String> {
+    viewer.check(window.label())?;
+    let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).pick_file() else {
+        return Ok(None);
+    };
+    read_csv(&path).map(Some)
+}
@@ -0,0 +1,169 @@
+//! Monte Carlo uncertainty runs: realistic-case inputs are sampled N times, each sample runs as
+//! a job of one batch, and the receptor hours are summarised as percentile bands.
+
+use crate::{
+    audit, disk,
+    jobs::{self, JobQueue},
+    meteo, results,
+    viewer::ViewerMode,
+    workspace, ProjectConfig,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf, thread};
+use tauri::{AppHandle, Manager, State};
+
+const MAX_RUNS: usize = 200;
+const PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+/// Realistic-case weights sent to the engine (see `RealisticCase` in the engine).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RealisticCase {
+    /// Monthly probability of direct sunshine, January first.
+    pub sunshine_probability: Vec<f64>,
+    pub operating_fraction: f64,
+    /// Relative frequency per wind sector, sector 0 centred on north, clockwise.
+    #[serde(default)]
+    pub wind_direction_frequency: Vec<f64>,
+    /// Years computed apart and averaged; the weights above are then unused.
+    #[serde(default)]
+    pub years: Vec<meteo::MeteoYear>,
+}
+
+#[derive(Deserialize)]
+pub struct Uncertainty {
+    runs: usize,
+    /// Central values; the samples spread around them.
+    base: RealisticCase,
+    /// Standard deviation of each monthly sunshine probability.
+    sunshine_sd: f64,
+    operating_sd: f64,
+    /// Relative (log-normal) spread of every wind sector frequency.
+    wind_sector_sd: f64,
+    seed: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct UncertaintyRow {
+    id: String,
+    mean_h: Option<f64>,
+    /// Hours at `PERCENTILES`, i.e. P10, P50 and P90.
+    bands_h: Vec<Option<f64>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct UncertaintyTable {
+    batch: String,
+    runs: usize,
+    failed_runs: usize,
+    percentiles: Vec<f64>,
+    rows: Vec<UncertaintyRow>,
+    csv_path: String,
+}
+
+/// Standard normal draw (Box-Muller).
+fn normal(rng: &mut StdRng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+fn sample(u: &Uncertainty, rng: &mut StdRng) -> RealisticCase {
+    RealisticCase {
+        sunshine_probability: u.base.sunshine_probability.iter().map(|p| (p + u.sunshine_sd * normal(rng)).clamp(0.0, 1.0)).collect(),
+        operating_fraction: (u.base.operating_fraction + u.operating_sd * normal(rng)).clamp(0.0, 1.0),
+        wind_direction_frequency: u.base.wind_direction_frequency.iter().map(|f| f * (u.wind_sector_sd * normal(rng)).exp()).collect(),
+        years: vec![],
+    }
+}
+
+/// Linear interpolation between closest ranks; `sorted` must be ascending.
+fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = p / 100.0 * last as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64))
+}
+
+fn tabulate(batch: &str, cfg: &ProjectConfig, jobs: &[crate::jobs::Job], dir: &std::path::Path) -> Result<UncertaintyTable, String> {
+    let done: Vec<_> = jobs.iter().filter_map(|j| j.engine.as_ref().filter(|_| j.error.is_none())).collect();
+    if done.is_empty() {
+        return Err("all Monte Carlo runs failed".to_string());
+    }
+    let per_run = done.iter().map(|e| results::receptor_hours(e, &cfg.receptors)).collect::<Result<Vec<_>, _>>()?;
+    let rows: Vec<UncertaintyRow> = cfg
+        .receptors
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let mut hours: Vec<f64> = per_run.iter().filter_map(|run| run[i]).collect();
+            hours.sort_by(f64::total_cmp);
+            let mean_h = (!hours.is_empty()).then(|| hours.iter().sum::<f64>() / hours.len() as f64);
+            UncertaintyRow { id: r.id.clone(), mean_h, bands_h: PERCENTILES.iter().map(|p| percentile(&hours, *p)).collect() }
+        })
+        .collect();
+
+    let csv_path = dir.join("montecarlo.csv");
+    let mut w = csv::WriterBuilder::new().delimiter(b';').from_path(&csv_path).map_err(|e| e.to_string())?;
+    let mut header = vec!["receptor".to_string(), "mean_h".to_string()];
+    header.extend(PERCENTILES.iter().map(|p| format!("p{p:.0}_h")));
+    w.write_record(&header).map_err(|e| e.to_string())?;
+    let fmt = |v: Option<f64>| v.map(|h| format!("{h:.2}")).unwrap_or_default();
+    for r in &rows {
+        let mut rec = vec![r.id.clone(), fmt(r.mean_h)];
+        rec.extend(r.bands_h.iter().map(|h| fmt(*h)));
+        w.write_record(&rec).map_err(|e| e.to_string())?;
+    }
+    w.flush().map_err(|e| e.to_string())?;
+    Ok(UncertaintyTable {
+        batch: batch.to_string(),
+        runs: jobs.len(),
+        failed_runs: jobs.len() - done.len(),
+        percentiles: PERCENTILES.to_vec(),
+        rows,
+        csv_path: csv_path.display().to_string(),
+    })
+}
+
+/// Queues `runs` sampled realistic-case runs and returns the batch id; the percentile table
+/// arrives as a `montecarlo://done` event (or `montecarlo://failed`).
+#[tauri::command]
+pub fn run_monte_carlo(cfg: ProjectConfig, uncertainty: Uncertainty, start_at: Option<String>, app: AppHandle, queue: State<JobQueue>, window: tauri::Window, viewer: State<ViewerMode>) -> Result<String, String> {
+    viewer.check(window.label())?;
+    let start_at = jobs::parse_optional_start_at(start_at)?;
+    if cfg.receptors.is_empty() {
+        return Err("Monte Carlo runs need receptors".to_string());
+    }
+    if uncertainty.runs == 0 || uncertainty.runs > MAX_RUNS {
+        return Err(format!("runs must be between 1 and {MAX_RUNS}"));
+    }
+    if uncertainty.base.sunshine_probability.len() != 12 {
+        return Err("sunshine_probability needs 12 monthly values".to_string());
+    }
+    disk::ensure_run_space(&app, &cfg, uncertainty.runs)?;
+    let batch = format!("montecarlo-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let dir = PathBuf::from(&cfg.project_path).join("montecarlo").join(&batch);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut rng = uncertainty.seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+    let mut ids = vec![];
+    for i in 0..uncertainty.runs {
+        let mut run = cfg.clone();
+        run.realistic = Some(sample(&uncertainty, &mut rng));
+        run.project_path = dir.join(format!("run{i:03}")).display().to_string();
+        if run.output["format"] == "geotiff" {
+            run.output["format"] = "both".into();
+        }
+        ids.push(queue.submit(format!("monte carlo {}/{}", i + 1, uncertainty.runs), Some(batch.clone()), jobs::Priority::Batch, start_at, &cfg.project_path, &run)?);
+    }
+    jobs::preempt_when_due(&app, start_at);
+    audit::record(&app, &cfg, "monte_carlo_submitted", serde_json::json!({ "batch": batch, "runs": uncertainty.runs, "seed": uncertainty.seed, "start_at": start_at }));
+    let batch_id = batch.clone();
+    thread::spawn(move || {
+        let jobs = app.state::<JobQueue>().wait_all(&ids);
+        match tabulate(&batch, &cfg, &jobs, &dir) {
+            Ok(table) => workspace::emit(&app, &cfg.project_path, "montecarlo://done", table),
+            Err(e) => workspace::emit(&app, &cfg.project_path, "montecarlo://failed", serde_json::json!({ "batch": batch, "error": e })),
+        }
+    });
+    Ok(batch_id)
+}
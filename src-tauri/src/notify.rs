@@ -0,0 +1,226 @@
+//! Job notices: a desktop notification for scheduled runs and batches, an email with a
+//! compliance table when SMTP is configured, and webhooks on run lifecycle events.
+
+use crate::{
+    jobs::{Job, JobQueue, JobStatus},
+    results, settings,
+    viewer::ViewerMode,
+};
+use lettre::{
+    message::{header::ContentType, Attachment, MultiPart, SinglePart},
+    transport::smtp::authentication::Credentials,
+    Message, SmtpTransport, Transport,
+};
+use serde_json::{json, Value};
+use std::{thread, time::Duration};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+struct Notice {
+    subject: String,
+    body: String,
+    jobs: Vec<Job>,
+}
+
+fn describe(job: &Job) -> String {
+    let max = job.engine.as_ref().and_then(|e| e["stats"]["max"].as_f64()).unwrap_or(0.0);
+    match (job.status, &job.error) {
+        (JobStatus::Cancelled, _) => format!("{}: cancelled", job.label),
+        (_, Some(e)) => format!("{}: failed: {e}", job.label),
+        _ => format!("{}: done, max {max:.1} h/yr", job.label),
+    }
+}
+
+fn notice(app: &AppHandle, job: &Job) -> Option<Notice> {
+    if let Some(batch) = &job.batch {
+        let jobs = app.state::<JobQueue>().finished_batch(batch)?;
+        let failed = jobs.iter().filter(|j| j.status == JobStatus::Error).count();
+        let subject = format!("Batch {batch} finished ({} runs, {failed} failed)", jobs.len());
+        let body = jobs.iter().map(describe).collect::<Vec<_>>().join("\n");
+        return Some(Notice { subject, body, jobs });
+    }
+    let outcome = if job.error.is_some() { "failed" } else { "completed" };
+    Some(Notice { subject: format!("Run \"{}\" {outcome}", job.label), body: describe(job), jobs: vec![job.clone()] })
+}
+
+/// `job;receptor;source;hours_h;limit_h;exceeds` for every finished job that has receptors:
+/// the cumulative hours (`total`) and, for runs with external groups, the project's own share
+/// and each group's apart.
+fn compliance_csv(jobs: &[Job], limit_h: f64) -> Result<Option<Vec<u8>>, String> {
+    let mut w = csv::WriterBuilder::new().delimiter(b';').from_writer(vec![]);
+    w.write_record(["job", "receptor", "source", "hours_h", "limit_h", "exceeds"]).map_err(|e| e.to_string())?;
+    let mut rows = 0;
+    for job in jobs {
+        let Some(engine) = job.engine.as_ref().filter(|_| job.status == JobStatus::Done) else { continue };
+        let receptors = job.receptors();
+        let Ok(hours) = results::receptor_hours(engine, &receptors) else { continue };
+        for (r, h) in receptors.iter().zip(hours) {
+            for (source, h) in std::iter::once(("total".to_string(), h)).chain(cumulative_shares(engine, &r.id)) {
+                let exceeds = h.map(|h| if h > limit_h { "yes" } else { "no" }).unwrap_or("n/a");
+                w.write_record([job.label.as_str(), &r.id, &source, &h.map(|h| format!("{h:.2}")).unwrap_or_default(), &format!("{limit_h}"), exceeds]).map_err(|e| e.to_string())?;
+                rows += 1;
+            }
+        }
+    }
+    let data = w.into_inner().map_err(|e| e.to_string())?;
+    Ok((rows > 0).then_some(data))
+}
+
+/// `own` and `<group> (<status>)` hours at `receptor` from the engine's cumulative rows, empty
+/// without external groups or for receptors outside the grid.
+fn cumulative_shares(engine: &Value, receptor: &str) -> Vec<(String, Option<f64>)> {
+    let rows = engine["cumulative"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let Some(row) = rows.iter().find(|r| r["receptor"] == receptor) else { return vec![] };
+    let groups = row["groups"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let shares = groups.iter().map(|g| (format!("{} ({})", g["name"].as_str().unwrap_or_default(), g["status"].as_str().unwrap_or_default()), g["hours_h"].as_f64()));
+    std::iter::once(("own".to_string(), row["own_h"].as_f64())).chain(shares).collect()
+}
+
+fn send_email(smtp: &settings::SmtpSettings, subject: &str, body: &str, table: Option<Vec<u8>>) -> Result<(), String> {
+    let mut builder = Message::builder().from(smtp.from.parse().map_err(|e| format!("from: {e}"))?).subject(format!("Wind Shadow Studio: {subject}"));
+    for to in &smtp.to {
+        builder = builder.to(to.parse().map_err(|e| format!("to {to}: {e}"))?);
+    }
+    let mut parts = MultiPart::mixed().singlepart(SinglePart::plain(body.to_string()));
+    if let Some(table) = table {
+        parts = parts.singlepart(Attachment::new("compliance.csv".to_string()).body(table, ContentType::parse("text/csv").map_err(|e| e.to_string())?));
+    }
+    let email = builder.multipart(parts).map_err(|e| e.to_string())?;
+    let transport = match smtp.security.as_str() {
+        "tls" => SmtpTransport::relay(&smtp.server),
+        "none" => Ok(SmtpTransport::builder_dangerous(&smtp.server)),
+        _ => SmtpTransport::starttls_relay(&smtp.server),
+    }
+    .map_err(|e| e.to_string())?
+    .port(smtp.port);
+    let transport = if smtp.username.is_empty() { transport } else { transport.credentials(Credentials::new(smtp.username.clone(), smtp.password.clone())) };
+    transport.build().send(&email).map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn placeholders(event: &str, job: &Job) -> Value {
+    let stats = job.engine.as_ref().map(|e| e["stats"].clone()).unwrap_or_default();
+    json!({
+        "event": event,
+        "job.id": job.id,
+        "job.label": job.label,
+        "job.batch": job.batch,
+        "job.status": job.status,
+        "job.error": job.error,
+        "job.project_path": job.project_path,
+        "job.project": job.project,
+        "stats.max": stats["max"],
+        "stats.mean": stats["mean"],
+    })
+}
+
+/// Replaces `{{name}}` with the JSON-escaped value, without quotes, so placeholders can sit
+/// inside JSON strings (`{"text": "Run {{job.label}} {{event}}"}`). Unknown names are left as is.
+pub fn render(template: &str, values: &Value) -> String {
+    let mut out = template.to_string();
+    for (name, value) in values.as_object().into_iter().flatten() {
+        let text = match value {
+            Value::String(s) => {
+                let quoted = serde_json::to_string(s).unwrap_or_default();
+                quoted[1..quoted.len() - 1].to_string()
+            }
+            Value::Null => String::new(),
+            v => v.to_string(),
+        };
+        out = out.replace(&format!("{{{{{name}}}}}"), &text);
+    }
+    out
+}
+
+fn post_webhook(hook: &settings::Webhook, event: &str, job: &Job) -> Result<(), String> {
+    let body = if hook.template.trim().is_empty() { json!({ "event": event, "job": job }).to_string() } else { render(&hook.template, &placeholders(event, job)) };
+    ureq::post(&hook.url).timeout(Duration::from_secs(10)).set("Content-Type", "application/json").send_string(&body).map(|_| ()).map_err(|e| format!("{}: {e}", hook.url))
+}
+
+/// Fires the webhooks subscribed to `event` on their own thread.
+pub fn job_event(app: &AppHandle, event: &'static str, job: &Job) {
+    let hooks: Vec<_> = settings::load(app).webhooks.into_iter().filter(|h| h.events.is_empty() || h.events.iter().any(|e| e == event)).collect();
+    if hooks.is_empty() {
+        return;
+    }
+    let (app, job) = (app.clone(), job.clone());
+    thread::spawn(move || {
+        for hook in &hooks {
+            if let Err(e) = post_webhook(hook, event, &job) {
+                let _ = app.emit("notify://webhook_failed", e);
+            }
+        }
+    });
+}
+
+/// Called by the worker for every finished job; mail goes out on its own thread.
+pub fn job_finished(app: &AppHandle, job: &Job) {
+    job_event(app, if job.error.is_some() { "failed" } else { "done" }, job);
+    announce(app, job);
+}
+
+/// Called for a job cancelled before it ran, which may be the last one of its batch.
+pub fn job_cancelled(app: &AppHandle, job: &Job) {
+    job_event(app, "cancelled", job);
+    if job.batch.is_some() {
+        announce(app, job);
+    }
+}
+
+/// Batch webhook, desktop notification and email for a finished run or batch.
+fn announce(app: &AppHandle, job: &Job) {
+    let Some(notice) = notice(app, job) else { return };
+    if job.batch.is_some() {
+        job_event(app, "batch_finished", job);
+    }
+    let settings = settings::load(app);
+    let scheduled = job.batch.is_some() || job.start_at.is_some();
+    if scheduled {
+        let _ = app.notification().builder().title("Wind Shadow Studio").body(&notice.subject).show();
+    }
+    let long = match (job.started_at, job.finished_at) {
+        (Some(a), Some(b)) => (b - a).num_seconds() as f64 >= settings.long_run_min * 60.0,
+        _ => false,
+    };
+    let Some(smtp) = settings.smtp.filter(|_| scheduled || long) else { return };
+    let app = app.clone();
+    thread::spawn(move || {
+        let sent = compliance_csv(&notice.jobs, settings.compliance_limit_h).and_then(|table| send_email(&smtp, &notice.subject, &notice.body, table));
+        if let Err(e) = sent {
+            let _ = app.emit("notify://email_failed", e);
+        }
+    });
+}
+
+/// Sends a test message with the given settings, so the SMTP form can be checked before saving;
+/// an empty password uses the saved one (which `get_settings` does not return) for the saved
+/// account only, see `SmtpSettings::fill_password`.
+#[tauri::command]
+pub fn send_test_email(mut smtp: settings::SmtpSettings, window: tauri::Window, viewer: State<ViewerMode>) -> Result<(), String> {
+    viewer.check(window.label())?;
+    smtp.fill_password(settings::load(window.app_handle()).smtp.as_ref());
+    if smtp.password.is_empty() && !smtp.username.trim().is_empty() {
+        return Err("enter the SMTP password: the saved one is only used for the saved server and username".to_string());
+    }
+    send_email(&smtp, "test message", "SMTP settings work.", None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cumulative_shares_split_own_and_each_group() {
+        let engine = json!({ "cumulative": [{
+            "receptor": "R1",
+            "own_h": 12.5,
+            "groups": [{ "name": "Monte Alto", "status": "existing", "hours_h": 20.0 }, { "name": "Colle", "status": "consented", "hours_h": 3.25 }],
+            "total_h": 35.75,
+        }] });
+        assert_eq!(
+            cumulative_shares(&engine, "R1"),
+            vec![("own".to_string(), Some(12.5)), ("Monte Alto (existing)".to_string(), Some(20.0)), ("Colle (consented)".to_string(), Some(3.25))]
+        );
+        assert!(cumulative_shares(&engine, "R2").is_empty());
+        assert!(cumulative_shares(&json!({}), "R1").is_empty());
+    }
+}
@@ -0,0 +1,40 @@
+//! Portable mode for running from a USB stick on air-gapped site machines: with a
+//! `portable.flag` next to the executable, settings, recent projects, run history, the engine
+//! runtime and the webview cache live in `data/` beside it instead of the OS profile folders.
+
+use std::{path::PathBuf, sync::OnceLock};
+use tauri::{AppHandle, Manager};
+
+pub const FLAG_FILE: &str = "portable.flag";
+
+/// `data/` next to the executable when the flag is present, checked once per launch.
+fn root() -> Option<&'static PathBuf> {
+    static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+    ROOT.get_or_init(|| {
+        let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+        exe_dir.join(FLAG_FILE).is_file().then(|| exe_dir.join("data"))
+    })
+    .as_ref()
+}
+
+/// Settings and recent projects.
+pub fn config_dir(app: &AppHandle) -> PathBuf {
+    root().map(|r| r.join("config")).or_else(|| app.path().app_config_dir().ok()).unwrap_or(PathBuf::from("."))
+}
+
+/// Run history and the engine runtime; `data/` itself in portable mode, next to `config/` and `webview/`.
+pub fn data_dir(app: &AppHandle) -> PathBuf {
+    root().cloned().or_else(|| app.path().app_data_dir().ok()).unwrap_or(PathBuf::from("."))
+}
+
+/// Must run before the webview starts: WebView2 keeps its profile and cache under this folder.
+pub fn redirect_webview() {
+    if let Some(root) = root() {
+        std::env::set_var("WEBVIEW2_USER_DATA_FOLDER", root.join("webview"));
+    }
+}
+
+#[tauri::command]
+pub fn get_portable_mode() -> bool {
+    root().is_some()
+}
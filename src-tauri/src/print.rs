@@ -0,0 +1,104 @@
+//! Printing generated reports through the OS print pipeline: CUPS `lp` on Linux and macOS, the
+//! registered PDF handler's `PrintTo` verb on Windows.
+
+use crate::jobs::JobQueue;
+use serde::Serialize;
+use std::{path::PathBuf, process::Command};
+use tauri::State;
+
+#[derive(Serialize)]
+pub struct Printer {
+    name: String,
+    default: bool,
+}
+
+fn run(cmd: &mut Command) -> Result<String, String> {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let out = cmd.output().map_err(|e| format!("{program}: {e}"))?;
+    if !out.status.success() {
+        return Err(format!("{program}: {}", String::from_utf8_lossy(&out.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+fn powershell(script: &str) -> Result<String, String> {
+    run(Command::new("powershell").args(["-NoProfile", "-NonInteractive", "-Command", script]))
+}
+
+/// Single-quoted PowerShell literal.
+fn ps_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+#[tauri::command(async)]
+pub fn list_printers() -> Result<Vec<Printer>, String> {
+    if cfg!(windows) {
+        let out = powershell("Get-CimInstance Win32_Printer | ForEach-Object { \"$($_.Name)`t$($_.Default)\" }")?;
+        return Ok(out.lines().filter_map(|l| l.split_once('\t')).map(|(name, default)| Printer { name: name.to_string(), default: default.eq_ignore_ascii_case("true") }).collect());
+    }
+    let default = run(Command::new("lpstat").arg("-d")).ok().and_then(|s| s.rsplit_once(": ").map(|(_, name)| name.trim().to_string()));
+    Ok(run(Command::new("lpstat").arg("-e"))?.lines().map(|name| Printer { name: name.to_string(), default: default.as_deref() == Some(name) }).collect())
+}
+
+/// Page ranges like `1-3,5`, normalised without spaces.
+fn parse_pages(text: &str) -> Result<String, String> {
+    let err = || format!("invalid page range {text:?}, expected e.g. 1-3,5");
+    let parts: Vec<String> = text.split(',').map(|p| p.trim().replace(' ', "")).collect();
+    for part in &parts {
+        let (from, to) = part.split_once('-').unwrap_or((part, part));
+        let (from, to) = (from.parse::<u32>().map_err(|_| err())?, to.parse::<u32>().map_err(|_| err())?);
+        if from == 0 || to < from {
+            return Err(err());
+        }
+    }
+    Ok(parts.join(","))
+}
+
+/// `target` is a job id (its report), a project folder (`outputs/report.pdf`) or a PDF path.
+fn resolve(target: &str, queue: &JobQueue) -> Result<PathBuf, String> {
+    if let Ok(id) = target.parse::<u64>() {
+        let job = queue.get(id).ok_or_else(|| format!("job {id} not found"))?;
+        let pdf = job.engine.as_ref().and_then(|e| e["outputs"]["pdf"].as_str()).ok_or_else(|| format!("job {id} has no report yet"))?;
+        return Ok(PathBuf::from(pdf));
+    }
+    let path = PathBuf::from(target);
+    let path = if path.is_dir() { path.join("outputs").join("report.pdf") } else { path };
+    if !path.is_file() {
+        return Err(format!("{}: report not found", path.display()));
+    }
+    if !path.extension().is_some_and(|e| e.eq_ignore_ascii_case("pdf")) {
+        return Err(format!("{}: not a PDF", path.display()));
+    }
+    Ok(path)
+}
+
+/// Sends the report to `printer` (the system default if `None`); returns the spooler's answer.
+#[tauri::command(async)]
+pub fn print_report(target: String, printer: Option<String>, pages: Option<String>, copies: Option<u32>, queue: State<JobQueue>) -> Result<String, String> {
+    let path = resolve(&target, &queue)?;
+    let pages = pages.filter(|p| !p.trim().is_empty()).map(|p| parse_pages(&p)).transpose()?;
+    let copies = copies.unwrap_or(1).clamp(1, 99);
+    if cfg!(windows) {
+        if pages.is_some() {
+            return Err("page ranges are not supported by the Windows print verb: print the whole report or open it in a PDF viewer".to_string());
+        }
+        let file = ps_quote(&path.display().to_string());
+        let verb = match &printer {
+            Some(name) => format!("-Verb PrintTo -ArgumentList {}", ps_quote(&format!("\"{name}\""))),
+            None => "-Verb Print".to_string(),
+        };
+        for _ in 0..copies {
+            powershell(&format!("Start-Process -FilePath {file} {verb} -WindowStyle Hidden"))?;
+        }
+        return Ok(format!("{} sent to {}", path.display(), printer.as_deref().unwrap_or("the default printer")));
+    }
+    let mut lp = Command::new("lp");
+    if let Some(name) = &printer {
+        lp.args(["-d", name]);
+    }
+    if let Some(pages) = &pages {
+        lp.args(["-P", pages]);
+    }
+    lp.args(["-n", &copies.to_string()]).arg(&path);
+    run(&mut lp)
+}
@@ -0,0 +1,65 @@
+//! Per-stage profiling of runs (`Settings::profile_runs`): the engine measures wall time and
+//! resident memory for DEM load, raycasting, aggregation and export, the run history keeps them
+//! and `profile_breakdown` turns them into shares of the total.
+
+use crate::{eta, jobs::JobQueue};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+/// One engine stage as reported in the job snapshot's `profile`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StageTiming {
+    pub stage: String,
+    pub seconds: f64,
+    /// Resident memory at the end of the stage and its peak during it.
+    pub rss_mb: f64,
+    pub peak_mb: f64,
+}
+
+#[derive(Serialize)]
+pub struct StageShare {
+    #[serde(flatten)]
+    timing: StageTiming,
+    share_pct: f64,
+}
+
+#[derive(Serialize)]
+pub struct ProfileBreakdown {
+    /// Queue job the profile comes from, `None` for the latest profiled run in the history.
+    job: Option<u64>,
+    finished_at: Option<DateTime<Local>>,
+    /// Grid cells times shadow sources, as in the run history.
+    work: Option<f64>,
+    total_s: f64,
+    peak_mb: f64,
+    stages: Vec<StageShare>,
+}
+
+pub fn from_snapshot(state: &Value) -> Option<Vec<StageTiming>> {
+    serde_json::from_value(state["profile"].clone()).ok().filter(|p: &Vec<StageTiming>| !p.is_empty())
+}
+
+fn breakdown(job: Option<u64>, finished_at: Option<DateTime<Local>>, work: Option<f64>, timings: Vec<StageTiming>) -> ProfileBreakdown {
+    let total_s: f64 = timings.iter().map(|t| t.seconds).sum();
+    let peak_mb = timings.iter().map(|t| t.peak_mb).fold(0.0, f64::max);
+    let stages = timings.into_iter().map(|timing| StageShare { share_pct: if total_s > 0.0 { timing.seconds / total_s * 100.0 } else { 0.0 }, timing }).collect();
+    ProfileBreakdown { job, finished_at, work, total_s, peak_mb, stages }
+}
+
+/// Stage breakdown of job `job`, or of the latest profiled run when `None`.
+#[tauri::command]
+pub fn profile_breakdown(job: Option<u64>, app: AppHandle, queue: State<JobQueue>) -> Result<ProfileBreakdown, String> {
+    match job {
+        Some(id) => {
+            let j = queue.get(id).ok_or_else(|| format!("job {id} not found"))?;
+            let timings = j.engine.as_ref().and_then(from_snapshot).ok_or_else(|| format!("job {id} has no profile; enable run profiling in the settings and run it again"))?;
+            Ok(breakdown(Some(id), j.finished_at, None, timings))
+        }
+        None => {
+            let (finished_at, work, timings) = eta::latest_profile(&app).ok_or("no profiled run yet; enable run profiling in the settings")?;
+            Ok(breakdown(None, Some(finished_at), Some(work), timings))
+        }
+    }
+}
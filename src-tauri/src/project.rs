@@ -0,0 +1,153 @@
+//! Saving `project.wssproj.json` without clobbering concurrent edits: the file's hash is
+//! remembered when a project is loaded or saved, and a save finding a different file on disk
+//! (a colleague on the same network share) returns a conflict for the user to resolve.
+
+use crate::{audit, recent, viewer::ViewerMode, ProjectConfig};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::{AppHandle, Manager, State};
+
+pub const PROJECT_FILE: &str = "project.wssproj.json";
+
+/// Hash of the project file as last loaded or saved, `None` when there was no file.
+#[derive(Default)]
+pub struct ProjectState {
+    loaded: Mutex<HashMap<PathBuf, Option<String>>>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SaveOutcome {
+    Saved { path: String },
+    /// The file changed on disk since it was loaded; call again with a resolution.
+    Conflict { path: String, modified: Option<String>, options: [&'static str; 3] },
+    /// `reload`: the disk version, which replaces the unsaved edits.
+    Reloaded { cfg: Box<ProjectConfig> },
+    /// `save_as`: the config now points at the new folder.
+    SavedAs { cfg: Box<ProjectConfig> },
+}
+
+fn file_hash(folder: &Path) -> Option<String> {
+    fs::read(folder.join(PROJECT_FILE)).ok().map(|data| hex::encode(Sha256::digest(data)))
+}
+
+/// Reads the project file of `folder`, with `project_path` set to the folder and relative input
+/// and result paths (as in an imported archive) resolved against it.
+pub fn read(folder: &Path) -> Result<ProjectConfig, String> {
+    let path = folder.join(PROJECT_FILE);
+    let mut cfg: ProjectConfig = serde_json::from_str(&fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?).map_err(|e| format!("{}: {e}", path.display()))?;
+    resolve_paths(&mut cfg, folder);
+    Ok(cfg)
+}
+
+/// Makes the raster paths of `cfg` absolute under `folder` and points it at the folder.
+pub fn resolve_paths(cfg: &mut ProjectConfig, folder: &Path) {
+    let absolute = |p: &mut String| {
+        if !p.is_empty() && Path::new(p.as_str()).is_relative() {
+            *p = folder.join(p.as_str()).display().to_string();
+        }
+    };
+    for p in [&mut cfg.dem_path, &mut cfg.dsm_path, &mut cfg.landcover_path, &mut cfg.buildings_path] {
+        absolute(p);
+    }
+    cfg.external_results.iter_mut().for_each(|r| absolute(&mut r.path));
+    cfg.project_path = folder.display().to_string();
+}
+
+/// Remembers the file as loaded; a new project expects no file, so saving over an existing one conflicts.
+pub fn loaded(app: &AppHandle, project_path: &str, from_disk: bool) {
+    let folder = PathBuf::from(project_path);
+    let hash = if from_disk { file_hash(&folder) } else { None };
+    if let Ok(mut loaded) = app.state::<ProjectState>().loaded.lock() {
+        loaded.insert(folder, hash);
+    }
+}
+
+/// Written next to the target and renamed, so readers on the share never see a half-written file.
+fn write(folder: &Path, cfg: &ProjectConfig) -> Result<(), String> {
+    let path = folder.join(PROJECT_FILE);
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(cfg).map_err(|e| e.to_string())?).map_err(|e| format!("{}: {e}", tmp.display()))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn saved(app: &AppHandle, folder: &Path, cfg: &ProjectConfig) -> Result<(), String> {
+    write(folder, cfg)?;
+    loaded(app, &folder.display().to_string(), true);
+    audit::record(app, cfg, "project_saved", serde_json::json!({}));
+    Ok(())
+}
+
+/// `resolution` is `None` for a normal save, or `overwrite`, `reload` or `save_as` after a conflict.
+#[tauri::command]
+pub fn save_project(cfg: ProjectConfig, resolution: Option<String>, app: AppHandle, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Option<SaveOutcome>, String> {
+    viewer.check(window.label())?;
+    let folder = PathBuf::from(&cfg.project_path);
+    let path = folder.join(PROJECT_FILE);
+    match resolution.as_deref() {
+        None => {
+            let expected = app.state::<ProjectState>().loaded.lock().map_err(|e| e.to_string())?.get(&folder).cloned().flatten();
+            if file_hash(&folder) != expected {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok().map(|t| chrono::DateTime::<chrono::Local>::from(t).to_rfc3339());
+                return Ok(Some(SaveOutcome::Conflict { path: path.display().to_string(), modified, options: ["overwrite", "reload", "save_as"] }));
+            }
+            saved(&app, &folder, &cfg)?;
+        }
+        Some("overwrite") => saved(&app, &folder, &cfg)?,
+        Some("reload") => {
+            let cfg = read(&folder)?;
+            loaded(&app, &cfg.project_path, true);
+            return Ok(Some(SaveOutcome::Reloaded { cfg: Box::new(cfg) }));
+        }
+        Some("save_as") => {
+            let Some(dest) = rfd::FileDialog::new().pick_folder() else {
+                return Ok(None);
+            };
+            if dest.join(PROJECT_FILE).exists() {
+                return Err(format!("{} already contains a project", dest.display()));
+            }
+            let mut cfg = cfg;
+            cfg.project_path = dest.display().to_string();
+            saved(&app, &dest, &cfg)?;
+            recent::remember(&app, &cfg.project_path);
+            return Ok(Some(SaveOutcome::SavedAs { cfg: Box::new(cfg) }));
+        }
+        Some(other) => return Err(format!("unknown save resolution: {other}")),
+    }
+    Ok(Some(SaveOutcome::Saved { path: path.display().to_string() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_inputs_resolve_against_the_project_folder() {
+        let folder = std::env::temp_dir().join(format!("wss-project-read-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let cfg = serde_json::json!({
+            "project_path": "",
+            "epsg": "EPSG:32632",
+            "cellsize_m": 10.0,
+            "buffer_m": 2000.0,
+            "terrain_aware": true,
+            "dem_path": "inputs/dem.tif",
+            "dsm_path": "inputs/dsm.tif",
+            "turbines": [],
+            "output": { "format": "asc" },
+        });
+        fs::write(folder.join(PROJECT_FILE), cfg.to_string()).unwrap();
+
+        let cfg = read(&folder).unwrap();
+        assert_eq!(PathBuf::from(&cfg.dem_path), folder.join("inputs/dem.tif"));
+        assert_eq!(PathBuf::from(&cfg.dsm_path), folder.join("inputs/dsm.tif"));
+        assert!(cfg.landcover_path.is_empty());
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
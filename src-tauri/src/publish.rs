@@ -0,0 +1,208 @@
+//! Uploads study outputs plus a `manifest.json` to a delivery target configured in the
+//! settings: an HTTP endpoint (PUT), an SFTP server (system `sftp` client) or an S3 bucket.
+
+use crate::{audit, settings, viewer::ViewerMode, ProjectConfig};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+use tauri::{AppHandle, State};
+
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Serialize)]
+pub struct PublishReport {
+    target: String,
+    /// Remote folder (or key prefix) holding this delivery.
+    location: String,
+    files: usize,
+    bytes: u64,
+}
+
+struct Upload {
+    local: PathBuf,
+    /// Forward-slash path relative to the delivery folder.
+    remote: String,
+}
+
+/// `files` are relative to the project folder; by default everything under `outputs/` and the
+/// project file are delivered. Absolute paths, `..` and links leading out of the folder are refused.
+fn collect(project: &Path, files: Option<Vec<String>>) -> Result<Vec<Upload>, String> {
+    let root = project.canonicalize().map_err(|e| format!("{}: {e}", project.display()))?;
+    let paths: Vec<PathBuf> = match files {
+        Some(files) => files
+            .iter()
+            .map(|f| {
+                let rel = Path::new(f);
+                if rel.is_absolute() || rel.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+                    return Err(format!("{f} is outside the project folder"));
+                }
+                Ok(project.join(rel))
+            })
+            .collect::<Result<_, String>>()?,
+        None => walkdir::WalkDir::new(project.join("outputs")).into_iter().filter_map(Result::ok).filter(|e| e.file_type().is_file()).map(|e| e.into_path()).chain([project.join("project.wssproj.json")]).filter(|p| p.exists()).collect(),
+    };
+    paths
+        .into_iter()
+        .map(|local| {
+            if !local.is_file() {
+                return Err(format!("{} not found", local.display()));
+            }
+            let local = local.canonicalize().map_err(|e| format!("{}: {e}", local.display()))?;
+            let rel = local.strip_prefix(&root).map_err(|_| format!("{} is outside the project folder", local.display()))?;
+            let remote = rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            Ok(Upload { local, remote })
+        })
+        .collect()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn manifest(cfg: &ProjectConfig, target: &str, uploads: &[Upload]) -> Result<Vec<u8>, String> {
+    let files = uploads
+        .iter()
+        .map(|u| {
+            let data = fs::read(&u.local).map_err(|e| format!("{}: {e}", u.local.display()))?;
+            Ok(json!({ "path": u.remote, "size": data.len(), "sha256": sha256_hex(&data) }))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let project = Path::new(&cfg.project_path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    serde_json::to_vec_pretty(&json!({
+        "project": project,
+        "epsg": cfg.epsg,
+        "turbines": cfg.turbines.len(),
+        "published_at": chrono::Local::now().to_rfc3339(),
+        "target": target,
+        "files": files,
+    }))
+    .map_err(|e| e.to_string())
+}
+
+fn put_http(target: &settings::PublishTarget, key: &str, data: &[u8]) -> Result<(), String> {
+    let mut req = ureq::put(&format!("{}/{key}", target.url.trim_end_matches('/'))).timeout(UPLOAD_TIMEOUT);
+    if !target.token.is_empty() {
+        req = req.set("Authorization", &format!("Bearer {}", target.token));
+    }
+    req.send_bytes(data).map(|_| ()).map_err(|e| format!("{key}: {e}"))
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// AWS signature V4 signing key for `date` (`YYYYMMDD`).
+fn s3_signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k = hmac(format!("AWS4{secret}").as_bytes(), date);
+    let k = hmac(&k, region);
+    let k = hmac(&k, service);
+    hmac(&k, "aws4_request")
+}
+
+fn uri_encode(segment: &str) -> String {
+    segment.bytes().map(|b| if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) { (b as char).to_string() } else { format!("%{b:02X}") }).collect()
+}
+
+/// Path-style PUT (`{endpoint}/{bucket}/{key}`), which also works with MinIO and other S3-compatible stores.
+fn put_s3(target: &settings::PublishTarget, key: &str, data: &[u8]) -> Result<(), String> {
+    let endpoint = target.url.trim_end_matches('/');
+    let host = endpoint.split("://").nth(1).unwrap_or(endpoint).split('/').next().unwrap_or_default();
+    let path = format!("/{}/{}", uri_encode(&target.bucket), key.split('/').map(uri_encode).collect::<Vec<_>>().join("/"));
+    let now = chrono::Utc::now();
+    let (amz_date, date) = (now.format("%Y%m%dT%H%M%SZ").to_string(), now.format("%Y%m%d").to_string());
+    let payload_hash = sha256_hex(data);
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical = format!("PUT\n{path}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{signed_headers}\n{payload_hash}");
+    let scope = format!("{date}/{}/s3/aws4_request", target.region);
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}", sha256_hex(canonical.as_bytes()));
+    let signature = hex::encode(hmac(&s3_signing_key(&target.secret_key, &date, &target.region, "s3"), &string_to_sign));
+    let authorization = format!("AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}", target.access_key);
+    ureq::put(&format!("{endpoint}{path}"))
+        .timeout(UPLOAD_TIMEOUT)
+        .set("x-amz-date", &amz_date)
+        .set("x-amz-content-sha256", &payload_hash)
+        .set("Authorization", &authorization)
+        .send_bytes(data)
+        .map(|_| ())
+        .map_err(|e| format!("{key}: {e}"))
+}
+
+/// Runs the system `sftp` client in batch mode, so SSH keys and `known_hosts` are the user's own.
+/// `url` is `user@host:/remote/dir`.
+fn put_sftp(target: &settings::PublishTarget, prefix: &str, uploads: &[Upload], manifest: &Path) -> Result<(), String> {
+    let (host, dir) = target.url.split_once(':').ok_or("SFTP target must look like user@host:/remote/dir")?;
+    let root = format!("{}/{prefix}", dir.trim_end_matches('/'));
+    let files: Vec<(&Path, String)> = uploads.iter().map(|u| (u.local.as_path(), format!("{root}/{}", u.remote))).chain([(manifest, format!("{root}/manifest.json"))]).collect();
+    // `-mkdir` tolerates existing folders; every ancestor is created in order.
+    let mut dirs: Vec<String> = vec![];
+    for (_, remote) in &files {
+        let parent = remote.rsplit_once('/').map_or("", |(d, _)| d);
+        let mut acc = if parent.starts_with('/') { "/".to_string() } else { String::new() };
+        for part in parent.split('/').filter(|p| !p.is_empty()) {
+            acc = if acc.is_empty() || acc == "/" { format!("{acc}{part}") } else { format!("{acc}/{part}") };
+            if !dirs.contains(&acc) {
+                dirs.push(acc.clone());
+            }
+        }
+    }
+    let mut script: String = dirs.iter().map(|d| format!("-mkdir \"{d}\"\n")).collect();
+    for (local, remote) in &files {
+        script.push_str(&format!("put \"{}\" \"{remote}\"\n", local.display()));
+    }
+    let batch = std::env::temp_dir().join(format!("wss-sftp-{}.txt", std::process::id()));
+    fs::write(&batch, script).map_err(|e| e.to_string())?;
+    let out = Command::new("sftp").arg("-b").arg(&batch).args(["-o", "BatchMode=yes", host]).output();
+    let _ = fs::remove_file(&batch);
+    let out = out.map_err(|e| format!("sftp: {e}"))?;
+    if !out.status.success() {
+        return Err(format!("sftp: {}", String::from_utf8_lossy(&out.stderr).trim()));
+    }
+    Ok(())
+}
+
+/// Publishes to the target named `target` in the settings.
+#[tauri::command(async)]
+pub fn publish_results(app: AppHandle, cfg: ProjectConfig, target: String, files: Option<Vec<String>>, window: tauri::Window, viewer: State<ViewerMode>) -> Result<PublishReport, String> {
+    viewer.check(window.label())?;
+    let target = settings::load(&app).publish_targets.into_iter().find(|t| t.name == target).ok_or_else(|| format!("publish target {target} not configured"))?;
+    let project = PathBuf::from(&cfg.project_path);
+    let uploads = collect(&project, files)?;
+    if uploads.is_empty() {
+        return Err("nothing to publish: run the calculation first".to_string());
+    }
+    let manifest = manifest(&cfg, &target.name, &uploads)?;
+    let folder = project.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or("project".to_string());
+    let prefix = format!("{folder}/{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let bytes = uploads.iter().filter_map(|u| fs::metadata(&u.local).ok()).map(|m| m.len()).sum::<u64>();
+    match target.kind.as_str() {
+        "http" | "s3" => {
+            let put = if target.kind == "s3" { put_s3 } else { put_http };
+            for u in &uploads {
+                let data = fs::read(&u.local).map_err(|e| format!("{}: {e}", u.local.display()))?;
+                put(&target, &format!("{prefix}/{}", u.remote), &data)?;
+            }
+            // Manifest last: its presence tells the receiving side the delivery is complete.
+            put(&target, &format!("{prefix}/manifest.json"), &manifest)?;
+        }
+        "sftp" => {
+            let local_manifest = std::env::temp_dir().join(format!("wss-manifest-{}.json", std::process::id()));
+            fs::write(&local_manifest, &manifest).map_err(|e| e.to_string())?;
+            let sent = put_sftp(&target, &prefix, &uploads, &local_manifest);
+            let _ = fs::remove_file(&local_manifest);
+            sent?;
+        }
+        kind => return Err(format!("unknown publish target kind: {kind}")),
+    }
+    let report = PublishReport { target: target.name, location: prefix, files: uploads.len(), bytes };
+    audit::record(&app, &cfg, "results_published", json!(report));
+    Ok(report)
+}
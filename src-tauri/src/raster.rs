@@ -0,0 +1,183 @@
+//! Minimal single-band raster access for the shell: ESRI ASCII grids (what the engine writes)
+//! and GeoTIFFs georeferenced with tie point + pixel scale (what DEM providers and windPRO write).
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+use tiff::{
+    decoder::{Decoder, DecodingResult},
+    tags::Tag,
+};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GridHeader {
+    pub ncols: usize,
+    pub nrows: usize,
+    /// Lower-left corner of the lower-left cell.
+    pub xll: f64,
+    pub yll: f64,
+    pub cellsize: f64,
+    pub nodata: Option<f64>,
+    pub epsg: Option<String>,
+}
+
+pub const ASC_NODATA: f64 = -9999.0;
+
+impl GridHeader {
+    /// True when both headers describe the same cells, so values can be combined index by index.
+    pub fn aligned_with(&self, other: &GridHeader) -> bool {
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-6 * self.cellsize.max(1.0);
+        self.ncols == other.ncols && self.nrows == other.nrows && close(self.xll, other.xll) && close(self.yll, other.yll) && close(self.cellsize, other.cellsize)
+    }
+}
+
+/// Cell values row-major from the top (north) row, as in the ASC body.
+pub struct Grid {
+    pub header: GridHeader,
+    pub data: Vec<f64>,
+}
+
+impl Grid {
+    pub fn is_nodata(&self, v: f64) -> bool {
+        v.is_nan() || self.header.nodata == Some(v)
+    }
+
+    /// Value of the cell containing (x, y), `None` outside the grid or on nodata.
+    pub fn sample(&self, x: f64, y: f64) -> Option<f64> {
+        let h = &self.header;
+        let col = ((x - h.xll) / h.cellsize).floor();
+        let row_from_bottom = ((y - h.yll) / h.cellsize).floor();
+        if col < 0.0 || row_from_bottom < 0.0 || col >= h.ncols as f64 || row_from_bottom >= h.nrows as f64 {
+            return None;
+        }
+        let v = self.data[(h.nrows - 1 - row_from_bottom as usize) * h.ncols + col as usize];
+        (!self.is_nodata(v)).then_some(v)
+    }
+
+    /// Min/max over valid cells, `None` if the grid is all nodata.
+    pub fn value_range(&self) -> Option<(f64, f64)> {
+        self.data.iter().filter(|v| !self.is_nodata(**v)).fold(None, |acc, &v| match acc {
+            None => Some((v, v)),
+            Some((lo, hi)) => Some((lo.min(v), hi.max(v))),
+        })
+    }
+}
+
+/// Writes an ESRI ASCII grid; NaN cells become `NODATA_value`.
+pub fn write_asc(path: &Path, header: &GridHeader, data: &[f64]) -> std::io::Result<()> {
+    let mut f = BufWriter::new(fs::File::create(path)?);
+    writeln!(f, "ncols {}", header.ncols)?;
+    writeln!(f, "nrows {}", header.nrows)?;
+    writeln!(f, "xllcorner {}", header.xll)?;
+    writeln!(f, "yllcorner {}", header.yll)?;
+    writeln!(f, "cellsize {}", header.cellsize)?;
+    writeln!(f, "NODATA_value {ASC_NODATA}")?;
+    for row in data.chunks(header.ncols.max(1)) {
+        let line: Vec<String> = row.iter().map(|v| if v.is_nan() { ASC_NODATA.to_string() } else { format!("{v:.4}") }).collect();
+        writeln!(f, "{}", line.join(" "))?;
+    }
+    f.flush()
+}
+
+fn is_tiff(path: &Path) -> bool {
+    matches!(path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref(), Some("tif" | "tiff"))
+}
+
+pub fn read(path: &Path) -> Result<Grid, String> {
+    if is_tiff(path) {
+        read_tiff(path)
+    } else {
+        read_asc(path)
+    }
+}
+
+fn read_asc(path: &Path) -> Result<Grid, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut lines = text.lines().peekable();
+    let (mut ncols, mut nrows, mut cellsize, mut nodata) = (None, None, None, None);
+    let (mut xll, mut yll, mut centered) = (None, None, false);
+    while let Some(line) = lines.peek() {
+        let mut parts = line.split_whitespace();
+        let (Some(key), Some(value)) = (parts.next(), parts.next()) else { break };
+        if key.parse::<f64>().is_ok() {
+            break;
+        }
+        let value: f64 = value.parse().map_err(|_| format!("invalid ASC header line: {line}"))?;
+        match key.to_lowercase().as_str() {
+            "ncols" => ncols = Some(value as usize),
+            "nrows" => nrows = Some(value as usize),
+            "xllcorner" => xll = Some(value),
+            "yllcorner" => yll = Some(value),
+            "xllcenter" => (xll, centered) = (Some(value), true),
+            "yllcenter" => (yll, centered) = (Some(value), true),
+            "cellsize" => cellsize = Some(value),
+            "nodata_value" => nodata = Some(value),
+            _ => return Err(format!("unknown ASC header key: {key}")),
+        }
+        lines.next();
+    }
+    let (Some(ncols), Some(nrows), Some(mut xll), Some(mut yll), Some(cellsize)) = (ncols, nrows, xll, yll, cellsize) else {
+        return Err("incomplete ASC header".to_string());
+    };
+    if centered {
+        xll -= cellsize / 2.0;
+        yll -= cellsize / 2.0;
+    }
+    let data = lines
+        .flat_map(|l| l.split_whitespace())
+        .map(|v| v.parse::<f64>().map_err(|_| format!("invalid ASC value: {v}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    if data.len() != ncols * nrows {
+        return Err(format!("ASC body has {} values, header says {}x{}", data.len(), ncols, nrows));
+    }
+    Ok(Grid { header: GridHeader { ncols, nrows, xll, yll, cellsize, nodata, epsg: None }, data })
+}
+
+/// EPSG code from the GeoKeyDirectory: ProjectedCSTypeGeoKey (3072), else GeographicTypeGeoKey (2048).
+fn tiff_epsg<R: std::io::Read + std::io::Seek>(dec: &mut Decoder<R>) -> Option<String> {
+    let keys = dec.get_tag_u16_vec(Tag::GeoKeyDirectoryTag).ok()?;
+    let entries: Vec<&[u16]> = keys.get(4..)?.chunks(4).filter(|e| e.len() == 4 && e[1] == 0).collect();
+    [3072, 2048]
+        .iter()
+        .find_map(|id| entries.iter().find(|e| e[0] == *id).map(|e| e[3]))
+        .filter(|code| *code != 0 && *code != 32767)
+        .map(|code| format!("EPSG:{code}"))
+}
+
+fn read_tiff(path: &Path) -> Result<Grid, String> {
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut dec = Decoder::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let (width, height) = dec.dimensions().map_err(|e| e.to_string())?;
+    let scale = dec.get_tag_f64_vec(Tag::ModelPixelScaleTag).map_err(|_| "GeoTIFF without ModelPixelScale tag".to_string())?;
+    let tie = dec.get_tag_f64_vec(Tag::ModelTiepointTag).map_err(|_| "GeoTIFF without ModelTiepoint tag".to_string())?;
+    if scale.len() < 2 || tie.len() < 6 {
+        return Err("malformed GeoTIFF georeferencing tags".to_string());
+    }
+    // Tie point maps raster (i, j) to model (x, y); j grows southwards.
+    let cellsize = scale[0];
+    let left = tie[3] - tie[0] * scale[0];
+    let top = tie[4] + tie[1] * scale[1];
+    let nodata = dec.get_tag_ascii_string(Tag::GdalNodata).ok().and_then(|s| s.trim_matches(char::from(0)).trim().parse().ok());
+    let epsg = tiff_epsg(&mut dec);
+    let data: Vec<f64> = match dec.read_image().map_err(|e| e.to_string())? {
+        DecodingResult::U8(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::U16(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::U32(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::U64(v) => v.into_iter().map(|x| x as f64).collect(),
+        DecodingResult::F32(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::F64(v) => v,
+        DecodingResult::I8(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::I16(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::I32(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::I64(v) => v.into_iter().map(|x| x as f64).collect(),
+    };
+    let (ncols, nrows) = (width as usize, height as usize);
+    if data.len() != ncols * nrows {
+        return Err("only single-band GeoTIFFs are supported".to_string());
+    }
+    let yll = top - nrows as f64 * scale[1];
+    Ok(Grid { header: GridHeader { ncols, nrows, xll: left, yll, cellsize, nodata, epsg }, data })
+}
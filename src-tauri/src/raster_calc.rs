@@ -0,0 +1,285 @@
+//! Per-cell raster algebra over aligned project rasters.
+//!
+//! Expressions use the input names as variables, e.g. `a + b` (cumulative layer),
+//! `max(a, b)`, `a - b` (difference) or `sum(a, b, c) > 30` (threshold mask, 1/0).
+//! A cell that is nodata in any input is nodata in the output.
+
+use crate::{raster, results::ExternalResult, viewer::ViewerMode};
+use std::{collections::HashMap, path::PathBuf};
+use tauri::State;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+enum Expr {
+    Num(f64),
+    Var(usize),
+    Neg(Box<Expr>),
+    Bin(&'static str, Box<Expr>, Box<Expr>),
+    Call(&'static str, Vec<Expr>),
+}
+
+const FUNCTIONS: [&str; 4] = ["sum", "max", "min", "abs"];
+const OPERATORS: [&str; 10] = [">=", "<=", "==", "!=", ">", "<", "+", "-", "*", "/"];
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let mut out = vec![];
+    let chars: Vec<char> = src.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let lit: String = chars[start..i].iter().collect();
+            out.push(Token::Num(lit.parse().map_err(|_| format!("invalid number: {lit}"))?));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            out.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '(' || c == ')' || c == ',' {
+            out.push(match c {
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                _ => Token::Comma,
+            });
+            i += 1;
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let op = OPERATORS.iter().find(|op| rest.starts_with(**op)).ok_or_else(|| format!("unexpected character: {c}"))?;
+            out.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(out)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    names: &'a [String],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn expect(&mut self, t: Token) -> Result<(), String> {
+        match self.next() {
+            Some(got) if got == t => Ok(()),
+            got => Err(format!("expected {t:?}, found {got:?}")),
+        }
+    }
+
+    fn peek_op(&self, ops: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let lhs = self.additive()?;
+        match self.peek_op(&OPERATORS[..6]) {
+            Some(op) => {
+                self.pos += 1;
+                Ok(Expr::Bin(op, Box::new(lhs), Box::new(self.additive()?)))
+            }
+            None => Ok(lhs),
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        while let Some(op) = self.peek_op(&["+", "-"]) {
+            self.pos += 1;
+            lhs = Expr::Bin(op, Box::new(lhs), Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        while let Some(op) = self.peek_op(&["*", "/"]) {
+            self.pos += 1;
+            lhs = Expr::Bin(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek_op(&["-"]).is_some() {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(v)) => Ok(Expr::Num(v)),
+            Some(Token::LParen) => {
+                let e = self.comparison()?;
+                self.expect(Token::RParen)?;
+                Ok(e)
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                let func = FUNCTIONS.iter().find(|f| **f == name).ok_or_else(|| format!("unknown function: {name}"))?;
+                self.pos += 1;
+                let mut args = vec![self.comparison()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    args.push(self.comparison()?);
+                }
+                self.expect(Token::RParen)?;
+                if *func == "abs" && args.len() != 1 {
+                    return Err("abs takes exactly one argument".to_string());
+                }
+                Ok(Expr::Call(func, args))
+            }
+            Some(Token::Ident(name)) => {
+                self.names.iter().position(|n| *n == name).map(Expr::Var).ok_or_else(|| format!("unknown input: {name}"))
+            }
+            t => Err(format!("unexpected token: {t:?}")),
+        }
+    }
+}
+
+fn parse(src: &str, names: &[String]) -> Result<Expr, String> {
+    let mut p = Parser { tokens: tokenize(src)?, pos: 0, names };
+    let e = p.comparison()?;
+    match p.peek() {
+        None => Ok(e),
+        Some(t) => Err(format!("unexpected trailing token: {t:?}")),
+    }
+}
+
+fn eval(e: &Expr, cell: &[f64]) -> f64 {
+    let flag = |b: bool| if b { 1.0 } else { 0.0 };
+    match e {
+        Expr::Num(v) => *v,
+        Expr::Var(i) => cell[*i],
+        Expr::Neg(a) => -eval(a, cell),
+        Expr::Bin(op, a, b) => {
+            let (a, b) = (eval(a, cell), eval(b, cell));
+            match *op {
+                "+" => a + b,
+                "-" => a - b,
+                "*" => a * b,
+                "/" if b == 0.0 => f64::NAN,
+                "/" => a / b,
+                ">" => flag(a > b),
+                ">=" => flag(a >= b),
+                "<" => flag(a < b),
+                "<=" => flag(a <= b),
+                "==" => flag(a == b),
+                _ => flag(a != b),
+            }
+        }
+        Expr::Call(func, args) => {
+            let vals = args.iter().map(|a| eval(a, cell));
+            match *func {
+                "sum" => vals.sum(),
+                "max" => vals.fold(f64::NEG_INFINITY, f64::max),
+                "min" => vals.fold(f64::INFINITY, f64::min),
+                _ => eval(&args[0], cell).abs(),
+            }
+        }
+    }
+}
+
+/// Evaluates `expression` over the named rasters, returning the common header and the result cells
+/// (NaN where any input is nodata).
+pub fn evaluate(expression: &str, inputs: &HashMap<String, String>) -> Result<(raster::GridHeader, Vec<f64>), String> {
+    if inputs.is_empty() {
+        return Err("raster_calc needs at least one input".to_string());
+    }
+    let mut names: Vec<String> = inputs.keys().cloned().collect();
+    names.sort();
+    let grids = names.iter().map(|n| raster::read(&PathBuf::from(&inputs[n])).map_err(|e| format!("{n}: {e}"))).collect::<Result<Vec<_>, _>>()?;
+    let header = grids[0].header.clone();
+    if let Some((name, _)) = names.iter().zip(&grids).find(|(_, g)| !g.header.aligned_with(&header)) {
+        return Err(format!("{name} is not aligned with {} (extent and cell size must match)", names[0]));
+    }
+    let expr = parse(expression, &names)?;
+    let mut cell = vec![0.0; grids.len()];
+    let data = (0..header.ncols * header.nrows)
+        .map(|i| {
+            for (slot, g) in cell.iter_mut().zip(&grids) {
+                *slot = g.data[i];
+            }
+            if grids.iter().zip(&cell).any(|(g, v)| g.is_nodata(*v)) {
+                return f64::NAN;
+            }
+            eval(&expr, &cell)
+        })
+        .collect();
+    Ok((header, data))
+}
+
+#[tauri::command]
+pub fn raster_calc(expression: String, inputs: HashMap<String, String>, output: String, window: tauri::Window, viewer: State<ViewerMode>) -> Result<ExternalResult, String> {
+    viewer.check(window.label())?;
+    let (mut header, data) = evaluate(&expression, &inputs)?;
+    let path = PathBuf::from(&output);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    header.nodata = Some(raster::ASC_NODATA);
+    let grid = raster::Grid { header, data };
+    raster::write_asc(&path, &grid.header, &grid.data).map_err(|e| e.to_string())?;
+    let range = grid.value_range();
+    let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    Ok(ExternalResult { name, source: format!("raster_calc: {expression}"), path: output, grid: grid.header, min: range.map(|r| r.0), max: range.map(|r| r.1) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn asc(dir: &std::path::Path, name: &str, body: &str) -> String {
+        let path = dir.join(format!("{name}.asc"));
+        fs::write(&path, format!("ncols 2\nnrows 2\nxllcorner 0\nyllcorner 0\ncellsize 10\nNODATA_value -9999\n{body}\n")).unwrap();
+        path.display().to_string()
+    }
+
+    #[test]
+    fn expression_over_two_rasters_keeps_nodata() {
+        let dir = std::env::temp_dir().join(format!("wss-raster-calc-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let inputs = HashMap::from([("a".to_string(), asc(&dir, "a", "10 20\n-9999 40")), ("b".to_string(), asc(&dir, "b", "1 25\n3 -9999"))]);
+
+        let (header, diff) = evaluate("a - b", &inputs).unwrap();
+        assert_eq!((header.ncols, header.nrows), (2, 2));
+        assert_eq!(&diff[..2], &[9.0, -5.0]);
+        assert!(diff[2].is_nan() && diff[3].is_nan());
+
+        let (_, mask) = evaluate("max(a, b) / 2 >= 10", &inputs).unwrap();
+        assert_eq!(&mask[..2], &[0.0, 1.0]);
+        assert!(mask[2].is_nan());
+
+        assert!(evaluate("a + c", &inputs).is_err_and(|e| e.contains("unknown input: c")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+//! Recently opened projects and the scenario last active in each, kept in `recent.json` next to
+//! the settings. With `reopen_last_project` set, the newest one is opened again at launch.
+
+use crate::{project, settings, viewer::ViewerMode, ProjectConfig};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::PathBuf};
+use tauri::{AppHandle, State};
+
+const MAX_RECENT: usize = 10;
+
+#[derive(Default, Serialize, Deserialize)]
+struct Recent {
+    /// Newest first.
+    projects: Vec<String>,
+    #[serde(default)]
+    active_scenario: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+pub struct ReopenedProject {
+    cfg: Box<ProjectConfig>,
+    active_scenario: Option<String>,
+}
+
+fn recent_path(app: &AppHandle) -> PathBuf {
+    crate::portable::config_dir(app).join("recent.json")
+}
+
+fn load(app: &AppHandle) -> Recent {
+    fs::read_to_string(recent_path(app)).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+fn save(app: &AppHandle, recent: &Recent) -> Result<(), String> {
+    let path = recent_path(app);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, serde_json::to_string_pretty(recent).map_err(|e| e.to_string())?).map_err(|e| e.to_string())
+}
+
+/// Moves `project_path` to the front of the list.
+pub fn remember(app: &AppHandle, project_path: &str) {
+    let mut recent = load(app);
+    recent.projects.retain(|p| p != project_path);
+    recent.projects.insert(0, project_path.to_string());
+    recent.projects.truncate(MAX_RECENT);
+    if let Err(e) = save(app, &recent) {
+        eprintln!("recent projects: {e}");
+    }
+}
+
+/// Recent projects whose folder still holds a project file.
+#[tauri::command]
+pub fn list_recent_projects(app: AppHandle) -> Vec<String> {
+    load(&app).projects.into_iter().filter(|p| PathBuf::from(p).join(project::PROJECT_FILE).is_file()).collect()
+}
+
+#[tauri::command]
+pub fn set_active_scenario(app: AppHandle, project_path: String, scenario: Option<String>) -> Result<(), String> {
+    let mut recent = load(&app);
+    match scenario {
+        Some(name) => recent.active_scenario.insert(project_path, name),
+        None => recent.active_scenario.remove(&project_path),
+    };
+    save(&app, &recent)
+}
+
+/// Called by the window once at startup; `None` when the option is off or no recent project is left.
+#[tauri::command]
+pub fn reopen_last_project(window: tauri::Window, app: AppHandle, viewer: State<ViewerMode>) -> Result<Option<ReopenedProject>, String> {
+    if !settings::load(&app).reopen_last_project {
+        return Ok(None);
+    }
+    let recent = load(&app);
+    let Some(path) = recent.projects.iter().find(|p| PathBuf::from(p).join(project::PROJECT_FILE).is_file()) else {
+        return Ok(None);
+    };
+    let cfg = project::read(&PathBuf::from(path))?;
+    crate::workspace::claim(&app, window.label(), &cfg.project_path)?;
+    crate::project_opened(&app, &viewer, &cfg, "open", window.label());
+    let active_scenario = recent.active_scenario.get(path).filter(|name| cfg.scenarios.iter().any(|s| &s.name == *name)).cloned();
+    Ok(Some(ReopenedProject { cfg: Box::new(cfg), active_scenario }))
+}
@@ -0,0 +1,85 @@
+//! Receptor utilities that work from the project rasters rather than user input.
+
+use crate::{raster, viewer::ViewerMode, ProjectConfig, Receptor};
+use serde::Serialize;
+use std::path::Path;
+use tauri::State;
+
+const DEFAULT_FOOTPRINT_M: f64 = 10.0;
+const STOREY_M: f64 = 3.0;
+/// Window centre above its floor.
+const SILL_M: f64 = 1.5;
+/// Below this the receptor is probably not on a building footprint.
+const MIN_BUILDING_M: f64 = 2.5;
+/// Above this the DSM most likely shows trees, cranes or noise rather than a dwelling.
+const MAX_BUILDING_M: f64 = 60.0;
+
+#[derive(Serialize)]
+pub struct DerivedHeight {
+    id: String,
+    eaves_height_m: Option<f64>,
+    window_height_m: Option<f64>,
+    /// Why the value was not applied; the receptor keeps its previous height.
+    flag: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DerivedHeights {
+    receptors: Vec<Receptor>,
+    report: Vec<DerivedHeight>,
+}
+
+/// Highest DSM-DTM difference within `radius` of (x, y), sampled at the DSM resolution.
+fn footprint_height(dsm: &raster::Grid, dtm: &raster::Grid, x: f64, y: f64, radius: f64) -> Option<f64> {
+    let step = dsm.header.cellsize.min(radius).max(0.5);
+    let n = (radius / step).ceil() as i64;
+    let mut best: Option<f64> = None;
+    for i in -n..=n {
+        for j in -n..=n {
+            let (dx, dy) = (i as f64 * step, j as f64 * step);
+            if dx * dx + dy * dy > radius * radius {
+                continue;
+            }
+            if let (Some(s), Some(t)) = (dsm.sample(x + dx, y + dy), dtm.sample(x + dx, y + dy)) {
+                best = Some(best.map_or(s - t, |b: f64| b.max(s - t)));
+            }
+        }
+    }
+    best
+}
+
+/// Top-floor window height for a building of the given eaves height.
+fn window_height(eaves: f64) -> f64 {
+    let storeys = (eaves / STOREY_M).round().max(1.0);
+    (storeys - 1.0) * STOREY_M + SILL_M
+}
+
+pub fn derive_heights(cfg: &ProjectConfig, footprint_m: f64) -> Result<DerivedHeights, String> {
+    if cfg.dsm_path.is_empty() || cfg.dem_path.is_empty() {
+        return Err("both a DTM (dem_path) and a DSM (dsm_path) are required".to_string());
+    }
+    let dtm = raster::read(Path::new(&cfg.dem_path)).map_err(|e| format!("DTM: {e}"))?;
+    let dsm = raster::read(Path::new(&cfg.dsm_path)).map_err(|e| format!("DSM: {e}"))?;
+    let mut receptors = vec![];
+    let mut report = vec![];
+    for r in &cfg.receptors {
+        let eaves = footprint_height(&dsm, &dtm, r.x, r.y, footprint_m);
+        let flag = match eaves {
+            None => Some("outside the DSM/DTM or nodata".to_string()),
+            Some(h) if h < 0.0 => Some(format!("DSM below DTM ({h:.1} m): check raster alignment")),
+            Some(h) if h < MIN_BUILDING_M => Some(format!("no building found ({h:.1} m): check receptor position")),
+            Some(h) if h > MAX_BUILDING_M => Some(format!("implausible height ({h:.1} m)")),
+            Some(_) => None,
+        };
+        let window = eaves.filter(|_| flag.is_none()).map(window_height);
+        receptors.push(Receptor { id: r.id.clone(), x: r.x, y: r.y, height_m: window.unwrap_or(r.height_m) });
+        report.push(DerivedHeight { id: r.id.clone(), eaves_height_m: eaves, window_height_m: window, flag });
+    }
+    Ok(DerivedHeights { receptors, report })
+}
+
+#[tauri::command]
+pub fn derive_receptor_heights(cfg: ProjectConfig, footprint_m: Option<f64>, window: tauri::Window, viewer: State<ViewerMode>) -> Result<DerivedHeights, String> {
+    viewer.check(window.label())?;
+    derive_heights(&cfg, footprint_m.unwrap_or(DEFAULT_FOOTPRINT_M))
+}
@@ -0,0 +1,172 @@
+//! Result rasters tracked by the project besides the engine's own `outputs/`.
+
+use crate::{raster, viewer::ViewerMode, ProjectConfig, Receptor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::State;
+
+/// A result raster not written by an engine run: either produced by another tool (windPRO,
+/// a previous study) and copied into `outputs/external/` so comparisons keep working when the
+/// original share disappears, or derived from project rasters with `raster_calc`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExternalResult {
+    pub name: String,
+    pub source: String,
+    pub path: String,
+    pub grid: raster::GridHeader,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[tauri::command]
+pub fn register_external_result(cfg: ProjectConfig, source: String, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Option<ExternalResult>, String> {
+    viewer.check(window.label())?;
+    let Some(src) = rfd::FileDialog::new().add_filter("Raster", &["asc", "tif", "tiff"]).pick_file() else {
+        return Ok(None);
+    };
+    let name = src.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    if cfg.external_results.iter().any(|r| r.name == name) {
+        return Err(format!("an external result named {name} is already registered"));
+    }
+    let grid = raster::read(&src)?;
+    let range = grid.value_range();
+    let dir = PathBuf::from(&cfg.project_path).join("outputs").join("external");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let file_name = src.file_name().ok_or("invalid raster path")?;
+    let dst = dir.join(file_name);
+    if dst != src {
+        fs::copy(&src, &dst).map_err(|e| e.to_string())?;
+    }
+    Ok(Some(ExternalResult { name, source, path: dst.display().to_string(), grid: grid.header, min: range.map(|r| r.0), max: range.map(|r| r.1) }))
+}
+
+/// Annual shadow hours at each receptor, read from the ASC grid of a finished engine job
+/// (`None` for receptors outside the calculation area).
+pub fn receptor_hours(engine_job: &Value, receptors: &[Receptor]) -> Result<Vec<Option<f64>>, String> {
+    let asc = engine_job["outputs"]["asc"].as_str().ok_or("the run produced no ASC grid")?;
+    let grid = raster::read(Path::new(asc))?;
+    Ok(receptors.iter().map(|r| grid.sample(r.x, r.y)).collect())
+}
+
+/// The project's own result grid in `outputs/`, ASC first as the engine always writes one of the two.
+pub fn own_grid(cfg: &ProjectConfig) -> Option<raster::Grid> {
+    let outputs = PathBuf::from(&cfg.project_path).join("outputs");
+    ["shadow_hours.asc", "shadow_hours.tif"].iter().map(|f| outputs.join(f)).find(|p| p.is_file()).and_then(|p| raster::read(&p).ok())
+}
+
+#[derive(Serialize)]
+pub struct ExternalRow {
+    id: String,
+    ours_h: Option<f64>,
+    theirs_h: Option<f64>,
+    difference_h: Option<f64>,
+}
+
+/// Our results side by side with a registered external result.
+#[derive(Serialize)]
+pub struct ExternalComparison {
+    name: String,
+    source: String,
+    rows: Vec<ExternalRow>,
+    /// Over the cells where both grids have a value.
+    mean_abs_difference_h: Option<f64>,
+    max_abs_difference_h: Option<f64>,
+    /// Ours minus theirs on our grid, registered like any external result; `path` is empty in
+    /// viewer mode, where neither it nor the CSV is written.
+    difference: ExternalResult,
+    csv_path: Option<String>,
+}
+
+/// Ours minus theirs at the centre of each of our cells; NaN where either has no value. The
+/// external grid may have another extent or cell size.
+fn difference(ours: &raster::Grid, theirs: &raster::Grid) -> Vec<f64> {
+    let h = &ours.header;
+    (0..h.nrows * h.ncols)
+        .map(|i| {
+            let (row, col) = (i / h.ncols, i % h.ncols);
+            let x = h.xll + (col as f64 + 0.5) * h.cellsize;
+            let y = h.yll + ((h.nrows - 1 - row) as f64 + 0.5) * h.cellsize;
+            match (ours.sample(x, y), theirs.sample(x, y)) {
+                (Some(a), Some(b)) => a - b,
+                _ => f64::NAN,
+            }
+        })
+        .collect()
+}
+
+/// Only reads rasters, so it is available in viewer mode too; the difference ASC and the CSV
+/// are written only outside it.
+#[tauri::command]
+pub fn compare_external_result(cfg: ProjectConfig, name: String, window: tauri::Window, viewer: State<ViewerMode>) -> Result<ExternalComparison, String> {
+    let external = cfg.external_results.iter().find(|r| r.name == name).ok_or_else(|| format!("no external result named {name}"))?;
+    let ours = own_grid(&cfg).ok_or("no result grid in outputs/: run the calculation first")?;
+    let theirs = raster::read(Path::new(&external.path))?;
+    let rows: Vec<ExternalRow> = cfg
+        .receptors
+        .iter()
+        .map(|r| {
+            let (a, b) = (ours.sample(r.x, r.y), theirs.sample(r.x, r.y));
+            ExternalRow { id: r.id.clone(), ours_h: a, theirs_h: b, difference_h: a.zip(b).map(|(a, b)| a - b) }
+        })
+        .collect();
+
+    let mut header = ours.header.clone();
+    header.nodata = Some(raster::ASC_NODATA);
+    let diff = raster::Grid { header, data: difference(&ours, &theirs) };
+    let abs: Vec<f64> = diff.data.iter().filter(|v| !v.is_nan()).map(|v| v.abs()).collect();
+    let range = diff.value_range();
+    let diff_name = format!("{name}_difference");
+    let (diff_path, csv_path) = if viewer.is_on(window.label()) { (String::new(), None) } else { write_comparison(&cfg, &name, &external.source, &diff, &rows)? };
+
+    Ok(ExternalComparison {
+        name: name.clone(),
+        source: external.source.clone(),
+        rows,
+        mean_abs_difference_h: (!abs.is_empty()).then(|| abs.iter().sum::<f64>() / abs.len() as f64),
+        max_abs_difference_h: abs.iter().copied().reduce(f64::max),
+        difference: ExternalResult { name: diff_name, source: format!("ours - {name}"), path: diff_path, grid: diff.header, min: range.map(|r| r.0), max: range.map(|r| r.1) },
+        csv_path,
+    })
+}
+
+/// Writes `<name>_difference.asc` and `<name>_comparison.csv` into `outputs/external/`.
+fn write_comparison(cfg: &ProjectConfig, name: &str, source: &str, diff: &raster::Grid, rows: &[ExternalRow]) -> Result<(String, Option<String>), String> {
+    let dir = PathBuf::from(&cfg.project_path).join("outputs").join("external");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let diff_path = dir.join(format!("{name}_difference.asc"));
+    raster::write_asc(&diff_path, &diff.header, &diff.data).map_err(|e| e.to_string())?;
+
+    let csv_path = dir.join(format!("{name}_comparison.csv"));
+    let mut w = csv::WriterBuilder::new().delimiter(b';').from_path(&csv_path).map_err(|e| e.to_string())?;
+    let fmt = |v: Option<f64>| v.map(|h| format!("{h:.2}")).unwrap_or_default();
+    w.write_record(["receptor", "ours_h", &format!("{source}_h"), "difference_h"]).map_err(|e| e.to_string())?;
+    for r in rows {
+        w.write_record([r.id.clone(), fmt(r.ours_h), fmt(r.theirs_h), fmt(r.difference_h)]).map_err(|e| e.to_string())?;
+    }
+    w.flush().map_err(|e| e.to_string())?;
+    Ok((diff_path.display().to_string(), Some(csv_path.display().to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(xll: f64, cellsize: f64, ncols: usize, nrows: usize, data: Vec<f64>) -> raster::Grid {
+        raster::Grid { header: raster::GridHeader { ncols, nrows, xll, yll: 0.0, cellsize, nodata: Some(raster::ASC_NODATA), epsg: None }, data }
+    }
+
+    #[test]
+    fn difference_samples_a_coarser_offset_grid() {
+        // Ours: 4x1 cells of 10 m from x=0; theirs: 2 cells of 20 m from x=10, the second nodata.
+        let ours = grid(0.0, 10.0, 4, 1, vec![5.0, 6.0, 7.0, 8.0]);
+        let theirs = grid(10.0, 20.0, 2, 1, vec![4.0, raster::ASC_NODATA]);
+        let diff = difference(&ours, &theirs);
+        assert!(diff[0].is_nan());
+        assert_eq!(&diff[1..3], &[2.0, 3.0]);
+        assert!(diff[3].is_nan());
+    }
+}
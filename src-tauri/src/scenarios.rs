@@ -0,0 +1,220 @@
+//! Named project scenarios (alternative layouts, turbine models, settings) run side by side:
+//! every scenario is one job of a batch, the engine workers take them in parallel, progress is
+//! reported for the batch as a whole and a comparison table is written when all have finished.
+
+use crate::{
+    audit, disk,
+    jobs::{self, Job, JobQueue, JobStatus},
+    results, settings,
+    viewer::ViewerMode,
+    workspace, ProjectConfig,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+use tauri::{AppHandle, Manager, State};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    /// JSON merge patch (RFC 7396) over the project config, e.g. `{"turbines": [...]}`.
+    #[serde(default)]
+    pub overrides: Value,
+}
+
+#[derive(Clone, Serialize)]
+struct ScenarioStatus {
+    name: String,
+    job: u64,
+    status: JobStatus,
+    progress_pct: f64,
+}
+
+#[derive(Clone, Serialize)]
+struct BatchProgress {
+    batch: String,
+    finished: usize,
+    total: usize,
+    /// Mean engine progress over all scenarios.
+    progress_pct: f64,
+    scenarios: Vec<ScenarioStatus>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ScenarioSummary {
+    name: String,
+    status: JobStatus,
+    error: Option<String>,
+    turbines: usize,
+    max_h: Option<f64>,
+    mean_h: Option<f64>,
+    /// Receptors above the compliance limit from the settings.
+    receptors_over_limit: Option<usize>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ComparisonRow {
+    id: String,
+    /// Hours per scenario, in `ScenarioComparison::scenarios` order.
+    hours: Vec<Option<f64>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ScenarioComparison {
+    batch: String,
+    limit_h: f64,
+    scenarios: Vec<ScenarioSummary>,
+    rows: Vec<ComparisonRow>,
+    csv_path: String,
+}
+
+fn merge(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = json!({});
+    }
+    if let Value::Object(target) = target {
+        for (k, v) in patch {
+            if v.is_null() {
+                target.remove(k);
+            } else {
+                merge(target.entry(k.clone()).or_insert(Value::Null), v);
+            }
+        }
+    }
+}
+
+fn apply(cfg: &ProjectConfig, scenario: &Scenario, dir: &Path) -> Result<ProjectConfig, String> {
+    let mut value = serde_json::to_value(cfg).map_err(|e| e.to_string())?;
+    merge(&mut value, &scenario.overrides);
+    let mut out: ProjectConfig = serde_json::from_value(value).map_err(|e| format!("scenario {}: {e}", scenario.name))?;
+    out.scenarios = vec![];
+    out.project_path = dir.join(&scenario.name).display().to_string();
+    if out.output["format"] == "geotiff" {
+        out.output["format"] = "both".into();
+    }
+    Ok(out)
+}
+
+fn validate(scenarios: &[Scenario]) -> Result<(), String> {
+    if scenarios.is_empty() {
+        return Err("the project has no scenarios".to_string());
+    }
+    for (i, s) in scenarios.iter().enumerate() {
+        if s.name.trim().is_empty() || s.name.contains(['/', '\\', ':']) || s.name.starts_with('.') {
+            return Err(format!("invalid scenario name: {:?}", s.name));
+        }
+        if scenarios[..i].iter().any(|o| o.name == s.name) {
+            return Err(format!("duplicate scenario name: {}", s.name));
+        }
+    }
+    Ok(())
+}
+
+fn progress(batch: &str, names: &[String], jobs: &[Job]) -> BatchProgress {
+    let scenarios: Vec<ScenarioStatus> = names
+        .iter()
+        .zip(jobs)
+        .map(|(name, j)| {
+            let pct = if j.status.is_finished() { 100.0 } else { j.engine.as_ref().and_then(|e| e["progress_pct"].as_f64()).unwrap_or(0.0) };
+            ScenarioStatus { name: name.clone(), job: j.id, status: j.status, progress_pct: pct }
+        })
+        .collect();
+    let progress_pct = scenarios.iter().map(|s| s.progress_pct).sum::<f64>() / scenarios.len().max(1) as f64;
+    BatchProgress { batch: batch.to_string(), finished: jobs.iter().filter(|j| j.status.is_finished()).count(), total: jobs.len(), progress_pct, scenarios }
+}
+
+/// Failed scenarios stay in the table with their error, so one broken variant does not hide the others.
+fn compare(batch: &str, cfg: &ProjectConfig, names: &[String], turbines: &[usize], jobs: &[Job], limit_h: f64, dir: &Path) -> Result<ScenarioComparison, String> {
+    let per_scenario: Vec<Option<Vec<Option<f64>>>> = jobs.iter().map(|j| j.engine.as_ref().filter(|_| j.status == JobStatus::Done).and_then(|e| results::receptor_hours(e, &cfg.receptors).ok())).collect();
+    let scenarios: Vec<ScenarioSummary> = names
+        .iter()
+        .zip(turbines)
+        .zip(jobs)
+        .zip(&per_scenario)
+        .map(|(((name, turbines), j), hours)| {
+            let stats = j.engine.as_ref().map(|e| &e["stats"]);
+            ScenarioSummary {
+                name: name.clone(),
+                status: j.status,
+                error: j.error.clone(),
+                turbines: *turbines,
+                max_h: stats.and_then(|s| s["max"].as_f64()),
+                mean_h: stats.and_then(|s| s["mean"].as_f64()),
+                receptors_over_limit: hours.as_ref().map(|h| h.iter().flatten().filter(|h| **h > limit_h).count()),
+            }
+        })
+        .collect();
+    let rows: Vec<ComparisonRow> = cfg.receptors.iter().enumerate().map(|(i, r)| ComparisonRow { id: r.id.clone(), hours: per_scenario.iter().map(|h| h.as_ref().and_then(|h| h[i])).collect() }).collect();
+
+    let csv_path = dir.join("scenarios.csv");
+    let mut w = csv::WriterBuilder::new().delimiter(b';').flexible(true).from_path(&csv_path).map_err(|e| e.to_string())?;
+    let fmt = |v: Option<f64>| v.map(|h| format!("{h:.2}")).unwrap_or_default();
+    w.write_record(["scenario", "status", "turbines", "max_h", "mean_h", "receptors_over_limit"]).map_err(|e| e.to_string())?;
+    for s in &scenarios {
+        let status = serde_json::to_value(s.status).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+        w.write_record([s.name.clone(), status, s.turbines.to_string(), fmt(s.max_h), fmt(s.mean_h), s.receptors_over_limit.map(|n| n.to_string()).unwrap_or_default()]).map_err(|e| e.to_string())?;
+    }
+    if !rows.is_empty() {
+        w.write_record([""]).map_err(|e| e.to_string())?;
+        let mut header = vec!["receptor".to_string()];
+        header.extend(names.iter().map(|n| format!("{n}_h")));
+        w.write_record(&header).map_err(|e| e.to_string())?;
+        for r in &rows {
+            let mut rec = vec![r.id.clone()];
+            rec.extend(r.hours.iter().map(|h| fmt(*h)));
+            w.write_record(&rec).map_err(|e| e.to_string())?;
+        }
+    }
+    w.flush().map_err(|e| e.to_string())?;
+    Ok(ScenarioComparison { batch: batch.to_string(), limit_h, scenarios, rows, csv_path: csv_path.display().to_string() })
+}
+
+/// Queues every scenario of the project and returns the batch id. Progress arrives as
+/// `scenarios://progress` events, the comparison as `scenarios://done` (or `scenarios://failed`).
+#[tauri::command]
+pub fn run_scenarios(cfg: ProjectConfig, start_at: Option<String>, app: AppHandle, queue: State<JobQueue>, window: tauri::Window, viewer: State<ViewerMode>) -> Result<String, String> {
+    viewer.check(window.label())?;
+    let start_at = jobs::parse_optional_start_at(start_at)?;
+    validate(&cfg.scenarios)?;
+    let batch = format!("scenarios-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let dir = PathBuf::from(&cfg.project_path).join("scenarios").join(&batch);
+    let runs = cfg.scenarios.iter().map(|s| apply(&cfg, s, &dir)).collect::<Result<Vec<_>, _>>()?;
+    if let Some(largest) = runs.iter().max_by_key(|r| disk::run_bytes(r)) {
+        disk::ensure_run_space(&app, largest, runs.len())?;
+    }
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let names: Vec<String> = cfg.scenarios.iter().map(|s| s.name.clone()).collect();
+    let turbines: Vec<usize> = runs.iter().map(|r| r.turbines.len()).collect();
+    let ids = names.iter().zip(&runs).map(|(name, run)| queue.submit(format!("scenario {name}"), Some(batch.clone()), jobs::Priority::Batch, start_at, &cfg.project_path, run)).collect::<Result<Vec<_>, _>>()?;
+    jobs::preempt_when_due(&app, start_at);
+    audit::record(&app, &cfg, "scenarios_submitted", json!({ "batch": batch, "scenarios": names, "start_at": start_at }));
+    let limit_h = settings::load(&app).compliance_limit_h;
+    let batch_id = batch.clone();
+    thread::spawn(move || {
+        let queue = app.state::<JobQueue>();
+        let jobs = loop {
+            let jobs: Vec<Job> = ids.iter().filter_map(|id| queue.get(*id)).collect();
+            workspace::emit(&app, &cfg.project_path, "scenarios://progress", progress(&batch, &names, &jobs));
+            if jobs.iter().all(|j| j.status.is_finished()) {
+                break jobs;
+            }
+            thread::sleep(PROGRESS_INTERVAL);
+        };
+        match compare(&batch, &cfg, &names, &turbines, &jobs, limit_h, &dir) {
+            Ok(table) => workspace::emit(&app, &cfg.project_path, "scenarios://done", table),
+            Err(e) => workspace::emit(&app, &cfg.project_path, "scenarios://failed", json!({ "batch": batch, "error": e })),
+        }
+    });
+    Ok(batch_id)
+}
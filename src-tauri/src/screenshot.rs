@@ -0,0 +1,75 @@
+//! Map screenshots for documenting working sessions: the screen under the map viewport is grabbed
+//! with the OS capture APIs and saved as `outputs/screenshots/map-<timestamp>.png`.
+
+use serde::Deserialize;
+use std::{fs, path::PathBuf};
+use tauri::WebviewWindow;
+use xcap::{image::imageops, Monitor};
+
+/// Map element bounds from `getBoundingClientRect`, in CSS pixels of the webview.
+#[derive(Deserialize)]
+pub struct Viewport {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+/// A monitor in physical pixels, with the size of its captured image.
+struct Screen {
+    x: f64,
+    y: f64,
+    width: f64,
+    image: (u32, u32),
+}
+
+/// Image rectangle for physical screen bounds `(left, top, width, height)`, `None` off screen.
+fn crop(screen: &Screen, (left, top, width, height): (f64, f64, f64, f64)) -> Option<(u32, u32, u32, u32)> {
+    // Image pixels per physical pixel, 1 unless the capture is downscaled.
+    let px = screen.image.0 as f64 / screen.width.max(1.0);
+    let crop_x = ((left - screen.x) * px).max(0.0) as u32;
+    let crop_y = ((top - screen.y) * px).max(0.0) as u32;
+    let crop_w = ((width * px) as u32).min(screen.image.0.saturating_sub(crop_x));
+    let crop_h = ((height * px) as u32).min(screen.image.1.saturating_sub(crop_y));
+    (crop_w > 0 && crop_h > 0).then_some((crop_x, crop_y, crop_w, crop_h))
+}
+
+/// The window must be visible and unobstructed: overlapping windows end up in the picture.
+#[tauri::command(async)]
+pub fn capture_map(viewport: Viewport, project_path: String, window: WebviewWindow) -> Result<String, String> {
+    if project_path.is_empty() {
+        return Err("open a project first".to_string());
+    }
+    let scale = window.scale_factor().map_err(|e| e.to_string())?;
+    let origin = window.inner_position().map_err(|e| e.to_string())?;
+    // Physical screen pixels of the viewport corners.
+    let left = origin.x as f64 + viewport.x * scale;
+    let top = origin.y as f64 + viewport.y * scale;
+    let (width, height) = (viewport.width * scale, viewport.height * scale);
+    // macOS places monitors in points, elsewhere monitor coordinates are physical pixels.
+    let units = if cfg!(target_os = "macos") { scale } else { 1.0 };
+    let monitor = Monitor::from_point(((left + width / 2.0) / units) as i32, ((top + height / 2.0) / units) as i32).map_err(|e| format!("screen capture: {e}"))?;
+    let image = monitor.capture_image().map_err(|e| format!("screen capture: {e}"))?;
+    let screen = Screen { x: monitor.x() as f64 * units, y: monitor.y() as f64 * units, width: monitor.width() as f64 * units, image: (image.width(), image.height()) };
+    let (crop_x, crop_y, crop_w, crop_h) = crop(&screen, (left, top, width, height)).ok_or("the map is not on screen")?;
+    let dir = PathBuf::from(&project_path).join("outputs").join("screenshots");
+    fs::create_dir_all(&dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    let path = dir.join(format!("map-{}.png", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    imageops::crop_imm(&image, crop_x, crop_y, crop_w, crop_h).to_image().save(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crop_on_a_retina_monitor() {
+        // A 1440x900 pt monitor at 2x, right of the main one: 2880x1800 physical pixels and image.
+        let screen = Screen { x: 2880.0, y: 0.0, width: 2880.0, image: (2880, 1800) };
+        assert_eq!(crop(&screen, (3000.0, 200.0, 800.0, 600.0)), Some((120, 200, 800, 600)));
+        let downscaled = Screen { image: (1440, 900), ..screen };
+        assert_eq!(crop(&downscaled, (3000.0, 200.0, 800.0, 600.0)), Some((60, 100, 400, 300)));
+        assert_eq!(crop(&downscaled, (6000.0, 200.0, 800.0, 600.0)), None);
+    }
+}
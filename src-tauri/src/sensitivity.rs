@@ -0,0 +1,142 @@
+//! Parameter sweeps for EIA uncertainty sections: every variant runs as a job of one batch and
+//! the receptor hours are tabulated against the baseline.
+
+use crate::{
+    audit, disk,
+    jobs::{self, JobQueue},
+    results,
+    viewer::ViewerMode,
+    workspace, ProjectConfig,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf, thread};
+use tauri::{AppHandle, Manager, State};
+
+fn default_thresholds() -> Vec<f64> {
+    vec![30.0]
+}
+
+#[derive(Deserialize)]
+pub struct Sweep {
+    /// Added to every project turbine's hub height, e.g. `[-10, 10]`.
+    #[serde(default)]
+    hub_height_offsets_m: Vec<f64>,
+    /// Whole-layout shifts `[dx, dy]` in project units.
+    #[serde(default)]
+    layout_shifts_m: Vec<[f64; 2]>,
+    /// Annual hour limits to count exceedances against; no extra runs needed.
+    #[serde(default = "default_thresholds")]
+    thresholds_h: Vec<f64>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SensitivityRow {
+    id: String,
+    /// Hours per variant, in `SensitivityTable::variants` order.
+    hours: Vec<Option<f64>>,
+    /// Largest absolute change against the baseline.
+    max_delta_h: Option<f64>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SensitivityTable {
+    batch: String,
+    variants: Vec<String>,
+    thresholds_h: Vec<f64>,
+    rows: Vec<SensitivityRow>,
+    /// `exceedances[v][t]`: receptors above `thresholds_h[t]` in variant `v`.
+    exceedances: Vec<Vec<usize>>,
+    csv_path: String,
+}
+
+fn variants(cfg: &ProjectConfig, sweep: &Sweep, dir: &std::path::Path) -> Vec<(String, ProjectConfig)> {
+    let mut out = vec![("baseline".to_string(), cfg.clone())];
+    for dh in &sweep.hub_height_offsets_m {
+        let mut v = cfg.clone();
+        v.turbines.iter_mut().for_each(|t| t.hub_height_m += dh);
+        out.push((format!("hub{dh:+}m"), v));
+    }
+    for [dx, dy] in &sweep.layout_shifts_m {
+        let mut v = cfg.clone();
+        v.turbines.iter_mut().for_each(|t| {
+            t.x += dx;
+            t.y += dy;
+        });
+        out.push((format!("shift{dx:+}_{dy:+}m"), v));
+    }
+    for (name, v) in out.iter_mut() {
+        v.project_path = dir.join(name.as_str()).display().to_string();
+        if v.output["format"] == "geotiff" {
+            v.output["format"] = "both".into();
+        }
+    }
+    out
+}
+
+fn tabulate(batch: &str, cfg: &ProjectConfig, names: Vec<String>, jobs: &[crate::jobs::Job], thresholds_h: Vec<f64>, dir: &std::path::Path) -> Result<SensitivityTable, String> {
+    if let Some(failed) = jobs.iter().find(|j| j.engine.is_none() || j.error.is_some()) {
+        return Err(format!("variant {} failed: {}", failed.label, failed.error.as_deref().unwrap_or("no result")));
+    }
+    let per_variant = jobs.iter().map(|j| results::receptor_hours(j.engine.as_ref().unwrap_or(&serde_json::Value::Null), &cfg.receptors)).collect::<Result<Vec<_>, _>>()?;
+    let rows: Vec<SensitivityRow> = cfg
+        .receptors
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let hours: Vec<Option<f64>> = per_variant.iter().map(|v| v[i]).collect();
+            let max_delta_h = hours[0].and_then(|base| hours.iter().flatten().map(|h| (h - base).abs()).reduce(f64::max));
+            SensitivityRow { id: r.id.clone(), hours, max_delta_h }
+        })
+        .collect();
+    let exceedances = (0..names.len()).map(|v| thresholds_h.iter().map(|t| rows.iter().filter(|r| r.hours[v].is_some_and(|h| h > *t)).count()).collect()).collect();
+
+    let csv_path = dir.join("sensitivity.csv");
+    let mut w = csv::WriterBuilder::new().delimiter(b';').from_path(&csv_path).map_err(|e| e.to_string())?;
+    let mut header = vec!["receptor".to_string()];
+    header.extend(names.iter().map(|n| format!("{n}_h")));
+    header.push("max_delta_h".to_string());
+    w.write_record(&header).map_err(|e| e.to_string())?;
+    let fmt = |v: Option<f64>| v.map(|h| format!("{h:.2}")).unwrap_or_default();
+    for r in &rows {
+        let mut rec = vec![r.id.clone()];
+        rec.extend(r.hours.iter().map(|h| fmt(*h)));
+        rec.push(fmt(r.max_delta_h));
+        w.write_record(&rec).map_err(|e| e.to_string())?;
+    }
+    w.flush().map_err(|e| e.to_string())?;
+    Ok(SensitivityTable { batch: batch.to_string(), variants: names, thresholds_h, rows, exceedances, csv_path: csv_path.display().to_string() })
+}
+
+/// Queues every variant and returns the batch id; the table arrives as a `sensitivity://done`
+/// event (or `sensitivity://failed`) once all runs finished.
+#[tauri::command]
+pub fn run_sensitivity(cfg: ProjectConfig, sweep: Sweep, start_at: Option<String>, app: AppHandle, queue: State<JobQueue>, window: tauri::Window, viewer: State<ViewerMode>) -> Result<String, String> {
+    viewer.check(window.label())?;
+    let start_at = jobs::parse_optional_start_at(start_at)?;
+    if cfg.receptors.is_empty() {
+        return Err("sensitivity analysis needs receptors".to_string());
+    }
+    let batch = format!("sensitivity-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let dir = PathBuf::from(&cfg.project_path).join("sensitivity").join(&batch);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let variants = variants(&cfg, &sweep, &dir);
+    disk::ensure_run_space(&app, &cfg, variants.len())?;
+    let mut names = vec![];
+    let mut ids = vec![];
+    for (name, variant) in variants {
+        ids.push(queue.submit(name.clone(), Some(batch.clone()), jobs::Priority::Batch, start_at, &cfg.project_path, &variant)?);
+        names.push(name);
+    }
+    jobs::preempt_when_due(&app, start_at);
+    audit::record(&app, &cfg, "sensitivity_submitted", serde_json::json!({ "batch": batch, "variants": names, "start_at": start_at }));
+    let thresholds_h = sweep.thresholds_h;
+    let batch_id = batch.clone();
+    thread::spawn(move || {
+        let jobs = app.state::<JobQueue>().wait_all(&ids);
+        match tabulate(&batch, &cfg, names, &jobs, thresholds_h, &dir) {
+            Ok(table) => workspace::emit(&app, &cfg.project_path, "sensitivity://done", table),
+            Err(e) => workspace::emit(&app, &cfg.project_path, "sensitivity://failed", serde_json::json!({ "batch": batch, "error": e })),
+        }
+    });
+    Ok(batch_id)
+}
@@ -0,0 +1,113 @@
+//! Setback zones where turbines cannot go: discs around receptors and bands along the inside of
+//! the site boundary, as GeoJSON in the project CRS. Turbines already inside a zone are listed
+//! with the exact distance, not the polygon approximation.
+
+use crate::{audit, dxf, viewer::ViewerMode, ProjectConfig};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{f64::consts::PI, fs};
+use tauri::{AppHandle, State};
+
+/// Vertices of a full circle; arcs use the same angular step.
+const CIRCLE_VERTICES: usize = 72;
+
+#[derive(Serialize)]
+pub struct Violation {
+    turbine: String,
+    /// Receptor id, or `boundary` for the site boundary.
+    from: String,
+    distance_m: f64,
+    setback_m: f64,
+}
+
+#[derive(Serialize)]
+pub struct Setbacks {
+    /// FeatureCollection with one Polygon per receptor disc and per boundary edge.
+    geojson: Value,
+    violations: Vec<Violation>,
+}
+
+fn circle((cx, cy): (f64, f64), r: f64) -> dxf::Ring {
+    (0..=CIRCLE_VERTICES).map(|i| 2.0 * PI * (i % CIRCLE_VERTICES) as f64 / CIRCLE_VERTICES as f64).map(|a| (cx + r * a.cos(), cy + r * a.sin())).collect()
+}
+
+/// Stadium around the segment `p`–`q`: every point within `r` of it, counter-clockwise.
+fn capsule(p: (f64, f64), q: (f64, f64), r: f64) -> dxf::Ring {
+    let heading = (q.1 - p.1).atan2(q.0 - p.0);
+    let half = CIRCLE_VERTICES / 2;
+    let cap = |(cx, cy): (f64, f64), from: f64| (0..=half).map(move |i| from + PI * i as f64 / half as f64).map(move |a| (cx + r * a.cos(), cy + r * a.sin()));
+    let mut ring: dxf::Ring = cap(q, heading - PI / 2.0).chain(cap(p, heading + PI / 2.0)).collect();
+    ring.push(ring[0]);
+    ring
+}
+
+fn distance_to_segment((x, y): (f64, f64), p: (f64, f64), q: (f64, f64)) -> f64 {
+    let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 { 0.0 } else { (((x - p.0) * dx + (y - p.1) * dy) / len2).clamp(0.0, 1.0) };
+    (x - p.0 - t * dx).hypot(y - p.1 - t * dy)
+}
+
+fn polygon(ring: &dxf::Ring) -> Value {
+    json!({ "type": "Polygon", "coordinates": [ring.iter().map(|&(x, y)| [x, y]).collect::<Vec<_>>()] })
+}
+
+/// `receptor_m` buffers every receptor, `boundary_m` every edge of the site boundary; either may
+/// be omitted. Boundary bands extend both ways from the edge, the outer half being off site anyway.
+pub fn generate(cfg: &ProjectConfig, receptor_m: Option<f64>, boundary_m: Option<f64>) -> Result<Setbacks, String> {
+    let positive = |d: Option<f64>, what: &str| match d {
+        Some(d) if d.is_nan() || d <= 0.0 => Err(format!("{what} setback must be positive, got {d}")),
+        d => Ok(d),
+    };
+    let (receptor_m, boundary_m) = (positive(receptor_m, "receptor")?, positive(boundary_m, "boundary")?);
+    let mut features = vec![];
+    let mut violations = vec![];
+    if let Some(d) = receptor_m {
+        for r in &cfg.receptors {
+            features.push(json!({ "type": "Feature", "properties": { "kind": "receptor", "id": r.id, "setback_m": d }, "geometry": polygon(&circle((r.x, r.y), d)) }));
+            for t in &cfg.turbines {
+                let distance_m = (t.x - r.x).hypot(t.y - r.y);
+                if distance_m < d {
+                    violations.push(Violation { turbine: t.id.clone(), from: r.id.clone(), distance_m, setback_m: d });
+                }
+            }
+        }
+    }
+    if let Some(d) = boundary_m {
+        let boundary = cfg.site_boundary.as_ref().ok_or("the project has no site boundary")?;
+        let edges: Vec<((f64, f64), (f64, f64))> = dxf::geojson_rings(boundary).iter().flat_map(|ring| ring.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>()).filter(|(p, q)| p != q).collect();
+        for (i, &(p, q)) in edges.iter().enumerate() {
+            features.push(json!({ "type": "Feature", "properties": { "kind": "boundary", "edge": i, "setback_m": d }, "geometry": polygon(&capsule(p, q, d)) }));
+        }
+        for t in &cfg.turbines {
+            let distance_m = edges.iter().map(|&(p, q)| distance_to_segment((t.x, t.y), p, q)).fold(f64::INFINITY, f64::min);
+            if distance_m < d {
+                violations.push(Violation { turbine: t.id.clone(), from: "boundary".to_string(), distance_m, setback_m: d });
+            }
+        }
+    }
+    if features.is_empty() {
+        return Err("nothing to buffer: give a receptor or boundary distance and add receptors or a boundary".to_string());
+    }
+    Ok(Setbacks { geojson: dxf::feature_collection(&cfg.epsg, features), violations })
+}
+
+#[tauri::command]
+pub fn generate_setbacks(cfg: ProjectConfig, receptor_m: Option<f64>, boundary_m: Option<f64>) -> Result<Setbacks, String> {
+    generate(&cfg, receptor_m, boundary_m)
+}
+
+/// Saves the setback zones as GeoJSON; returns the path, `None` when the dialog is cancelled.
+#[tauri::command]
+pub fn export_setbacks(cfg: ProjectConfig, receptor_m: Option<f64>, boundary_m: Option<f64>, app: AppHandle, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Option<String>, String> {
+    viewer.check(window.label())?;
+    let setbacks = generate(&cfg, receptor_m, boundary_m)?;
+    let Some(path) = rfd::FileDialog::new().add_filter("GeoJSON", &["geojson"]).set_directory(&cfg.project_path).set_file_name("setbacks.geojson").save_file() else {
+        return Ok(None);
+    };
+    let text = serde_json::to_string_pretty(&setbacks.geojson).map_err(|e| e.to_string())?;
+    fs::write(&path, text).map_err(|e| format!("{}: {e}", path.display()))?;
+    let path = path.display().to_string();
+    audit::record(&app, &cfg, "setbacks_exported", json!({ "path": path, "receptor_m": receptor_m, "boundary_m": boundary_m, "violations": setbacks.violations.len() }));
+    Ok(Some(path))
+}
@@ -0,0 +1,220 @@
+//! Application settings (not per project), kept in `settings.json` in the app config dir.
+
+use crate::{backup::BackupSettings, viewer::ViewerMode};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use tauri::{AppHandle, State};
+
+fn default_limit_h() -> f64 {
+    30.0
+}
+
+/// Half the cores, at most 4: engine runs are numpy-bound and memory hungry.
+fn default_engine_workers() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| (n.get() / 2).clamp(1, 4))
+}
+
+fn default_long_run_min() -> f64 {
+    10.0
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SmtpSettings {
+    pub server: String,
+    pub port: u16,
+    /// `starttls`, `tls` (implicit, usually port 465) or `none`.
+    pub security: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+impl SmtpSettings {
+    /// Fills an empty password from `stored` only for the same account (server, port, username),
+    /// so the saved password is never sent to a host it was not entered for.
+    pub fn fill_password(&mut self, stored: Option<&SmtpSettings>) {
+        let Some(stored) = stored else { return };
+        if self.password.is_empty() && (self.server.trim(), self.port, self.username.trim()) == (stored.server.trim(), stored.port, stored.username.trim()) {
+            self.password = stored.password.clone();
+        }
+    }
+}
+
+/// POSTed on run lifecycle events (`started`, `done`, `failed`, `cancelled`, `batch_finished`).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Events to fire on; empty means all.
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// JSON body with `{{placeholders}}` (see `notify::render`); empty sends the job record.
+    #[serde(default)]
+    pub template: String,
+}
+
+/// Delivery endpoint for `publish_results`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PublishTarget {
+    pub name: String,
+    /// `http`, `sftp` or `s3`.
+    pub kind: String,
+    /// http: base URL files are PUT under; sftp: `user@host:/remote/dir`; s3: endpoint URL.
+    pub url: String,
+    /// http: bearer token.
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub access_key: String,
+    #[serde(default)]
+    pub secret_key: String,
+}
+
+/// Engine on another machine, reached over HTTPS instead of the local sidecar. Project paths
+/// (DEM, outputs) must be on storage the engine sees under the same names.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RemoteEngine {
+    /// `https://host:port`; plain `http://` is only accepted for loopback addresses.
+    pub url: String,
+    /// PEM file with the CA (or the self-signed certificate) to trust instead of the public roots.
+    #[serde(default)]
+    pub ca_path: String,
+    /// SHA-256 fingerprints of accepted server certificates (hex, colons optional). When set, a
+    /// matching certificate is trusted without a CA chain.
+    #[serde(default)]
+    pub pinned_sha256: Vec<String>,
+    /// The engine's `WSS_API_TOKEN`, sent with every call; kept out of `get_settings` like the
+    /// other secrets.
+    #[serde(default)]
+    pub token: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Settings {
+    /// Email on finished/failed batches and long runs; `None` disables it.
+    #[serde(default)]
+    pub smtp: Option<SmtpSettings>,
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    #[serde(default)]
+    pub publish_targets: Vec<PublishTarget>,
+    /// Scheduled project backups; `None` disables them.
+    #[serde(default)]
+    pub backup: Option<BackupSettings>,
+    /// Opens the most recent project (and its last active scenario) at launch.
+    #[serde(default)]
+    pub reopen_last_project: bool,
+    /// Jobs sent to the engine at the same time; read at startup.
+    #[serde(default = "default_engine_workers")]
+    pub engine_workers: usize,
+    /// Single runs shorter than this are not emailed.
+    #[serde(default = "default_long_run_min")]
+    pub long_run_min: f64,
+    /// Annual hours limit for compliance tables (30 h/yr is the usual worst-case guideline).
+    #[serde(default = "default_limit_h")]
+    pub compliance_limit_h: f64,
+    /// Runs go to this engine instead of the local one; `None` uses the sidecar.
+    #[serde(default)]
+    pub remote_engine: Option<RemoteEngine>,
+    /// Asks the engine for per-stage timings and memory, kept in the run history (see `profile`).
+    #[serde(default)]
+    pub profile_runs: bool,
+}
+
+impl Settings {
+    /// Copy for the window: the SMTP password and publish tokens and keys stay in the backend.
+    fn redacted(mut self) -> Settings {
+        if let Some(smtp) = &mut self.smtp {
+            smtp.password.clear();
+        }
+        for t in &mut self.publish_targets {
+            t.token.clear();
+            t.secret_key.clear();
+        }
+        if let Some(remote) = &mut self.remote_engine {
+            remote.token.clear();
+        }
+        self
+    }
+
+    /// Secrets the window sends back empty keep their stored value; the SMTP password only while
+    /// server, port and username are unchanged, the engine token only while the URL is.
+    fn keep_secrets(&mut self, stored: &Settings) {
+        if let (Some(remote), Some(old)) = (&mut self.remote_engine, &stored.remote_engine) {
+            if remote.token.is_empty() && remote.url.trim() == old.url.trim() {
+                remote.token = old.token.clone();
+            }
+        }
+        if let Some(smtp) = &mut self.smtp {
+            smtp.fill_password(stored.smtp.as_ref());
+        }
+        for t in &mut self.publish_targets {
+            let Some(old) = stored.publish_targets.iter().find(|o| o.name == t.name) else { continue };
+            if t.token.is_empty() {
+                t.token = old.token.clone();
+            }
+            if t.secret_key.is_empty() {
+                t.secret_key = old.secret_key.clone();
+            }
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { smtp: None, webhooks: vec![], publish_targets: vec![], backup: None, reopen_last_project: false, engine_workers: default_engine_workers(), long_run_min: default_long_run_min(), compliance_limit_h: default_limit_h(), remote_engine: None, profile_runs: false }
+    }
+}
+
+fn settings_path(app: &AppHandle) -> PathBuf {
+    crate::portable::config_dir(app).join("settings.json")
+}
+
+/// Missing or unreadable settings fall back to the defaults. Secrets included, for the backend only.
+pub fn load(app: &AppHandle) -> Settings {
+    fs::read_to_string(settings_path(app)).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+/// Settings without secrets, see `redacted`.
+#[tauri::command]
+pub fn get_settings(app: AppHandle) -> Settings {
+    load(&app).redacted()
+}
+
+#[tauri::command]
+pub fn set_settings(app: AppHandle, mut settings: Settings, window: tauri::Window, viewer: State<ViewerMode>) -> Result<(), String> {
+    viewer.check(window.label())?;
+    settings.keep_secrets(&load(&app));
+    let path = settings_path(&app);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smtp(server: &str, username: &str, password: &str) -> SmtpSettings {
+        SmtpSettings { server: server.into(), port: 587, security: "starttls".into(), username: username.into(), password: password.into(), from: "wss@example.com".into(), to: vec![] }
+    }
+
+    #[test]
+    fn saved_smtp_password_stays_with_its_account() {
+        let stored = smtp("smtp.example.com", "studio", "secret");
+        let mut same = smtp("smtp.example.com", "studio", "");
+        same.fill_password(Some(&stored));
+        assert_eq!(same.password, "secret");
+        for mut other in [smtp("mail.elsewhere.net", "studio", ""), smtp("smtp.example.com", "someone", "")] {
+            other.fill_password(Some(&stored));
+            assert_eq!(other.password, "");
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! TLS for remote engine connections: a custom CA and/or certificate pinning on top of the rustls
+//! stack ureq already uses, so dwelling coordinates never cross the network in plaintext.
+
+use crate::settings::RemoteEngine;
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    client::WebPkiServerVerifier,
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use sha2::{Digest, Sha256};
+use std::{fs::File, io::BufReader, sync::Arc};
+
+/// Accepts a server certificate whose SHA-256 is pinned, after the CA chain when one is given.
+#[derive(Debug)]
+struct Pinned {
+    pins: Vec<String>,
+    chain: Option<Arc<WebPkiServerVerifier>>,
+    provider: Arc<CryptoProvider>,
+}
+
+/// Lowercase hex without separators, as computed from the certificate.
+fn normalise_pin(pin: &str) -> String {
+    pin.chars().filter(char::is_ascii_hexdigit).collect::<String>().to_lowercase()
+}
+
+impl ServerCertVerifier for Pinned {
+    fn verify_server_cert(&self, end_entity: &CertificateDer<'_>, intermediates: &[CertificateDer<'_>], server_name: &ServerName<'_>, ocsp: &[u8], now: UnixTime) -> Result<ServerCertVerified, rustls::Error> {
+        if let Some(chain) = &self.chain {
+            chain.verify_server_cert(end_entity, intermediates, server_name, ocsp, now)?;
+        }
+        let fingerprint = hex::encode(Sha256::digest(end_entity.as_ref()));
+        if self.pins.contains(&fingerprint) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!("server certificate SHA-256 {fingerprint} is not pinned")))
+        }
+    }
+
+    fn verify_tls12_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn ca_roots(path: &str) -> Result<RootCertStore, String> {
+    let mut reader = BufReader::new(File::open(path).map_err(|e| format!("{path}: {e}"))?);
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut reader) {
+        roots.add(cert.map_err(|e| format!("{path}: {e}"))?).map_err(|e| format!("{path}: {e}"))?;
+    }
+    if roots.is_empty() {
+        return Err(format!("{path}: no PEM certificates"));
+    }
+    Ok(roots)
+}
+
+/// Client configuration for `remote`: its CA or the public roots, plus its pins.
+pub fn client_config(remote: &RemoteEngine) -> Result<Arc<ClientConfig>, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone()).with_safe_default_protocol_versions().map_err(|e| e.to_string())?;
+    let custom_ca = !remote.ca_path.trim().is_empty();
+    let roots = if custom_ca {
+        ca_roots(remote.ca_path.trim())?
+    } else {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        roots
+    };
+    let pins: Vec<String> = remote.pinned_sha256.iter().map(|p| normalise_pin(p)).filter(|p| !p.is_empty()).collect();
+    if let Some(bad) = pins.iter().find(|p| p.len() != 64) {
+        return Err(format!("certificate pin {bad} is not a SHA-256 fingerprint (64 hex digits)"));
+    }
+    let config = if pins.is_empty() {
+        builder.with_root_certificates(roots).with_no_client_auth()
+    } else {
+        // A pin alone admits self-signed engines; with a custom CA the chain must hold as well.
+        let chain = if custom_ca { Some(WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone()).build().map_err(|e| e.to_string())?) } else { None };
+        builder.dangerous().with_custom_certificate_verifier(Arc::new(Pinned { pins, chain, provider })).with_no_client_auth()
+    };
+    Ok(Arc::new(config))
+}
+
+/// Refuses plaintext URLs except to this machine.
+pub fn check_url(url: &str) -> Result<(), String> {
+    let lower = url.trim().to_lowercase();
+    if lower.starts_with("https://") {
+        return Ok(());
+    }
+    let host = lower.strip_prefix("http://").map(|rest| rest.split('/').next().unwrap_or_default());
+    let host = host.map(|h| h.rsplit_once(':').filter(|(_, port)| port.bytes().all(|b| b.is_ascii_digit())).map_or(h, |(name, _)| name));
+    match host {
+        Some("localhost" | "127.0.0.1" | "[::1]") => Ok(()),
+        _ => Err(format!("remote engine URL {url} must use https")),
+    }
+}
@@ -0,0 +1,49 @@
+//! Read-only viewer mode for reviewers and clients: results, maps and reports stay available,
+//! while every command that edits, saves, runs or sends project data is refused here.
+//!
+//! Enabled for every window by the `--viewer` launch flag (or `WSS_VIEWER=1`), or for one window
+//! by opening there a project whose file has `"read_only": true`; the window leaves it when it
+//! opens another project or closes. Commands check it against the window that calls them.
+
+use std::{collections::BTreeSet, sync::Mutex};
+
+#[derive(Default)]
+pub struct ViewerMode {
+    launch: bool,
+    /// Handles of windows holding a read-only project, see `workspace`.
+    read_only: Mutex<BTreeSet<String>>,
+}
+
+impl ViewerMode {
+    pub fn from_launch() -> Self {
+        let launch = std::env::args().any(|a| a == "--viewer") || std::env::var("WSS_VIEWER").is_ok_and(|v| v == "1");
+        ViewerMode { launch, read_only: Mutex::default() }
+    }
+
+    pub fn set_read_only(&self, handle: &str, on: bool) {
+        if let Ok(mut read_only) = self.read_only.lock() {
+            if on {
+                read_only.insert(handle.to_string());
+            } else {
+                read_only.remove(handle);
+            }
+        }
+    }
+
+    pub fn is_on(&self, handle: &str) -> bool {
+        // A poisoned lock errs on the read-only side.
+        self.launch || self.read_only.lock().map_or(true, |r| r.contains(handle))
+    }
+
+    pub fn check(&self, handle: &str) -> Result<(), String> {
+        if self.is_on(handle) {
+            return Err("not available in read-only viewer mode".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn get_viewer_mode(window: tauri::Window, viewer: tauri::State<ViewerMode>) -> bool {
+    viewer.is_on(window.label())
+}
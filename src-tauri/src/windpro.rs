@@ -0,0 +1,269 @@
+//! windPRO interchange: tab-separated WTG and shadow receptor lists that windPRO's
+//! "import objects from text file" wizard reads with a one-line header skip (the project EPSG
+//! goes in a trailing column), and the reverse path for site exports (object lists plus shadow
+//! calculation settings).
+
+use crate::{audit, viewer::ViewerMode, ProjectConfig, Receptor, Turbine};
+use serde::Serialize;
+use std::{fs, io, path::Path};
+use tauri::{AppHandle, State};
+
+pub const TURBINES_FILE: &str = "windpro_wtg.txt";
+pub const RECEPTORS_FILE: &str = "windpro_shadow_receptors.txt";
+
+const TURBINE_COLUMNS: [&str; 6] = ["Label", "Easting", "Northing", "Hub height [m]", "Rotor diameter [m]", "Coordinate system"];
+const RECEPTOR_COLUMNS: [&str; 8] = ["Label", "Easting", "Northing", "Width [m]", "Height [m]", "Height above ground [m]", "Direction mode", "Coordinate system"];
+
+// windPRO's shadow receptor defaults: a 1x1 m window in "green house" mode, i.e. facing every direction.
+const RECEPTOR_WINDOW_M: f64 = 1.0;
+const RECEPTOR_MODE: &str = "Green house";
+
+fn header(columns: &[&str]) -> String {
+    format!("{}\n", columns.join("\t"))
+}
+
+pub fn turbines_text(epsg: &str, turbines: &[Turbine]) -> String {
+    let mut out = header(&TURBINE_COLUMNS);
+    for t in turbines {
+        out.push_str(&format!("{}\t{:.2}\t{:.2}\t{:.1}\t{:.1}\t{epsg}\n", t.id, t.x, t.y, t.hub_height_m, t.rotor_diameter_m));
+    }
+    out
+}
+
+pub fn receptors_text(epsg: &str, receptors: &[Receptor]) -> String {
+    let mut out = header(&RECEPTOR_COLUMNS);
+    for r in receptors {
+        out.push_str(&format!(
+            "{}\t{:.2}\t{:.2}\t{RECEPTOR_WINDOW_M:.1}\t{RECEPTOR_WINDOW_M:.1}\t{:.1}\t{RECEPTOR_MODE}\t{epsg}\n",
+            r.id, r.x, r.y, r.height_m
+        ));
+    }
+    out
+}
+
+/// Writes the WTG list (and the receptor list, when the project has receptors) into `folder`.
+pub fn export(folder: &Path, cfg: &ProjectConfig) -> io::Result<Vec<String>> {
+    let mut written = vec![];
+    let wtg = folder.join(TURBINES_FILE);
+    fs::write(&wtg, turbines_text(&cfg.epsg, &cfg.turbines))?;
+    written.push(wtg.display().to_string());
+    if !cfg.receptors.is_empty() {
+        let rec = folder.join(RECEPTORS_FILE);
+        fs::write(&rec, receptors_text(&cfg.epsg, &cfg.receptors))?;
+        written.push(rec.display().to_string());
+    }
+    Ok(written)
+}
+
+#[tauri::command]
+pub fn export_windpro(cfg: ProjectConfig, app: AppHandle, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Vec<String>, String> {
+    viewer.check(window.label())?;
+    let Some(folder) = rfd::FileDialog::new().set_directory(&cfg.project_path).pick_folder() else {
+        return Ok(vec![]);
+    };
+    let files = export(&folder, &cfg).map_err(|e| e.to_string())?;
+    audit::record(&app, &cfg, "windpro_exported", serde_json::json!({ "files": files }));
+    Ok(files)
+}
+
+#[derive(Serialize)]
+pub struct WindproImport {
+    cfg: ProjectConfig,
+    warnings: Vec<String>,
+}
+
+#[derive(Default)]
+struct Parsed {
+    epsg: Option<String>,
+    cellsize_m: Option<f64>,
+    buffer_m: Option<f64>,
+    turbines: Vec<Turbine>,
+    receptors: Vec<Receptor>,
+}
+
+/// windPRO writes files in the Windows code page; anything that is not valid UTF-8 is read as Latin-1.
+fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// Reads the leading number of a cell, accepting decimal commas and trailing units ("2.000,5 m").
+fn parse_num(cell: &str) -> Option<f64> {
+    let raw: String = cell.trim().chars().take_while(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '+' | ' ')).filter(|c| *c != ' ').collect();
+    let normalized = match (raw.rfind('.'), raw.rfind(',')) {
+        (Some(dot), Some(comma)) if comma > dot => raw.replace('.', "").replace(',', "."),
+        (Some(_), Some(_)) => raw.replace(',', ""),
+        (None, Some(_)) => raw.replace(',', "."),
+        _ => raw,
+    };
+    normalized.parse().ok()
+}
+
+fn split(line: &str) -> Vec<String> {
+    let delim = if line.contains('\t') { '\t' } else { ';' };
+    line.split(delim).map(|c| c.trim().trim_matches('"').to_string()).collect()
+}
+
+fn find_epsg(text: &str) -> Option<String> {
+    let upper = text.to_uppercase();
+    let start = upper.find("EPSG:")?;
+    let code: String = upper[start + 5..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    (!code.is_empty()).then(|| format!("EPSG:{code}"))
+}
+
+fn column(header: &[String], aliases: &[&str]) -> Option<usize> {
+    header.iter().position(|h| {
+        let h = h.to_lowercase();
+        let name = h.split('[').next().unwrap_or("").trim();
+        aliases.contains(&name)
+    })
+}
+
+const LABEL: &[&str] = &["label", "user label", "name", "id", "wtg"];
+const EASTING: &[&str] = &["easting", "east", "x", "utm east"];
+const NORTHING: &[&str] = &["northing", "north", "y", "utm north"];
+const HUB: &[&str] = &["hub height", "hubheight"];
+const ROTOR: &[&str] = &["rotor diameter", "rotor"];
+const ABOVE_GROUND: &[&str] = &["height above ground", "eye height", "window height"];
+const COORDINATE_SYSTEM: &[&str] = &["coordinate system", "epsg"];
+const RESOLUTION: &[&str] = &["resolution", "calculation resolution", "grid resolution"];
+const DISTANCE: &[&str] = &["max distance", "maximum distance", "calculation distance", "max. distance for influence", "maximum distance for influence"];
+
+/// Preamble `key;value` lines; keys are matched whole (units in brackets aside), so e.g. a
+/// "Distance to receptor" line does not set the buffer.
+fn parse_settings_line(cells: &[String], parsed: &mut Parsed) {
+    let [key, value, ..] = cells else { return };
+    let key = key.to_lowercase();
+    let key = key.split('[').next().unwrap_or("").trim().trim_end_matches(':').trim();
+    if COORDINATE_SYSTEM.contains(&key) {
+        let code = value.trim_start_matches("EPSG:");
+        if code.chars().all(|c| c.is_ascii_digit()) && !code.is_empty() {
+            parsed.epsg = Some(format!("EPSG:{code}"));
+        }
+    } else if RESOLUTION.contains(&key) {
+        parsed.cellsize_m = parse_num(value).or(parsed.cellsize_m);
+    } else if DISTANCE.contains(&key) {
+        parsed.buffer_m = parse_num(value).or(parsed.buffer_m);
+    }
+}
+
+fn parse_file(name: &str, text: &str, parsed: &mut Parsed, warnings: &mut Vec<String>) {
+    let lines: Vec<&str> = text.lines().collect();
+    let header_at = lines.iter().position(|l| {
+        let cells = split(l);
+        column(&cells, EASTING).is_some() && column(&cells, NORTHING).is_some()
+    });
+    let preamble = &lines[..header_at.unwrap_or(lines.len())];
+    for line in preamble {
+        if parsed.epsg.is_none() {
+            parsed.epsg = find_epsg(line);
+        }
+        parse_settings_line(&split(line), parsed);
+    }
+    let Some(header_at) = header_at else { return };
+
+    let header = split(lines[header_at]);
+    let (x_col, y_col) = (column(&header, EASTING).unwrap_or(0), column(&header, NORTHING).unwrap_or(0));
+    let label_col = column(&header, LABEL);
+    let hub_col = column(&header, HUB);
+    let rotor_col = column(&header, ROTOR);
+    let agl_col = column(&header, ABOVE_GROUND);
+    let crs_col = column(&header, COORDINATE_SYSTEM);
+    let is_wtg = hub_col.is_some() || rotor_col.is_some();
+
+    for (i, line) in lines.iter().enumerate().skip(header_at + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells = split(line);
+        let get = |col: Option<usize>| col.and_then(|c| cells.get(c)).and_then(|v| parse_num(v));
+        if parsed.epsg.is_none() {
+            parsed.epsg = crs_col.and_then(|c| cells.get(c)).and_then(|v| find_epsg(v));
+        }
+        let (Some(x), Some(y)) = (get(Some(x_col)), get(Some(y_col))) else {
+            warnings.push(format!("{name}:{}: missing or invalid coordinates, row skipped", i + 1));
+            continue;
+        };
+        let fallback = if is_wtg { format!("T{}", parsed.turbines.len() + 1) } else { format!("R{}", parsed.receptors.len() + 1) };
+        let id = label_col.and_then(|c| cells.get(c)).filter(|v| !v.is_empty()).cloned().unwrap_or(fallback);
+        if is_wtg {
+            let (Some(hub_height_m), Some(rotor_diameter_m)) = (get(hub_col), get(rotor_col)) else {
+                warnings.push(format!("{name}:{}: turbine {id} lacks hub height or rotor diameter, row skipped", i + 1));
+                continue;
+            };
+            parsed.turbines.push(Turbine { id, x, y, hub_height_m, rotor_diameter_m });
+        } else {
+            let height_m = get(agl_col).unwrap_or(RECEPTOR_WINDOW_M);
+            parsed.receptors.push(Receptor { id, x, y, height_m });
+        }
+    }
+}
+
+/// Parses windPRO object lists and settings files, replacing the objects they contain in `cfg`.
+/// Settings override project values only when present; objects replace the existing lists
+/// only if the import actually produced some.
+pub fn import(paths: &[std::path::PathBuf], mut cfg: ProjectConfig) -> io::Result<WindproImport> {
+    let mut parsed = Parsed::default();
+    let mut warnings = vec![];
+    for path in paths {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        parse_file(&name, &decode(&fs::read(path)?), &mut parsed, &mut warnings);
+    }
+    if let Some(epsg) = parsed.epsg {
+        cfg.epsg = epsg;
+    }
+    if let Some(cellsize) = parsed.cellsize_m {
+        cfg.cellsize_m = cellsize;
+    }
+    if let Some(buffer) = parsed.buffer_m {
+        cfg.buffer_m = buffer;
+    }
+    if !parsed.turbines.is_empty() {
+        cfg.turbines = parsed.turbines;
+    }
+    if !parsed.receptors.is_empty() {
+        cfg.receptors = parsed.receptors;
+    }
+    Ok(WindproImport { cfg, warnings })
+}
+
+#[tauri::command]
+pub fn import_windpro(cfg: ProjectConfig, window: tauri::Window, viewer: State<ViewerMode>) -> Result<Option<WindproImport>, String> {
+    viewer.check(window.label())?;
+    let Some(paths) = rfd::FileDialog::new().add_filter("windPRO", &["txt", "csv"]).pick_files() else {
+        return Ok(None);
+    };
+    import(&paths, cfg).map(Some).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_keys_match_whole() {
+        let mut parsed = Parsed::default();
+        for line in ["Max distance [m];2000", "Distance to receptor [m];350", "Calculation resolution:;10", "Min. resolution of the terrain;25"] {
+            parse_settings_line(&split(line), &mut parsed);
+        }
+        assert_eq!(parsed.buffer_m, Some(2000.0));
+        assert_eq!(parsed.cellsize_m, Some(10.0));
+    }
+
+    #[test]
+    fn export_has_one_header_line_and_keeps_the_epsg() {
+        let turbines = [Turbine { id: "T1".into(), x: 500000.0, y: 5000000.0, hub_height_m: 120.0, rotor_diameter_m: 150.0 }];
+        let text = turbines_text("EPSG:32632", &turbines);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], TURBINE_COLUMNS.join("\t"));
+        assert!(lines[1].starts_with("T1\t500000.00\t5000000.00\t"));
+
+        let mut parsed = Parsed::default();
+        parse_file(TURBINES_FILE, &text, &mut parsed, &mut vec![]);
+        assert_eq!(parsed.epsg.as_deref(), Some("EPSG:32632"));
+        assert_eq!(parsed.turbines.len(), 1);
+    }
+}
@@ -0,0 +1,140 @@
+//! Several projects open at once, one per window. A window's label is the handle of the project
+//! it holds: `main` for the first window, `project-<n>` for those from `open_project_window`.
+//!
+//! Every handle runs its own engine process (see `EngineState`), so runs of two sites do not
+//! share one interpreter; jobs go to the engine of their project's handle and their events to
+//! its window, or to every window when no window holds the project (headless runs, a project
+//! closed meanwhile).
+
+use crate::{project, viewer::ViewerMode, EngineState, ProjectConfig};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+    thread,
+};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+pub const MAIN: &str = "main";
+
+#[derive(Default)]
+pub struct Workspace {
+    /// Project path by handle.
+    open: Mutex<BTreeMap<String, String>>,
+    next: AtomicU32,
+}
+
+#[derive(Serialize)]
+pub struct OpenProject {
+    handle: String,
+    project_path: String,
+}
+
+impl Workspace {
+    pub fn bind(&self, handle: &str, project_path: &str) {
+        if let Ok(mut open) = self.open.lock() {
+            open.insert(handle.to_string(), project_path.to_string());
+        }
+    }
+
+    pub fn release(&self, handle: &str) {
+        if let Ok(mut open) = self.open.lock() {
+            open.remove(handle);
+        }
+    }
+
+    pub fn handle_of(&self, project_path: &str) -> Option<String> {
+        self.open.lock().ok()?.iter().find(|(_, p)| *p == project_path).map(|(h, _)| h.clone())
+    }
+
+    fn project_of(&self, handle: &str) -> Option<String> {
+        self.open.lock().ok()?.get(handle).cloned()
+    }
+}
+
+/// Handle whose engine runs `project_path`: the window holding it, else the main one.
+pub fn handle_for(app: &AppHandle, project_path: &str) -> String {
+    app.state::<Workspace>().handle_of(project_path).unwrap_or(MAIN.to_string())
+}
+
+/// Sends a project event to the window holding the project, or to every window.
+pub fn emit<S: Serialize + Clone>(app: &AppHandle, project_path: &str, event: &str, payload: S) {
+    let _ = match app.state::<Workspace>().handle_of(project_path) {
+        Some(handle) => app.emit_to(handle.as_str(), event, payload),
+        None => app.emit(event, payload),
+    };
+}
+
+/// Fails when `project_path` is already open in another window, and brings that window forward.
+pub fn claim(app: &AppHandle, handle: &str, project_path: &str) -> Result<(), String> {
+    match app.state::<Workspace>().handle_of(project_path).filter(|h| h != handle) {
+        Some(other) => {
+            if let Some(window) = app.get_webview_window(&other) {
+                let _ = window.set_focus();
+            }
+            Err(format!("{project_path} is already open in another window"))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Forgets a closed window's project and viewer mode and stops its engine; runs still going
+/// there continue on the main engine. The main handle keeps its engine until the app exits.
+pub fn closed(app: &AppHandle, handle: &str) {
+    app.state::<Workspace>().release(handle);
+    app.state::<ViewerMode>().set_read_only(handle, false);
+    if handle != MAIN {
+        let (app, handle) = (app.clone(), handle.to_string());
+        thread::spawn(move || crate::stop_engine(&app, &handle));
+    }
+}
+
+/// Opens an existing project (picked when `project_path` is `None`) in a new window with its
+/// own engine and returns the window's handle; a project already open just gets focus.
+#[tauri::command(async)]
+pub fn open_project_window(project_path: Option<String>, app: AppHandle, viewer: State<ViewerMode>) -> Result<Option<String>, String> {
+    let folder = match project_path {
+        Some(p) => PathBuf::from(p),
+        None => match rfd::FileDialog::new().pick_folder() {
+            Some(folder) => folder,
+            None => return Ok(None),
+        },
+    };
+    let cfg = project::read(&folder)?;
+    let workspace = app.state::<Workspace>();
+    if let Some(handle) = workspace.handle_of(&cfg.project_path) {
+        if let Some(window) = app.get_webview_window(&handle) {
+            let _ = window.set_focus();
+        }
+        return Ok(Some(handle));
+    }
+    let handle = format!("project-{}", workspace.next.fetch_add(1, Ordering::SeqCst) + 1);
+    workspace.bind(&handle, &cfg.project_path);
+    crate::start_engine(&app, &app.state::<EngineState>(), &handle);
+    let name = folder.file_name().map_or(cfg.project_path.clone(), |n| n.to_string_lossy().to_string());
+    let built = tauri::WebviewWindowBuilder::new(&app, handle.as_str(), tauri::WebviewUrl::App("index.html".into()))
+        .title(format!("Wind Shadow Studio - {name}"))
+        .inner_size(1400.0, 900.0)
+        .build();
+    if let Err(e) = built {
+        closed(&app, &handle);
+        return Err(e.to_string());
+    }
+    crate::project_opened(&app, &viewer, &cfg, "open", &handle);
+    Ok(Some(handle))
+}
+
+/// The project a window was opened with, read from disk; `None` for a window without one.
+#[tauri::command]
+pub fn window_project(window: tauri::WebviewWindow, workspace: State<Workspace>) -> Result<Option<ProjectConfig>, String> {
+    workspace.project_of(window.label()).map(|path| project::read(&PathBuf::from(path))).transpose()
+}
+
+#[tauri::command]
+pub fn list_open_projects(workspace: State<Workspace>) -> Vec<OpenProject> {
+    workspace.open.lock().map(|open| open.iter().map(|(handle, project_path)| OpenProject { handle: handle.clone(), project_path: project_path.clone() }).collect()).unwrap_or_default()
+}
@@ -1,12 +1,20 @@
 import { useMemo, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import { messages } from './i18n/messages';
-import type { ProjectConfig, Turbine } from './types';
+import type { Action, BackupSettings, BenchmarkReport, EngineError, ExternalComparison, ExternalResult, Job, Measurement, MemoryPlan, MeteoYear, PrimedCache, Priority, ProfileBreakdown, ProjectConfig, RealisticCase, Receptor, ReceptorCumulative, ReceptorInterannual, RemoteEngine, ScenarioComparison, ScenarioProgress, SensitivityTable, Settings, SmtpSettings, Webhook, Turbine, TurbineGroup, UncertaintyTable } from './types';
 import { MapContainer, Marker, TileLayer, ImageOverlay } from 'react-leaflet';
 import 'leaflet/dist/leaflet.css';
 
+// Project events reach only the window holding the project, see `workspace.rs`.
+const appWindow = getCurrentWebviewWindow();
+const listen = appWindow.listen.bind(appWindow);
+
 const defaultTurbine = (): Turbine => ({ id: 'T1', x: 500100, y: 5000100, hub_height_m: 120, rotor_diameter_m: 140 });
 
+const defaultSmtp: SmtpSettings = { server: '', port: 587, security: 'starttls', username: '', password: '', from: '', to: [] };
+const describeError = (e: EngineError) => `${e.message} [${e.code}, ${e.stage}]`;
+
 const defaultCfg: ProjectConfig = {
   project_path: '',
   epsg: 'EPSG:32632',
@@ -14,27 +22,282 @@ const defaultCfg: ProjectConfig = {
   buffer_m: 2000,
   terrain_aware: false,
   dem_path: '',
+  dsm_path: '',
+  landcover_path: '',
+  // landcover_heights is left out so the shell fills in its CORINE defaults.
+  buildings_path: '',
   turbines: [defaultTurbine()],
+  external_groups: [],
+  receptors: [],
+  external_results: [],
+  realistic: null,
+  read_only: false,
+  scenarios: [],
+  site_boundary: null,
   output: { format: 'both' }
 };
 
+// Northern Italy monthly sunshine fractions, operating 90%, uniform wind rose.
+const defaultRealistic: RealisticCase = {
+  sunshine_probability: [0.35, 0.42, 0.48, 0.52, 0.56, 0.62, 0.68, 0.66, 0.58, 0.45, 0.33, 0.31],
+  operating_fraction: 0.9,
+  wind_direction_frequency: Array(12).fill(1)
+};
+
 export default function App() {
   const [lang, setLang] = useState<'it' | 'en'>('it');
   const t = messages[lang];
   const [cfg, setCfg] = useState<ProjectConfig>(defaultCfg);
   const [engineBase, setEngineBase] = useState('http://127.0.0.1:8000');
   const [job, setJob] = useState<any>(null);
+  const [current, setCurrent] = useState<Job | null>(null);
+  const [activeScenario, setActiveScenario] = useState<string | null>(null);
   const [logs, setLogs] = useState<string[]>([]);
+  const [startAt, setStartAt] = useState('');
+  const [priority, setPriority] = useState<Priority | ''>('');
+  const [primed, setPrimed] = useState<PrimedCache | null>(null);
+  const [settings, setSettings] = useState<Settings | null>(null);
+  const [viewer, setViewer] = useState(false);
+  const [portable, setPortable] = useState(false);
+  const [archivePassword, setArchivePassword] = useState('');
+  const [actions, setActions] = useState<Action[]>([]);
+  const [palette, setPalette] = useState('');
+  const [printers, setPrinters] = useState<{ name: string; default: boolean }[]>([]);
+  const [printer, setPrinter] = useState('');
+  const [printPages, setPrintPages] = useState('');
+  const [coordinate, setCoordinate] = useState('');
+  const [externalSource, setExternalSource] = useState('windPRO');
+  const [compareWith, setCompareWith] = useState('');
+  const [conflict, setConflict] = useState<{ path: string; modified: string | null } | null>(null);
 
   useMemo(async () => {
     const p = await invoke<number>('get_engine_port');
     setEngineBase(`http://127.0.0.1:${p}`);
   }, []);
 
+  useMemo(async () => {
+    setSettings(await invoke<Settings>('get_settings'));
+    const own = await invoke<ProjectConfig | null>('window_project');
+    if (own) setCfg(own);
+    const reopened = own ? null : await invoke<{ cfg: ProjectConfig; active_scenario: string | null } | null>('reopen_last_project');
+    if (reopened) {
+      setCfg(reopened.cfg);
+      setActiveScenario(reopened.active_scenario);
+    }
+    setViewer(await invoke<boolean>('get_viewer_mode'));
+    setPortable(await invoke<boolean>('get_portable_mode'));
+    const paused = (await invoke<Job[]>('list_jobs')).filter((j) => j.status === 'paused' && (!own || j.project === own.project_path)).pop();
+    if (paused) {
+      setCurrent(paused);
+      watch(paused.id);
+    }
+    await listen<{ path: string; what: string; available: number }>('disk://low', (e) => setLogs((l) => [...l, `${t.diskLow}: ${e.payload.path} (${(e.payload.available / 2 ** 30).toFixed(1)} GB)`]));
+    await listen<string>('notify://email_failed', (e) => setLogs((l) => [...l, `Email: ${e.payload}`]));
+    await listen<string>('backup://failed', (e) => setLogs((l) => [...l, `Backup: ${e.payload}`]));
+    await listen<string>('notify://webhook_failed', (e) => setLogs((l) => [...l, `Webhook: ${e.payload}`]));
+    await listen<PrimedCache>('cache://primed', (e) => setPrimed(e.payload));
+    await listen<{ job: number; error: EngineError }>('engine://error', (e) => setLogs((l) => [...l, `${t.engineError} #${e.payload.job}: ${describeError(e.payload.error)}`]));
+  }, []);
+
+  const smtp = settings?.smtp ?? defaultSmtp;
+  const setSmtp = (patch: Partial<SmtpSettings>) => settings && setSettings({ ...settings, smtp: { ...smtp, ...patch } });
+
+  const hook: Webhook = settings?.webhooks[0] ?? { url: '', events: [], template: '' };
+  const setHook = (patch: Partial<Webhook>) => settings && setSettings({ ...settings, webhooks: [{ ...hook, ...patch }, ...settings.webhooks.slice(1)] });
+
+  const backup: BackupSettings = settings?.backup ?? { path: '', interval_h: 4, keep: 10 };
+  const setBackup = (patch: Partial<BackupSettings>) => settings && setSettings({ ...settings, backup: { ...backup, ...patch } });
+
+  async function backupNow() {
+    try {
+      const res = await invoke<{ snapshot: string | null; pruned: number }>('backup_now', { projectPath: cfg.project_path });
+      setLogs([res.snapshot ? `${t.backupDone}: ${res.snapshot}` : t.backupUnchanged]);
+    } catch (e) {
+      setLogs([`Backup: ${e}`]);
+    }
+  }
+
+  const remote: RemoteEngine = settings?.remote_engine ?? { url: '', ca_path: '', pinned_sha256: [], token: '' };
+  const setRemote = (patch: Partial<RemoteEngine>) => settings && setSettings({ ...settings, remote_engine: { ...remote, ...patch } });
+
+  async function saveSettings() {
+    if (!settings) return;
+    await invoke('set_settings', { settings: { ...settings, smtp: smtp.server ? smtp : null, webhooks: settings.webhooks.filter((h) => h.url), backup: backup.path ? backup : null, remote_engine: remote.url ? remote : null } });
+    setLogs([t.settingsSaved]);
+  }
+
+  async function testEmail() {
+    try {
+      await invoke('send_test_email', { smtp });
+      setLogs([t.testEmailSent]);
+    } catch (e) {
+      setLogs([`Email: ${e}`]);
+    }
+  }
+
+  async function publishResults() {
+    const target = window.prompt(t.publishTarget, settings?.publish_targets[0]?.name ?? '');
+    if (!target) return;
+    try {
+      const res = await invoke<{ location: string; files: number }>('publish_results', { cfg, target });
+      setLogs([`${t.published}: ${res.files} → ${target}:${res.location}`]);
+    } catch (e) {
+      setLogs([`${t.publish}: ${e}`]);
+    }
+  }
+
+  async function exportArchive() {
+    const path = await invoke<string | null>('export_archive', { cfg, password: archivePassword || null });
+    if (path) setLogs([`${t.archiveExported}: ${path}${archivePassword ? ' (AES-256)' : ''}`]);
+  }
+
+  async function saveProject(resolution: 'overwrite' | 'reload' | 'save_as' | null = null) {
+    type Res =
+      | { status: 'saved'; path: string }
+      | { status: 'conflict'; path: string; modified: string | null }
+      | { status: 'reloaded' | 'saved_as'; cfg: ProjectConfig };
+    try {
+      const res = await invoke<Res | null>('save_project', { cfg, resolution });
+      if (!res) return;
+      if (res.status === 'conflict') {
+        setConflict(res);
+        return;
+      }
+      setConflict(null);
+      if (res.status === 'saved') setLogs([`${t.projectSaved}: ${res.path}`]);
+      else setCfg(res.cfg);
+    } catch (e) {
+      setLogs([`${t.saveProject}: ${e}`]);
+    }
+  }
+
+  async function addFromCoordinate(kind: 'turbine' | 'receptor') {
+    try {
+      const p = await invoke<{ x: number; y: number; format: string }>('parse_coordinate', { text: coordinate, epsg: cfg.epsg });
+      setCfg((s) => kind === 'turbine'
+        ? { ...s, turbines: [...s.turbines, { ...defaultTurbine(), id: `T${s.turbines.length + 1}`, x: p.x, y: p.y }] }
+        : { ...s, receptors: [...s.receptors, { id: `R${s.receptors.length + 1}`, x: p.x, y: p.y, height_m: 2 }] });
+      setLogs([`${p.format}: ${p.x.toFixed(1)} ${p.y.toFixed(1)}`]);
+      setCoordinate('');
+    } catch (e) {
+      setLogs([String(e)]);
+    }
+  }
+
+  async function loadPrinters() {
+    try {
+      const list = await invoke<{ name: string; default: boolean }[]>('list_printers');
+      setPrinters(list);
+      if (!printer) setPrinter(list.find((p) => p.default)?.name ?? '');
+    } catch (e) {
+      setLogs([`${t.print}: ${e}`]);
+    }
+  }
+
+  async function printReport() {
+    const target = current?.status === 'done' ? String(current.id) : cfg.project_path;
+    try {
+      setLogs([`${t.print}: ${await invoke<string>('print_report', { target, printer: printer || null, pages: printPages || null, copies: 1 })}`]);
+    } catch (e) {
+      setLogs([`${t.print}: ${e}`]);
+    }
+  }
+
+  async function captureMap() {
+    const rect = document.querySelector('.leaflet-container')?.getBoundingClientRect();
+    if (!rect) return;
+    try {
+      const path = await invoke<string>('capture_map', { viewport: { x: rect.x, y: rect.y, width: rect.width, height: rect.height }, projectPath: cfg.project_path });
+      setLogs([`${t.screenshotSaved}: ${path}`]);
+    } catch (e) {
+      setLogs([`${t.screenshot}: ${e}`]);
+    }
+  }
+
+  async function exportAuditLog() {
+    try {
+      const path = await invoke<string | null>('export_audit_log', { projectPath: cfg.project_path });
+      if (path) setLogs([`${t.auditLogExported}: ${path}`]);
+    } catch (e) {
+      setLogs([`${t.exportAuditLog}: ${e}`]);
+    }
+  }
+
+  async function importArchive(path: string | null = null) {
+    type Res = { status: 'password_required'; path: string } | { status: 'imported'; cfg: ProjectConfig; files: number };
+    try {
+      const res = await invoke<Res | null>('import_archive', { path, password: archivePassword || null });
+      if (!res) return;
+      if (res.status === 'password_required') {
+        const password = window.prompt(t.archivePassword);
+        if (password) {
+          const again = await invoke<Res | null>('import_archive', { path: res.path, password });
+          if (again?.status === 'imported') setCfg(again.cfg);
+        }
+      } else {
+        setCfg(res.cfg);
+      }
+      setViewer(await invoke<boolean>('get_viewer_mode'));
+    } catch (e) {
+      setLogs([`${t.importArchive}: ${e}`]);
+    }
+  }
+
+  async function chooseScenario(name: string | null) {
+    setActiveScenario(name);
+    await invoke('set_active_scenario', { projectPath: cfg.project_path, scenario: name });
+  }
+
+  async function refreshActions() {
+    setActions(await invoke<Action[]>('list_actions', { cfg: cfg.project_path ? cfg : null }));
+  }
+
+  async function invokeAction(title: string) {
+    const action = actions.find((a) => a.title === title && a.enabled);
+    if (!action) return;
+    setPalette('');
+    try {
+      const { result } = await invoke<{ action: string; result: any }>('invoke_action', { id: action.id, cfg: cfg.project_path ? cfg : null });
+      if (action.id.startsWith('project.') && action.id !== 'project.save') {
+        if (result) {
+          setCfg(result as ProjectConfig);
+          setActiveScenario(null);
+          setViewer(await invoke<boolean>('get_viewer_mode'));
+        }
+      } else if (action.id === 'project.save' && result?.status === 'conflict') setConflict(result);
+      else if (action.id === 'archive.import' && result?.status === 'imported') setCfg(result.cfg);
+      else if (action.id === 'run.calculate') watch(result as number);
+      else if (action.group === 'scenario') setActiveScenario(result.active_scenario);
+      else if (result != null) setLogs([`${action.title}: ${typeof result === 'string' ? result : JSON.stringify(result)}`]);
+    } catch (e) {
+      setLogs([`${action.title}: ${e}`]);
+    }
+  }
+
   async function chooseProject(mode: 'new' | 'open' | 'demo') {
     const data = await invoke<any>('choose_project', { mode });
     if (!data) return;
     setCfg(data as ProjectConfig);
+    setActiveScenario(null);
+    setViewer(await invoke<boolean>('get_viewer_mode'));
+  }
+
+  async function restartEngine() {
+    try {
+      const p = await invoke<number>('restart_engine');
+      setEngineBase(`http://127.0.0.1:${p}`);
+      setLogs([`${t.restartEngine}: ${p}`]);
+    } catch (e) {
+      setLogs([`${t.restartEngine}: ${e}`]);
+    }
+  }
+
+  async function openProjectWindow() {
+    try {
+      await invoke<string | null>('open_project_window', { projectPath: null });
+    } catch (e) {
+      setLogs([`${t.openInNewWindow}: ${e}`]);
+    }
   }
 
   async function chooseDem() {
@@ -42,62 +305,430 @@ export default function App() {
     if (p) setCfg((s) => ({ ...s, dem_path: p }));
   }
 
+  async function chooseDsm() {
+    const p = await invoke<string>('pick_dem');
+    if (p) setCfg((s) => ({ ...s, dsm_path: p }));
+  }
+
+  async function chooseLandcover() {
+    const p = await invoke<string>('pick_dem');
+    if (p) setCfg((s) => ({ ...s, landcover_path: p }));
+  }
+
+  async function rasterizeBuildings() {
+    const res = await invoke<{ path: string; buildings: number } | null>('rasterize_buildings', { cfg });
+    if (res) setCfg((s) => ({ ...s, buildings_path: res.path }));
+  }
+
+  async function deriveReceptorHeights() {
+    const res = await invoke<{ receptors: Receptor[]; report: { id: string; flag: string | null }[] }>('derive_receptor_heights', { cfg });
+    setCfg((s) => ({ ...s, receptors: res.receptors }));
+    setLogs(res.report.filter((r) => r.flag).map((r) => `${r.id}: ${r.flag}`));
+  }
+
   async function importCsv() {
     const rows = await invoke<Turbine[]>('import_csv_turbines');
     if (rows?.length) setCfg((s) => ({ ...s, turbines: rows.slice(0, 20) }));
   }
 
-  async function run() {
-    const res = await fetch(`${engineBase}/jobs/run`, { method: 'POST', headers: { 'Content-Type': 'application/json' }, body: JSON.stringify(cfg) });
-    const json = await res.json();
-    const id = json.id;
+  async function importExternalGroup() {
+    const name = window.prompt(t.externalGroupName);
+    if (!name) return;
+    const group = await invoke<TurbineGroup | null>('import_external_group', { name, status: 'existing' });
+    if (group) setCfg((s) => ({ ...s, external_groups: [...s.external_groups, group] }));
+  }
+
+  async function importWindpro() {
+    const res = await invoke<{ cfg: ProjectConfig; warnings: string[] } | null>('import_windpro', { cfg });
+    if (!res) return;
+    setCfg(res.cfg);
+    setLogs(res.warnings);
+  }
+
+  async function importGpx() {
+    try {
+      const res = await invoke<{ cfg: ProjectConfig; turbines: string[]; receptors: string[]; warnings: string[] } | null>('import_gpx', { cfg });
+      if (!res) return;
+      setCfg(res.cfg);
+      setLogs([`GPX: ${res.turbines.length} ${t.gpxTurbines}, ${res.receptors.length} ${t.gpxReceptors}`, ...res.warnings]);
+    } catch (e) {
+      setLogs([`GPX: ${e}`]);
+    }
+  }
+
+  async function importMeteoYears() {
+    try {
+      const years = await invoke<MeteoYear[] | null>('import_meteo_years');
+      if (!years) return;
+      setCfg((s) => ({ ...s, realistic: { ...(s.realistic ?? defaultRealistic), years } }));
+      setLogs([`${t.meteoYears}: ${years.map((y) => y.year).join(', ')}`]);
+    } catch (e) {
+      setLogs([`${t.meteoYears}: ${e}`]);
+    }
+  }
+
+  async function exportWindpro() {
+    await invoke<string[]>('export_windpro', { cfg });
+  }
+
+  async function importDxfZones() {
+    try {
+      const dxf = await invoke<{ path: string; units: string | null; layers: { name: string; closed: number; area_m2: number }[] } | null>('list_dxf_layers');
+      if (!dxf) return;
+      const closed = dxf.layers.filter((l) => l.closed > 0).sort((a, b) => b.area_m2 - a.area_m2);
+      const names = closed.map((l) => l.name).join(', ');
+      const boundaryLayer = window.prompt(`${t.dxfBoundaryLayer} (${names})`, closed[0]?.name ?? '');
+      if (boundaryLayer === null) return;
+      const exclusions = window.prompt(`${t.dxfExclusionLayers} (${names})`, '');
+      if (exclusions === null) return;
+      const units = dxf.units === null ? window.prompt(t.dxfUnits, 'm') : null;
+      const res = await invoke<{ cfg: ProjectConfig; boundary_area_m2: number; exclusion_count: number; files: string[]; warnings: string[] }>('import_dxf_zones', {
+        cfg,
+        path: dxf.path,
+        boundaryLayer: boundaryLayer || null,
+        exclusionLayers: exclusions.split(',').map((l) => l.trim()).filter(Boolean),
+        units
+      });
+      setCfg(res.cfg);
+      setLogs([`DXF: ${(res.boundary_area_m2 / 10000).toFixed(1)} ha, ${res.exclusion_count} ${t.dxfExclusions}`, ...res.files, ...res.warnings]);
+    } catch (e) {
+      setLogs([`DXF: ${e}`]);
+    }
+  }
+
+  async function exportSetbacks() {
+    const receptor = window.prompt(t.setbackReceptorM, '500');
+    if (receptor === null) return;
+    const boundary = cfg.site_boundary ? window.prompt(t.setbackBoundaryM, '') : '';
+    if (boundary === null) return;
+    const distances = { receptorM: receptor ? Number(receptor) : null, boundaryM: boundary ? Number(boundary) : null };
+    try {
+      const res = await invoke<{ violations: { turbine: string; from: string; distance_m: number; setback_m: number }[] }>('generate_setbacks', { cfg, ...distances });
+      const path = await invoke<string | null>('export_setbacks', { cfg, ...distances });
+      setLogs([
+        ...(path ? [path] : []),
+        ...res.violations.map((v) => `${v.turbine} – ${v.from}: ${v.distance_m.toFixed(0)} m < ${v.setback_m} m`)
+      ]);
+    } catch (e) {
+      setLogs([`${t.setbacks}: ${e}`]);
+    }
+  }
+
+  async function nearestDwellings() {
+    try {
+      const rows = await invoke<({ turbine: string; receptor: string } & Measurement)[]>('nearest_dwellings', { cfg });
+      setLogs(rows.map((r) => `${r.turbine} → ${r.receptor}: ${r.distance_m.toFixed(1)} m, ${r.bearing_deg.toFixed(1)}°`));
+    } catch (e) {
+      setLogs([`${t.nearestDwellings}: ${e}`]);
+    }
+  }
+
+  async function runBenchmark() {
+    setLogs([t.benchmarkRunning]);
+    try {
+      const res = await invoke<BenchmarkReport>('run_benchmark', { cfg: cfg.turbines.length ? cfg : null });
+      setLogs([
+        `${t.benchmark}: ${res.score} (${res.seconds.toFixed(1)} s, ${res.cores} cores${res.speedup ? `, x${res.speedup.toFixed(2)} with 2 runs` : ''})`,
+        `${t.suggestedWorkers}: ${res.suggested_workers} (${res.current_workers})`,
+        ...res.estimates.map((e) => `${e.cellsize_m} m: ~${Math.ceil(e.estimated_s / 60)} min`),
+        ...res.warnings
+      ]);
+    } catch (e) {
+      setLogs([`${t.benchmark}: ${e}`]);
+    }
+  }
+
+  async function profileBreakdown() {
+    try {
+      const res = await invoke<ProfileBreakdown>('profile_breakdown', { job: current?.id ?? null });
+      setLogs([
+        `${t.profileBreakdown}: ${res.total_s.toFixed(1)} s, peak ${res.peak_mb.toFixed(0)} MB`,
+        ...res.stages.map((s) => `${s.stage}: ${s.seconds.toFixed(1)} s (${s.share_pct.toFixed(0)}%), ${s.rss_mb.toFixed(0)} MB / peak ${s.peak_mb.toFixed(0)} MB`)
+      ]);
+    } catch (e) {
+      setLogs([`${t.profileBreakdown}: ${e}`]);
+    }
+  }
+
+  async function turbineReport() {
+    try {
+      type Impact = { receptor: string; direction: string; contribution_h: number | null; total_h: number | null } & Measurement;
+      const res = await invoke<{ turbines: { turbine: string; receptors: Impact[] }[]; warnings: string[] }>('turbine_receptor_report', { cfg });
+      const hours = (h: number | null) => (h === null ? '-' : h.toFixed(1));
+      setLogs([
+        ...res.turbines.flatMap((tb) =>
+          tb.receptors.map((r) => `${tb.turbine} → ${r.receptor}: ${r.distance_m.toFixed(0)} m ${r.direction}, ${hours(r.contribution_h)} / ${hours(r.total_h)} h`)
+        ),
+        ...res.warnings
+      ]);
+    } catch (e) {
+      setLogs([`${t.turbineReport}: ${e}`]);
+    }
+  }
+
+  async function exportDxf() {
+    try {
+      const res = await invoke<{ path: string; contour_lines: number; warnings: string[] } | null>('export_dxf', { cfg });
+      if (res) setLogs([`DXF: ${res.path} (${res.contour_lines} ${t.dxfContours})`, ...res.warnings]);
+    } catch (e) {
+      setLogs([`DXF: ${e}`]);
+    }
+  }
+
+  async function registerExternal() {
+    const res = await invoke<ExternalResult | null>('register_external_result', { cfg, source: externalSource.trim() || 'windPRO' });
+    if (res) setCfg((s) => ({ ...s, external_results: [...s.external_results, res] }));
+  }
+
+  async function compareExternal() {
+    try {
+      const c = await invoke<ExternalComparison>('compare_external_result', { cfg, name: compareWith });
+      const fmt = (h: number | null) => h?.toFixed(1) ?? '-';
+      setCfg((s) => ({ ...s, external_results: [...s.external_results.filter((r) => r.name !== c.difference.name), c.difference] }));
+      setLogs([
+        c.csv_path,
+        `${t.compareExternal} ${c.name} (${c.source}): |Δ| ${t.mean} ${fmt(c.mean_abs_difference_h)} h, max ${fmt(c.max_abs_difference_h)} h${c.csv_path ? ` → ${c.difference.path}` : ''}`,
+        ...c.rows.map((r) => `${r.id}: ${fmt(r.ours_h)} / ${fmt(r.theirs_h)} h (Δ ${fmt(r.difference_h)})`),
+      ]);
+    } catch (e) {
+      setLogs([`${t.compareExternal}: ${e}`]);
+    }
+  }
+
+  function watch(id: number) {
     const timer = setInterval(async () => {
-      const state = await (await fetch(`${engineBase}/jobs/${id}`)).json();
-      setJob(state);
-      setLogs(state.logs || []);
-      if (state.status === 'done' || state.status === 'error') clearInterval(timer);
+      const rec = await invoke<Job>('get_job', { id });
+      setCurrent(rec);
+      if (rec.engine) setJob(rec.engine);
+      const interannual: ReceptorInterannual[] = rec.engine?.interannual ?? [];
+      const spread = interannual.map((r) => `${r.receptor} ${t.interannual}: ${r.mean_h.toFixed(2)} ± ${r.std_h.toFixed(2)} h (${r.min_h.toFixed(2)}–${r.max_h.toFixed(2)})`);
+      const cumulative: ReceptorCumulative[] = rec.engine?.cumulative ?? [];
+      const shares = cumulative.map((r) => `${r.receptor} ${t.cumulative}: ${r.total_h.toFixed(2)} h = ${t.own} ${r.own_h.toFixed(2)} + ${r.groups.map((g) => `${g.name} ${g.hours_h.toFixed(2)}`).join(' + ')}`);
+      setLogs(rec.error ? [...(rec.engine?.logs || []), rec.error_detail ? describeError(rec.error_detail) : rec.error] : [...(rec.engine?.logs || []), ...spread, ...shares]);
+      if (['done', 'error', 'cancelled'].includes(rec.status)) clearInterval(timer);
     }, 900);
   }
 
+  async function run() {
+    const plan = await invoke<MemoryPlan>('estimate_run_memory', { cfg }).catch(() => null);
+    if (plan?.mode === 'refuse') {
+      setLogs([`${t.memoryRefused}: ${plan.tiled_required_mb.toFixed(0)} / ${plan.available_mb.toFixed(0)} MB`, ...Object.entries(plan.parts_mb).map(([k, v]) => `${k}: ${v.toFixed(0)} MB`)]);
+      return;
+    }
+    if (plan?.mode === 'tiled') setLogs([`${t.memoryTiled}: ${plan.required_mb.toFixed(0)} / ${plan.available_mb.toFixed(0)} MB`]);
+    watch(await invoke<number>('submit_run', { cfg, startAt: startAt || null, priority: priority || null }));
+  }
+
+  async function pauseOrResume() {
+    if (!current) return;
+    try {
+      await invoke<Job>(current.status === 'paused' ? 'resume_job' : 'pause_job', { id: current.id });
+    } catch (e) {
+      setLogs((l) => [...l, String(e)]);
+    }
+  }
+
+  async function runSensitivity() {
+    const batch = await invoke<string>('run_sensitivity', { cfg, sweep: { hub_height_offsets_m: [-10, 10], layout_shifts_m: [[50, 0], [0, 50]], thresholds_h: [30] }, startAt: startAt || null });
+    setLogs([`${t.sensitivity}: ${batch}`]);
+    const unlisten = await listen<SensitivityTable>('sensitivity://done', (e) => {
+      if (e.payload.batch !== batch) return;
+      unlisten();
+      setLogs([e.payload.csv_path, ...e.payload.rows.map((r) => `${r.id}: ${r.hours.map((h) => h?.toFixed(1) ?? '-').join(' / ')} (Δmax ${r.max_delta_h?.toFixed(1) ?? '-'} h)`)]);
+    });
+  }
+
+  async function runMonteCarlo() {
+    const uncertainty = { runs: 50, base: cfg.realistic ?? defaultRealistic, sunshine_sd: 0.05, operating_sd: 0.03, wind_sector_sd: 0.2, seed: null };
+    const batch = await invoke<string>('run_monte_carlo', { cfg, uncertainty, startAt: startAt || null });
+    setLogs([`${t.monteCarlo}: ${batch}`]);
+    const unlisten = await listen<UncertaintyTable>('montecarlo://done', (e) => {
+      if (e.payload.batch !== batch) return;
+      unlisten();
+      const fmt = (h: number | null) => h?.toFixed(1) ?? '-';
+      setLogs([e.payload.csv_path, ...e.payload.rows.map((r) => `${r.id}: P10 ${fmt(r.bands_h[0])} / P50 ${fmt(r.bands_h[1])} / P90 ${fmt(r.bands_h[2])} h`)]);
+    });
+  }
+
+  async function runScenarios() {
+    const batch = await invoke<string>('run_scenarios', { cfg, startAt: startAt || null });
+    const unlistenProgress = await listen<ScenarioProgress>('scenarios://progress', (e) => {
+      if (e.payload.batch !== batch) return;
+      setLogs([`${t.scenarios}: ${e.payload.finished}/${e.payload.total} (${e.payload.progress_pct.toFixed(0)}%)`, ...e.payload.scenarios.map((s) => `${s.name}: ${s.status} ${s.progress_pct.toFixed(0)}%`)]);
+    });
+    const unlisten = await listen<ScenarioComparison>('scenarios://done', (e) => {
+      if (e.payload.batch !== batch) return;
+      unlisten();
+      unlistenProgress();
+      const fmt = (h: number | null) => h?.toFixed(1) ?? '-';
+      setLogs([
+        e.payload.csv_path,
+        ...e.payload.scenarios.map((s) => `${s.name}: ${s.error ?? `max ${fmt(s.max_h)} h, > ${e.payload.limit_h} h: ${s.receptors_over_limit ?? '-'}`}`),
+        ...e.payload.rows.map((r) => `${r.id}: ${r.hours.map(fmt).join(' / ')} h`),
+      ]);
+    });
+  }
+
   const overlayUrl = job?.outputs?.preview_png ? `${engineBase}/jobs/${job.id}/files/preview_png` : '';
+  const hillshade = primed && primed.project_path === cfg.project_path && primed.outputs.hillshade_png && primed.overlay_bounds ? primed : null;
 
   return (
     <div className="app">
       <header>
         <h1>Wind Shadow Studio</h1>
+        {viewer && <span className="badge">{t.viewerMode}</span>}
+        {portable && <span className="badge">{t.portableMode}</span>}
+        <input list="actions" placeholder={t.commandPalette} value={palette} onFocus={refreshActions} onChange={(e) => setPalette(e.target.value)} onKeyDown={(e) => e.key === 'Enter' && invokeAction(palette)} />
+        <datalist id="actions">
+          {actions.filter((a) => a.enabled).map((a) => <option key={a.id} value={a.title}>{a.shortcut ?? ''}</option>)}
+        </datalist>
         <select value={lang} onChange={(e) => setLang(e.target.value as 'it' | 'en')}><option value="it">IT</option><option value="en">EN</option></select>
       </header>
       <div className="row">
-        <button onClick={() => chooseProject('new')}>{t.newProject}</button>
+        <button onClick={() => chooseProject('new')} disabled={viewer}>{t.newProject}</button>
         <button onClick={() => chooseProject('open')}>{t.openProject}</button>
         <button onClick={() => chooseProject('demo')}>{t.openDemo}</button>
+        <button onClick={openProjectWindow}>{t.openInNewWindow}</button>
+        <button onClick={restartEngine}>{t.restartEngine}</button>
+        <button onClick={() => saveProject()} disabled={viewer || !cfg.project_path}>{t.saveProject}</button>
+        <button onClick={() => importArchive()} disabled={viewer}>{t.importArchive}</button>
+        <button onClick={exportArchive} disabled={viewer || !cfg.project_path}>{t.exportArchive}</button>
+        <input type="password" placeholder={t.archivePassword} value={archivePassword} onChange={(e) => setArchivePassword(e.target.value)} />
+        <button onClick={exportAuditLog} disabled={!cfg.project_path}>{t.exportAuditLog}</button>
       </div>
+      {conflict && (
+        <div className="row conflict">
+          <span>{t.saveConflict} ({conflict.modified ?? conflict.path})</span>
+          <button onClick={() => saveProject('overwrite')}>{t.overwrite}</button>
+          <button onClick={() => saveProject('reload')}>{t.reload}</button>
+          <button onClick={() => saveProject('save_as')}>{t.saveAs}</button>
+        </div>
+      )}
       <section className="grid">
         <div>
           <label>EPSG <input value={cfg.epsg} onChange={(e) => setCfg({ ...cfg, epsg: e.target.value })} /></label>
           <label>Cellsize <input type="number" value={cfg.cellsize_m} onChange={(e) => setCfg({ ...cfg, cellsize_m: Number(e.target.value) })} /></label>
           <label>Buffer m <input type="number" value={cfg.buffer_m} onChange={(e) => setCfg({ ...cfg, buffer_m: Number(e.target.value) })} /></label>
           <label>DEM <input value={cfg.dem_path} readOnly /><button onClick={chooseDem}>Pick</button></label>
+          <label>DSM <input value={cfg.dsm_path} readOnly /><button onClick={chooseDsm}>Pick</button></label>
+          <label>{t.landcover} <input value={cfg.landcover_path} readOnly /><button onClick={chooseLandcover}>Pick</button></label>
+          <label>{t.buildings} <input value={cfg.buildings_path} readOnly /><button onClick={rasterizeBuildings} disabled={viewer}>GeoJSON</button></label>
           <label>Terrain-aware <input type="checkbox" checked={cfg.terrain_aware} onChange={(e) => setCfg({ ...cfg, terrain_aware: e.target.checked })} /></label>
           <label>Output
-            <select value={cfg.output.format} onChange={(e) => setCfg({ ...cfg, output: { format: e.target.value as any } })}>
+            <select value={cfg.output.format} onChange={(e) => setCfg({ ...cfg, output: { ...cfg.output, format: e.target.value as any } })}>
               <option value="both">both</option><option value="asc">asc</option><option value="geotiff">geotiff</option>
             </select>
           </label>
-          <button onClick={importCsv}>Import CSV (;)</button>
-          <button onClick={run}>{t.run}</button>
+          <label>{t.printMap}
+            <select value={cfg.output.page_size ?? 'A4'} onChange={(e) => setCfg({ ...cfg, output: { ...cfg.output, page_size: e.target.value as 'A4' | 'A3' } })}>
+              <option value="A4">A4</option><option value="A3">A3</option>
+            </select>
+            <input type="number" min={72} max={600} step={50} value={cfg.output.dpi ?? 150} onChange={(e) => setCfg({ ...cfg, output: { ...cfg.output, dpi: Number(e.target.value) } })} /> DPI
+          </label>
+          <label>{t.coordinate} <input value={coordinate} placeholder={'45°27\'51"N 9°11\'24"E, 32T 514925 5034860'} onChange={(e) => setCoordinate(e.target.value)} /></label>
+          <button onClick={() => addFromCoordinate('turbine')} disabled={viewer || !coordinate || cfg.turbines.length >= 20}>{t.addTurbine}</button>
+          <button onClick={() => addFromCoordinate('receptor')} disabled={viewer || !coordinate}>{t.addReceptor}</button>
+          <button onClick={importCsv} disabled={viewer}>Import CSV (;)</button>
+          <button onClick={importExternalGroup} disabled={viewer}>{t.importExternalGroup}</button>
+          <button onClick={deriveReceptorHeights} disabled={viewer || !cfg.dsm_path || !cfg.receptors.length}>{t.deriveReceptorHeights}</button>
+          <button onClick={importWindpro} disabled={viewer}>{t.importWindpro}</button>
+          <button onClick={importGpx} disabled={viewer}>{t.importGpx}</button>
+          <button onClick={importMeteoYears} disabled={viewer}>{t.importMeteoYears}</button>
+          <button onClick={exportWindpro} disabled={viewer}>{t.exportWindpro}</button>
+          <button onClick={exportDxf} disabled={viewer}>{t.exportDxf}</button>
+          <button onClick={importDxfZones} disabled={viewer}>{t.importDxfZones}</button>
+          <button onClick={exportSetbacks} disabled={viewer}>{t.setbacks}</button>
+          <button onClick={nearestDwellings} disabled={!cfg.receptors.length}>{t.nearestDwellings}</button>
+          <button onClick={turbineReport} disabled={!cfg.receptors.length}>{t.turbineReport}</button>
+          <button onClick={profileBreakdown}>{t.profileBreakdown}</button>
+          <button onClick={runBenchmark}>{t.benchmark}</button>
+          {cfg.site_boundary && (
+            <button onClick={() => setCfg((s) => ({ ...s, site_boundary: null }))} disabled={viewer}>{t.clearSiteBoundary}</button>
+          )}
+          <label>{t.externalSource} <input list="external-sources" value={externalSource} onChange={(e) => setExternalSource(e.target.value)} /></label>
+          <datalist id="external-sources">
+            {['windPRO', 'WindFarmer', 'openWind', 'WindPlanner'].map((s) => <option key={s} value={s} />)}
+          </datalist>
+          <button onClick={registerExternal} disabled={viewer}>{t.registerExternal}</button>
+          {cfg.external_results.length > 0 && (
+            <>
+              <select value={compareWith} onChange={(e) => setCompareWith(e.target.value)}>
+                <option value="">{t.compareExternal}…</option>
+                {cfg.external_results.map((r) => <option key={r.name} value={r.name}>{r.name} ({r.source})</option>)}
+              </select>
+              <button onClick={compareExternal} disabled={!compareWith}>{t.compareExternal}</button>
+            </>
+          )}
+          <button onClick={publishResults} disabled={viewer || !settings?.publish_targets.length}>{t.publish}</button>
+          <label>{t.startAt} <input value={startAt} placeholder="22:00" onChange={(e) => setStartAt(e.target.value)} /></label>
+          <select value={priority} onChange={(e) => setPriority(e.target.value as Priority | '')}>
+            <option value="">{t.priorityAuto}</option>
+            <option value="interactive">{t.priorityInteractive}</option>
+            <option value="export">{t.priorityExport}</option>
+            <option value="batch">{t.priorityBatch}</option>
+          </select>
+          <button onClick={run} disabled={viewer}>{t.run}</button>
+          <button onClick={pauseOrResume} disabled={viewer || !current || !['running', 'paused'].includes(current.status)}>{current?.status === 'paused' ? t.resume : t.pause}</button>
+          {current?.status === 'running' && current.eta_s != null && <span>{t.eta}: {Math.ceil(current.eta_s / 60)} min</span>}
+          <select value={printer} onFocus={loadPrinters} onChange={(e) => setPrinter(e.target.value)}>
+            <option value="">{t.defaultPrinter}</option>
+            {printers.map((p) => <option key={p.name} value={p.name}>{p.name}</option>)}
+          </select>
+          <input placeholder={t.printPages} value={printPages} onChange={(e) => setPrintPages(e.target.value)} />
+          <button onClick={printReport} disabled={!cfg.project_path}>{t.print}</button>
+          <button onClick={runSensitivity} disabled={viewer || !cfg.receptors.length}>{t.sensitivity}</button>
+          <button onClick={runScenarios} disabled={viewer || !cfg.scenarios.length}>{t.scenarios}</button>
+          {cfg.scenarios.length > 0 && (
+            <select value={activeScenario ?? ''} onChange={(e) => chooseScenario(e.target.value || null)}>
+              <option value="">{t.baseScenario}</option>
+              {cfg.scenarios.map((s) => <option key={s.name} value={s.name}>{s.name}</option>)}
+            </select>
+          )}
+          <button onClick={runMonteCarlo} disabled={viewer || !cfg.receptors.length}>{t.monteCarlo}</button>
         </div>
         <div>
           <MapContainer center={[45, 10]} zoom={7} style={{ height: 360 }}>
             <TileLayer attribution="&copy; OpenStreetMap contributors" url="https://{s}.tile.openstreetmap.org/{z}/{x}/{y}.png" />
+            {hillshade && <ImageOverlay bounds={hillshade.overlay_bounds!} url={`${engineBase}/jobs/${hillshade.engine_job}/files/hillshade_png`} opacity={0.4} />}
             {overlayUrl && job?.overlay_bounds && <ImageOverlay bounds={job.overlay_bounds} url={overlayUrl} opacity={0.6} />}
             {cfg.turbines.map((tb) => (
               <Marker key={tb.id} position={[45 + (tb.y % 1000) / 10000, 10 + (tb.x % 1000) / 10000]} />
             ))}
           </MapContainer>
+          <button onClick={captureMap} disabled={!cfg.project_path}>{t.screenshot}</button>
           <div className="legend">Legend min/max: {job?.stats?.min ?? '-'} / {job?.stats?.max ?? '-'}</div>
         </div>
       </section>
+      <section>
+        <h3>{t.emailNotifications}</h3>
+        <label>SMTP <input value={smtp.server} onChange={(e) => setSmtp({ server: e.target.value })} /></label>
+        <label>Port <input type="number" value={smtp.port} onChange={(e) => setSmtp({ port: Number(e.target.value) })} /></label>
+        <select value={smtp.security} onChange={(e) => setSmtp({ security: e.target.value as SmtpSettings['security'] })}>
+          <option value="starttls">STARTTLS</option><option value="tls">TLS</option><option value="none">none</option>
+        </select>
+        <label>User <input value={smtp.username} onChange={(e) => setSmtp({ username: e.target.value })} /></label>
+        <label>Password <input type="password" value={smtp.password} onChange={(e) => setSmtp({ password: e.target.value })} /></label>
+        <label>From <input value={smtp.from} onChange={(e) => setSmtp({ from: e.target.value })} /></label>
+        <label>To <input value={smtp.to.join(', ')} onChange={(e) => setSmtp({ to: e.target.value.split(',').map((x) => x.trim()).filter(Boolean) })} /></label>
+        <h3>Webhook</h3>
+        <label>URL <input value={hook.url} onChange={(e) => setHook({ url: e.target.value })} /></label>
+        <label>{t.webhookEvents} <input value={hook.events.join(', ')} placeholder="done, failed, batch_finished" onChange={(e) => setHook({ events: e.target.value.split(',').map((x) => x.trim()).filter(Boolean) })} /></label>
+        <label>{t.webhookTemplate} <textarea value={hook.template} placeholder={'{"text": "Run {{job.label}} {{event}}, max {{stats.max}} h"}'} onChange={(e) => setHook({ template: e.target.value })} /></label>
+        <h3>Backup</h3>
+        <label>{t.backupPath} <input value={backup.path} onChange={(e) => setBackup({ path: e.target.value })} /></label>
+        <label>{t.backupInterval} <input type="number" value={backup.interval_h} onChange={(e) => setBackup({ interval_h: Number(e.target.value) })} /></label>
+        <label>{t.backupKeep} <input type="number" value={backup.keep} onChange={(e) => setBackup({ keep: Number(e.target.value) })} /></label>
+        <button onClick={backupNow} disabled={viewer || !cfg.project_path || !settings?.backup}>{t.backupNow}</button>
+        <h3>{t.remoteEngine}</h3>
+        <label>URL <input value={remote.url} placeholder="https://engine.example:8443" onChange={(e) => setRemote({ url: e.target.value })} /></label>
+        <label>{t.remoteEngineCa} <input value={remote.ca_path} onChange={(e) => setRemote({ ca_path: e.target.value })} /></label>
+        <label>{t.remoteEnginePins} <input value={remote.pinned_sha256.join(', ')} placeholder="SHA-256" onChange={(e) => setRemote({ pinned_sha256: e.target.value.split(',').map((x) => x.trim()).filter(Boolean) })} /></label>
+        <label>{t.remoteEngineToken} <input type="password" value={remote.token} onChange={(e) => setRemote({ token: e.target.value })} /></label>
+        <label><input type="checkbox" checked={settings?.profile_runs ?? false} onChange={(e) => settings && setSettings({ ...settings, profile_runs: e.target.checked })} /> {t.profileRuns}</label>
+        <label><input type="checkbox" checked={settings?.reopen_last_project ?? false} onChange={(e) => settings && setSettings({ ...settings, reopen_last_project: e.target.checked })} /> {t.reopenLastProject}</label>
+        <button onClick={saveSettings} disabled={viewer}>{t.saveSettings}</button>
+        <button onClick={testEmail} disabled={viewer || !smtp.server}>{t.testEmail}</button>
+      </section>
       <section>
         <h3>Logs</h3>
         <pre>{logs.join('\n')}</pre>
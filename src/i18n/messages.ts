@@ -1,14 +1,214 @@
 export const messages = {
   it: {
     newProject: 'Nuovo progetto',
+    viewerMode: 'Sola lettura',
+    portableMode: 'Portatile',
+    printMap: 'Mappa di stampa',
+    coordinate: 'Coordinata',
+    importGpx: 'Importa GPX',
+    importMeteoYears: 'Importa anni meteo (CSV)',
+    openInNewWindow: 'Apri in una nuova finestra',
+    restartEngine: 'Riavvia motore',
+    meteoYears: 'Anni meteorologici',
+    interannual: 'media pluriennale ± dev. std (min–max)',
+    cumulative: 'cumulato',
+    own: 'parco',
+    gpxTurbines: 'turbine',
+    gpxReceptors: 'ricettori',
+    addTurbine: 'Aggiungi turbina',
+    addReceptor: 'Aggiungi ricettore',
+    screenshot: 'Cattura mappa',
+    screenshotSaved: 'Cattura salvata',
+    print: 'Stampa report',
+    defaultPrinter: 'Stampante predefinita',
+    printPages: 'Pagine (es. 1-3,5)',
+    landcover: 'Uso del suolo',
+    buildings: 'Edifici',
     openProject: 'Apri progetto',
     openDemo: 'Apri demo',
-    run: 'Esegui calcolo'
+    saveProject: 'Salva progetto',
+    projectSaved: 'Progetto salvato',
+    saveConflict: 'Il file progetto è stato modificato da un altro utente',
+    overwrite: 'Sovrascrivi',
+    reload: 'Ricarica',
+    saveAs: 'Salva con nome',
+    importArchive: 'Importa archivio',
+    exportArchive: 'Esporta archivio',
+    archivePassword: 'Password archivio (opzionale)',
+    archiveExported: 'Archivio esportato',
+    exportAuditLog: 'Esporta registro modifiche',
+    auditLogExported: 'Registro modifiche esportato',
+    run: 'Esegui calcolo',
+    pause: 'Pausa',
+    resume: 'Riprendi',
+    eta: 'Tempo stimato',
+    diskLow: 'Spazio su disco quasi esaurito',
+    importExternalGroup: 'Importa parco limitrofo',
+    externalGroupName: 'Nome del parco limitrofo',
+    deriveReceptorHeights: 'Altezze ricettori da DSM/DTM',
+    importWindpro: 'Importa windPRO',
+    exportWindpro: 'Esporta windPRO',
+    exportDxf: 'Esporta DXF',
+    dxfContours: 'isolinee',
+    importDxfZones: 'Importa confine DXF',
+    dxfBoundaryLayer: 'Layer del confine di sito',
+    dxfExclusionLayers: 'Layer delle zone di esclusione, separati da virgola',
+    dxfUnits: 'Unità del disegno (m, cm, mm, km, ft, us_ft)',
+    dxfExclusions: 'zone di esclusione',
+    clearSiteBoundary: 'Rimuovi confine di sito',
+    setbacks: 'Fasce di rispetto',
+    nearestDwellings: 'Distanza dal ricettore più vicino',
+    turbineReport: 'Ricettori per WTG',
+    setbackReceptorM: 'Distanza dai ricettori (m, vuoto per nessuna)',
+    setbackBoundaryM: 'Distanza dal confine di sito (m, vuoto per nessuna)',
+    registerExternal: 'Aggiungi risultato esterno',
+    externalSource: 'Fonte',
+    compareExternal: 'Confronta con i nostri risultati',
+    mean: 'media',
+    sensitivity: 'Analisi di sensitività',
+    monteCarlo: 'Incertezza Monte Carlo',
+    scenarios: 'Confronto scenari',
+    startAt: 'Avvio programmato (HH:MM)',
+    priorityAuto: 'Priorità automatica',
+    priorityInteractive: 'Interattiva',
+    priorityExport: 'Elaborati',
+    priorityBatch: 'Batch',
+    emailNotifications: 'Notifiche email',
+    saveSettings: 'Salva impostazioni',
+    settingsSaved: 'Impostazioni salvate',
+    testEmail: 'Email di prova',
+    testEmailSent: 'Email di prova inviata',
+    webhookEvents: 'Eventi (vuoto = tutti)',
+    webhookTemplate: 'Payload JSON',
+    publish: 'Pubblica risultati',
+    publishTarget: 'Destinazione di pubblicazione',
+    published: 'File pubblicati',
+    backupPath: 'Cartella di backup',
+    reopenLastProject: "Riapri l'ultimo progetto all'avvio",
+    remoteEngine: 'Motore remoto (HTTPS)',
+    engineError: 'Errore del motore, job',
+    profileRuns: 'Profila i calcoli (tempi e memoria per fase)',
+    profileBreakdown: 'Profilo del calcolo',
+    benchmark: 'Benchmark hardware',
+    memoryRefused: 'Memoria insufficiente per il calcolo (necessaria / disponibile)',
+    memoryTiled: 'Memoria limitata, raster di screening letti solo attorno al sito',
+    benchmarkRunning: 'Benchmark in corso…',
+    suggestedWorkers: 'Worker consigliati (attuali)',
+    remoteEngineCa: 'CA (file PEM)',
+    remoteEnginePins: 'Impronte certificato fissate',
+    remoteEngineToken: 'Token API (WSS_API_TOKEN, vuoto per tenere quello salvato)',
+    baseScenario: 'Progetto base',
+    commandPalette: 'Cerca un comando…',
+    backupInterval: 'Intervallo (ore)',
+    backupKeep: 'Copie da conservare',
+    backupNow: 'Backup ora',
+    backupDone: 'Backup completato',
+    backupUnchanged: 'Nessuna modifica dal backup precedente'
   },
   en: {
     newProject: 'New project',
+    viewerMode: 'Read-only',
+    portableMode: 'Portable',
+    printMap: 'Print map',
+    coordinate: 'Coordinate',
+    importGpx: 'Import GPX',
+    importMeteoYears: 'Import meteorological years (CSV)',
+    openInNewWindow: 'Open in a new window',
+    restartEngine: 'Restart engine',
+    meteoYears: 'Meteorological years',
+    interannual: 'long-term mean ± std dev (min–max)',
+    cumulative: 'cumulative',
+    own: 'own farm',
+    gpxTurbines: 'turbines',
+    gpxReceptors: 'receptors',
+    addTurbine: 'Add turbine',
+    addReceptor: 'Add receptor',
+    screenshot: 'Capture map',
+    screenshotSaved: 'Screenshot saved',
+    print: 'Print report',
+    defaultPrinter: 'Default printer',
+    printPages: 'Pages (e.g. 1-3,5)',
+    landcover: 'Land cover',
+    buildings: 'Buildings',
     openProject: 'Open project',
     openDemo: 'Open demo',
-    run: 'Run calculation'
+    saveProject: 'Save project',
+    projectSaved: 'Project saved',
+    saveConflict: 'The project file was changed by someone else',
+    overwrite: 'Overwrite',
+    reload: 'Reload',
+    saveAs: 'Save as',
+    importArchive: 'Import archive',
+    exportArchive: 'Export archive',
+    archivePassword: 'Archive password (optional)',
+    archiveExported: 'Archive exported',
+    exportAuditLog: 'Export audit log',
+    auditLogExported: 'Audit log exported',
+    run: 'Run calculation',
+    pause: 'Pause',
+    resume: 'Resume',
+    eta: 'ETA',
+    diskLow: 'Disk almost full',
+    importExternalGroup: 'Import neighbouring farm',
+    externalGroupName: 'Neighbouring farm name',
+    deriveReceptorHeights: 'Receptor heights from DSM/DTM',
+    importWindpro: 'Import windPRO',
+    exportWindpro: 'Export windPRO',
+    exportDxf: 'Export DXF',
+    dxfContours: 'contour lines',
+    importDxfZones: 'Import DXF boundary',
+    dxfBoundaryLayer: 'Site boundary layer',
+    dxfExclusionLayers: 'Exclusion zone layers, comma separated',
+    dxfUnits: 'Drawing units (m, cm, mm, km, ft, us_ft)',
+    dxfExclusions: 'exclusion zones',
+    clearSiteBoundary: 'Remove site boundary',
+    setbacks: 'Setback zones',
+    nearestDwellings: 'Distance to nearest dwelling',
+    turbineReport: 'Receptors per turbine',
+    setbackReceptorM: 'Distance from receptors (m, empty for none)',
+    setbackBoundaryM: 'Distance from the site boundary (m, empty for none)',
+    registerExternal: 'Add external result',
+    externalSource: 'Source',
+    compareExternal: 'Compare with our results',
+    mean: 'mean',
+    sensitivity: 'Sensitivity analysis',
+    monteCarlo: 'Monte Carlo uncertainty',
+    scenarios: 'Scenario comparison',
+    startAt: 'Scheduled start (HH:MM)',
+    priorityAuto: 'Automatic priority',
+    priorityInteractive: 'Interactive',
+    priorityExport: 'Export',
+    priorityBatch: 'Batch',
+    emailNotifications: 'Email notifications',
+    saveSettings: 'Save settings',
+    settingsSaved: 'Settings saved',
+    testEmail: 'Test email',
+    testEmailSent: 'Test email sent',
+    webhookEvents: 'Events (empty = all)',
+    webhookTemplate: 'JSON payload',
+    publish: 'Publish results',
+    publishTarget: 'Publish target',
+    published: 'Files published',
+    backupPath: 'Backup folder',
+    reopenLastProject: 'Reopen the last project at startup',
+    remoteEngine: 'Remote engine (HTTPS)',
+    engineError: 'Engine error, job',
+    profileRuns: 'Profile runs (time and memory per stage)',
+    profileBreakdown: 'Run profile',
+    benchmark: 'Hardware benchmark',
+    memoryRefused: 'Not enough memory for the run (needed / available)',
+    memoryTiled: 'Memory is tight, screening rasters are read around the site only',
+    benchmarkRunning: 'Running benchmark…',
+    suggestedWorkers: 'Suggested workers (current)',
+    remoteEngineCa: 'CA (PEM file)',
+    remoteEnginePins: 'Pinned certificate fingerprints',
+    remoteEngineToken: 'API token (WSS_API_TOKEN, empty keeps the saved one)',
+    baseScenario: 'Base project',
+    commandPalette: 'Search commands…',
+    backupInterval: 'Interval (hours)',
+    backupKeep: 'Copies to keep',
+    backupNow: 'Back up now',
+    backupDone: 'Backup completed',
+    backupUnchanged: 'No changes since the previous backup'
   }
 } as const;
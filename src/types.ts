@@ -6,6 +6,81 @@ export type Turbine = {
   rotor_diameter_m: number;
 };
 
+export type TurbineGroup = {
+  name: string;
+  status: 'existing' | 'consented';
+  turbines: Turbine[];
+};
+
+export type Receptor = {
+  id: string;
+  x: number;
+  y: number;
+  height_m: number;
+};
+
+export type GridHeader = {
+  ncols: number;
+  nrows: number;
+  xll: number;
+  yll: number;
+  cellsize: number;
+  nodata: number | null;
+  epsg: string | null;
+};
+
+export type ExternalResult = {
+  name: string;
+  source: string;
+  path: string;
+  grid: GridHeader;
+  min: number | null;
+  max: number | null;
+};
+
+/** Our results next to an external one; `difference` is ours minus theirs on our grid. */
+export type ExternalComparison = {
+  name: string;
+  source: string;
+  rows: { id: string; ours_h: number | null; theirs_h: number | null; difference_h: number | null }[];
+  mean_abs_difference_h: number | null;
+  max_abs_difference_h: number | null;
+  difference: ExternalResult;
+  // Null in viewer mode, where the comparison is not written.
+  csv_path: string | null;
+};
+
+export type MeteoYear = {
+  year: number;
+  sunshine_probability: number[];
+  operating_fraction: number;
+  wind_direction_frequency: number[];
+};
+
+export type RealisticCase = {
+  sunshine_probability: number[];
+  operating_fraction: number;
+  wind_direction_frequency: number[];
+  years?: MeteoYear[];
+};
+
+export type ReceptorInterannual = {
+  receptor: string;
+  years: Record<string, number>;
+  mean_h: number;
+  std_h: number;
+  min_h: number;
+  max_h: number;
+  cv: number;
+};
+
+export type ReceptorCumulative = {
+  receptor: string;
+  own_h: number;
+  groups: { name: string; status: string; hours_h: number }[];
+  total_h: number;
+};
+
 export type ProjectConfig = {
   project_path: string;
   epsg: string;
@@ -13,6 +88,206 @@ export type ProjectConfig = {
   buffer_m: number;
   terrain_aware: boolean;
   dem_path: string;
+  dsm_path: string;
+  landcover_path: string;
+  landcover_heights?: Record<string, number>;
+  buildings_path: string;
   turbines: Turbine[];
-  output: { format: 'asc' | 'geotiff' | 'both' };
+  external_groups: TurbineGroup[];
+  receptors: Receptor[];
+  external_results: ExternalResult[];
+  realistic: RealisticCase | null;
+  read_only: boolean;
+  scenarios: Scenario[];
+  /** GeoJSON Polygon or MultiPolygon in the project CRS. */
+  site_boundary: { type: 'Polygon' | 'MultiPolygon'; coordinates: unknown } | null;
+  /** `dpi` and `page_size` set the print map in `map.png` and the report; defaults 150 and A4. */
+  output: { format: 'asc' | 'geotiff' | 'both'; dpi?: number; page_size?: 'A4' | 'A3' };
+};
+
+/** Engine failure; `stage` is `request` for rejected calls and `shell` when the engine was not reached. */
+export type EngineError = {
+  code: string;
+  stage: string;
+  message: string;
+  details: any;
+};
+
+/** `cache://primed`: the engine job holding the project's hillshade, shown under the results. */
+export type PrimedCache = {
+  project_path: string;
+  engine_job: string;
+  outputs: Record<string, string>;
+  overlay_bounds: [[number, number], [number, number]] | null;
+};
+
+export type StageTiming = {
+  stage: string;
+  seconds: number;
+  rss_mb: number;
+  peak_mb: number;
+};
+
+export type ProfileBreakdown = {
+  job: number | null;
+  finished_at: string | null;
+  work: number | null;
+  total_s: number;
+  peak_mb: number;
+  stages: (StageTiming & { share_pct: number })[];
+};
+
+export type BenchmarkReport = {
+  score: number;
+  seconds: number;
+  pair_seconds: number | null;
+  speedup: number | null;
+  cores: number;
+  current_workers: number;
+  suggested_workers: number;
+  estimates: { cellsize_m: number; estimated_s: number }[];
+  suggested_cellsize_m: number | null;
+  warnings: string[];
+};
+
+/** Engine memory estimate before a run; `refuse` runs fail with `insufficient_memory`. */
+export type MemoryPlan = {
+  mode: 'full' | 'tiled' | 'refuse';
+  required_mb: number;
+  tiled_required_mb: number;
+  available_mb: number;
+  parts_mb: Record<string, number>;
+};
+
+export type Priority = 'interactive' | 'export' | 'batch';
+
+export type Job = {
+  id: number;
+  label: string;
+  batch: string | null;
+  status: 'queued' | 'running' | 'done' | 'error' | 'cancelled' | 'paused';
+  priority: Priority;
+  preempted: boolean;
+  start_at: string | null;
+  /** Output folder; a subfolder of `project` for batch variants. */
+  project_path: string;
+  /** Project the job was submitted from, whose window receives its events. */
+  project: string;
+  error: string | null;
+  error_detail: EngineError | null;
+  engine: any | null;
+  eta_s: number | null;
+};
+
+export type SensitivityTable = {
+  batch: string;
+  variants: string[];
+  thresholds_h: number[];
+  rows: { id: string; hours: (number | null)[]; max_delta_h: number | null }[];
+  exceedances: number[][];
+  csv_path: string;
+};
+
+export type UncertaintyTable = {
+  batch: string;
+  runs: number;
+  failed_runs: number;
+  percentiles: number[];
+  rows: { id: string; mean_h: number | null; bands_h: (number | null)[] }[];
+  csv_path: string;
+};
+
+export type Scenario = {
+  name: string;
+  /** JSON merge patch over the project config. */
+  overrides: Partial<ProjectConfig>;
+};
+
+export type Action = {
+  /** Parameterised actions carry the parameter after a colon, e.g. `scenario.activate:north`. */
+  id: string;
+  title: string;
+  group: 'project' | 'export' | 'run' | 'scenario';
+  shortcut: string | null;
+  enabled: boolean;
+};
+
+export type ScenarioProgress = {
+  batch: string;
+  finished: number;
+  total: number;
+  progress_pct: number;
+  scenarios: { name: string; job: number; status: Job['status']; progress_pct: number }[];
+};
+
+export type ScenarioComparison = {
+  batch: string;
+  limit_h: number;
+  scenarios: { name: string; status: Job['status']; error: string | null; turbines: number; max_h: number | null; mean_h: number | null; receptors_over_limit: number | null }[];
+  rows: { id: string; hours: (number | null)[] }[];
+  csv_path: string;
+};
+
+export type SmtpSettings = {
+  server: string;
+  port: number;
+  security: 'starttls' | 'tls' | 'none';
+  username: string;
+  password: string;
+  from: string;
+  to: string[];
+};
+
+export type Webhook = {
+  url: string;
+  events: string[];
+  template: string;
+};
+
+export type PublishTarget = {
+  name: string;
+  kind: 'http' | 'sftp' | 's3';
+  url: string;
+  token: string;
+  bucket: string;
+  region: string;
+  access_key: string;
+  secret_key: string;
+};
+
+export type BackupSettings = {
+  path: string;
+  interval_h: number;
+  keep: number;
+};
+
+/** Engine reached over HTTPS; pins are SHA-256 certificate fingerprints. */
+export type RemoteEngine = {
+  url: string;
+  ca_path: string;
+  pinned_sha256: string[];
+  /** Empty from `get_settings`; left empty, the saved token is kept while the URL is unchanged. */
+  token: string;
+};
+
+export type Settings = {
+  smtp: SmtpSettings | null;
+  webhooks: Webhook[];
+  publish_targets: PublishTarget[];
+  backup: BackupSettings | null;
+  reopen_last_project: boolean;
+  engine_workers: number;
+  long_run_min: number;
+  compliance_limit_h: number;
+  remote_engine: RemoteEngine | null;
+  profile_runs: boolean;
+};
+
+/** Geodesic (WGS84) distance and true bearings; grid figures are `null` in lon/lat projects. */
+export type Measurement = {
+  distance_m: number;
+  bearing_deg: number;
+  back_bearing_deg: number;
+  grid_distance_m: number | null;
+  grid_bearing_deg: number | null;
 };